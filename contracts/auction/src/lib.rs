@@ -77,7 +77,7 @@ pub struct Contract {
 
 #[ext_contract(ext_spaceship)]
 pub trait spaceship {
-    fn nft_payout(
+    fn auction_distribute(
         &mut self,
         receiver_id: AccountId,
         token_id: TokenId,
@@ -111,7 +111,7 @@ impl Contract {
         require!( predecessor_id == auction_info.buyer, "ERR_NOT_AUCTION_WINNER");
         require!(!auction_info.claimed, "ERR_AUCTION_ALREADY_CLAIMED");
 
-        ext_spaceship:: nft_payout( 
+        ext_spaceship:: auction_distribute( 
             auction_info.buyer.clone(),
             auction_info.token_id.clone(), 
             self.spaceship_id.clone(),
@@ -52,6 +52,22 @@ pub enum Event<'a> {
         caller_id: &'a AccountId,
         limit: u8,
     },
+    UBoxSaleClosed {
+        buyer_id: &'a AccountId,
+        amount: &'a U128,
+        num: u32,
+    },
+    OraclePriceUpdated {
+        asset_id: &'a AccountId,
+        price: &'a U128,
+        timestamp: u64,
+    },
+    OraclePriceRejected {
+        asset_id: &'a AccountId,
+        price: Option<&'a U128>,
+        timestamp: Option<u64>,
+        reason: &'a str,
+    },
 
     Bind{
         from: &'a AccountId,
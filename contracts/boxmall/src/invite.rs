@@ -85,12 +85,13 @@ impl Contract {
       Event::RewardToken{from:&from, to: &to, amount: &U128(amount)}.emit();
   }
 
-  pub fn reward_u(&mut self, from: AccountId, to: AccountId, amount: u128) {
+  // paid in the token the box was bought with
+  pub fn reward_u(&mut self, from: AccountId, to: AccountId, token_id: &AccountId, amount: u128) {
       ext_fungible_token::ft_transfer(
           to.clone(),
           U128(amount),
           None,
-          self.usn.clone(),
+          token_id.clone(),
           1,
           GAS_FOR_BATCH_TRANSFER
       );
@@ -4,11 +4,11 @@ use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LazyOption, UnorderedSet, LookupMap, UnorderedMap, Vector};
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{Base64VecU8, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     assert_one_yocto, env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault,
-    PromiseOrValue, Gas, ext_contract, Promise, log, Balance, serde_json
+    PromiseOrValue, Gas, ext_contract, Promise, PromiseResult, log, Balance, serde_json
 };
 use rand_distr::{Normal, Distribution};
 use rand::{Rng,SeedableRng};
@@ -19,9 +19,17 @@ mod owner;
 mod invite;
 mod utils;
 mod events;
+mod whitelist;
+mod oracle;
+mod pricing;
+mod payment;
 
 pub use crate::utils::*;
 pub use crate::events::*;
+pub use crate::whitelist::*;
+pub use crate::oracle::*;
+pub use crate::pricing::*;
+pub use crate::payment::*;
 
 pub type BoxType = u8;
 pub type PriceType = u128;
@@ -84,11 +92,18 @@ pub struct UBoxSale {
     end: TimeStampSec,
 }
 
+impl UBoxSale {
+    pub fn is_open(&self, now: TimeStampSec) -> bool {
+        self.start <= now && now < self.end && self.sale < self.total
+    }
+}
+
 /*
 // this struct is used to pass parameter for BuyU/BuyS
 {
   "type": "buy_u",
   "num": 10,
+  "proof": ["base64 sibling hash", ...] // only for rounds behind a merkle whitelist
 }
 */
 
@@ -96,7 +111,7 @@ pub struct UBoxSale {
 #[serde(crate = "near_sdk::serde")]
 #[serde(untagged)]
 enum TransferCallInfo {
-   BuyInfo{ box_type: String, num: u32 },
+   BuyInfo{ box_type: String, num: u32, #[serde(default)] proof: Option<Vec<Base64VecU8>> },
    ContractCallInfo{ contract_id: String, user_id: AccountId },
 }
 
@@ -169,6 +184,15 @@ pub(crate) enum StorageKey {
     Balances,
     // consolidate the function of invite contract
     UserRelation, 
+    RoundLimits,
+    RoundWhitelists,
+    RoundWhitelist { index: u64 },
+    LifetimeCaps,
+    RoundPurchases,
+    LifetimePurchases,
+    UBoxSalePricing,
+    PaymentTokens,
+    UBoxSalePriorities,
 }
 
 #[near_bindgen]
@@ -231,6 +255,30 @@ pub struct Contract {
     //ship_wallet_contract_id: AccountId,
     //usn: AccountId,
     user_relation: UnorderedMap<AccountId, Relation>,
+
+    // U-box round index => per-account cap and merkle whitelist root
+    round_limits: LookupMap<u64, RoundLimits>,
+    // U-box round index => accounts uploaded to its whitelist
+    round_whitelists: LookupMap<u64, UnorderedSet<AccountId>>,
+    // box type => per-account cap over the whole sale
+    lifetime_caps: LookupMap<BoxType, u32>,
+    // "index:account" => U-boxes bought in the round
+    round_purchases: LookupMap<String, u32>,
+    // "box_type:account" => boxes bought overall
+    lifetime_purchases: LookupMap<String, u32>,
+
+    // last TIA price accepted from the oracle
+    tia_twap_price: Option<TwapPrice>,
+    oracle_config: OracleConfig,
+
+    // U-box round index => pricing strategy, fixed when missing
+    ubox_sale_pricing: LookupMap<u64, UBoxPricing>,
+
+    // token contract => box type it pays for, price and sale statistics
+    payment_tokens: UnorderedMap<AccountId, PaymentToken>,
+
+    // U-box round index => priority, 0 when missing, kept out of UBoxSale so the stored rounds keep their layout
+    ubox_sale_priorities: LookupMap<u64, u32>,
 }
 
 #[near_bindgen]
@@ -244,7 +292,7 @@ impl Contract {
                 luck: AccountId 
                 ) -> Self {
         assert!(!env::state_exists(), "Already initialized");
-        Contract {
+        let mut this = Contract {
             owner_id,
 
             ubox_sale_pool: Vector::new(b"v".to_vec()),
@@ -292,7 +340,27 @@ impl Contract {
             balances: UnorderedMap::new( StorageKey::Balances),
             total_balance_shipwallet: 0,
             user_relation: UnorderedMap::new(StorageKey::UserRelation),
-        }
+
+            round_limits: LookupMap::new(StorageKey::RoundLimits),
+            round_whitelists: LookupMap::new(StorageKey::RoundWhitelists),
+            lifetime_caps: LookupMap::new(StorageKey::LifetimeCaps),
+            round_purchases: LookupMap::new(StorageKey::RoundPurchases),
+            lifetime_purchases: LookupMap::new(StorageKey::LifetimePurchases),
+
+            tia_twap_price: None,
+            oracle_config: OracleConfig{
+                max_price_age: DEFAULT_MAX_PRICE_AGE,
+                max_deviation_rate: DEFAULT_MAX_PRICE_DEVIATION_RATE,
+            },
+
+            ubox_sale_pricing: LookupMap::new(StorageKey::UBoxSalePricing),
+
+            payment_tokens: UnorderedMap::new(StorageKey::PaymentTokens),
+
+            ubox_sale_priorities: LookupMap::new(StorageKey::UBoxSalePriorities),
+        };
+        this.internal_add_base_payment_tokens();
+        this
     }
 }
 
@@ -307,22 +375,30 @@ impl FungibleTokenReceiver for Contract {
         msg: String,
     ) -> PromiseOrValue<U128> {
         let predecessor_id = env::predecessor_account_id();
-        require!( predecessor_id == self.usn || predecessor_id == self.token_tia , "Invalid contract Id");
 
         let mut refund: u128 = 0;
 
         let info: TransferCallInfo = serde_json::from_str::<TransferCallInfo>(&msg).expect("invalid msg");
 
         match info {
-            TransferCallInfo::BuyInfo{box_type, num} => {
-                if box_type == "buy_u".to_string() {
-                    refund = self.internal_buy_u(amount.clone(),sender_id.clone(), num );
+            TransferCallInfo::BuyInfo{box_type, num, proof} => {
+                let box_type = match box_type.as_str() {
+                    "buy_u" => TYPE_U,
+                    "buy_s" => TYPE_S,
+                    _ => env::panic_str("ERR_INVALID_BOX_TYPE"),
+                };
+                // only a token registered for this box type pays for it
+                require!( self.payment_tokens.get(&predecessor_id).filter(|token| token.box_type == box_type).is_some(), "Invalid contract Id");
+                if box_type == TYPE_U {
+                    refund = self.internal_buy_u(&predecessor_id, amount,sender_id.clone(), num, proof );
                 }
-                if box_type == "buy_s".to_string() {
-                    refund = self.internal_buy_s(amount.clone(),sender_id.clone(), num );
+                else {
+                    refund = self.internal_buy_s(&predecessor_id, amount,sender_id.clone(), num );
                 }
             },
             TransferCallInfo::ContractCallInfo{contract_id, user_id} => {
+                // pools credit ship wallets in TIA even if TIA stops paying for boxes
+                require!( predecessor_id == self.token_tia, "Invalid contract Id");
                 if  contract_id == "trialpool".to_string() ||
                     contract_id == "collectpool".to_string() ||
                     contract_id == "corepool".to_string() {
@@ -347,31 +423,42 @@ impl Contract {
     // msg->{"type": "buy_u","num": 10}
     //
     // BML-00-02
-    pub fn internal_buy_u(&mut self, amount: U128, buyer_id:AccountId, num: u32) -> u128 {
+    pub fn internal_buy_u(&mut self, token_id: &AccountId, amount: U128, buyer_id:AccountId, num: u32, proof: Option<Vec<Base64VecU8>>) -> u128 {
         
         let mut refund: u128 = 0;
         let actual_payamount: u128 = amount.0;
+
+        require!( self.buy_u_switch == true, "buy_u_swith is false");
+        require!( num > 0, "ERR_INVALID_NUM");
+        let token = self.internal_payment_token(token_id, TYPE_U);
+        // no round open, hand the whole payment back
+        let sale_index = match self.internal_active_ubox_sale(nano_to_sec(env::block_timestamp())) {
+            Some(sale_index) => sale_index,
+            None => {
+                Event::UBoxSaleClosed{buyer_id: &buyer_id, amount: &amount, num}.emit();
+                return actual_payamount;
+            }
+        };
        
         self.ubox_sale_num += num; // use temp variable
 
-        let sale_pool_len = self.ubox_sale_pool.len();
         // conditions check
         require!( self.ubox_sale_num <= self.ubox_sale_num_limit, "num: is over");
-        require!( sale_pool_len > 0, "ubox_sale_pool is empty");
-        require!( self.buy_u_switch == true, "buy_u_swith is false");
         // check ubox_switch
         // conditions check
 
-        let mut sale: UBoxSale = self.ubox_sale_pool.get(sale_pool_len-1).unwrap().clone();
+        let mut sale: UBoxSale = self.ubox_sale_pool.get(sale_index).unwrap().clone();
+        require!( sale.sale + num <= sale.total, "ERR_UBOX_SALE_SOLD_OUT");
+        self.internal_record_purchase(&buyer_id, TYPE_U, Some(sale_index), num, &proof);
+        // priced by the round's strategy before the boxes are counted as sold
+        let base_amount: u128 = self.internal_ubox_total_price(sale_index, &sale, num, nano_to_sec(env::block_timestamp()));
+        let mut totalamount: u128 = self.internal_payment_total(&token, base_amount, num).expect("ERR_NO_PAYMENT_RATE");
         sale.sale += num;
-        let u_box_price = sale.price;
-        
-        let mut totalamount: u128 = num as u128 *u_box_price;
         require!(totalamount <= actual_payamount, "payment less than actual amount");
         refund = actual_payamount - totalamount;
 
-        
-        self.ubox_sale_amount += totalamount;
+        // counted in USN whatever token paid
+        self.ubox_sale_amount += base_amount;
         
         // transfer amount. Done by user already!
         //IERC20(usn).safeTransferFrom(msg.sender, address(this), amount);
@@ -379,20 +466,21 @@ impl Contract {
         let invite_reward: u128 = totalamount * self.reward_rate.invite_reward_rate as u128 / RATE_DENOMINATOR as u128;
         let relation: Relation = self.get_info(buyer_id.clone());
         if relation.parent != "".to_string() {
-            self.reward_u(buyer_id.clone(), relation.parent.parse().unwrap(), invite_reward);
+            self.reward_u(buyer_id.clone(), relation.parent.parse().unwrap(), token_id, invite_reward);
             totalamount -= invite_reward;
         }
 
         // transfer amount to USN account
         //IERC20(usn).safeTransfer(bankU, amount);
         ext_fungible_token::ft_transfer(
-            self.bank_u.clone(),
+            self.internal_payment_bank(&token),
             U128(totalamount),
             None,
-            self.owner_id.clone(),
+            token_id.clone(),
             1,
             GAS_FOR_BATCH_TRANSFER
         );
+        self.internal_record_payment(token_id, token, num, actual_payamount - refund);
 
         // mint num boxes
         ext_magicbox::batch_mint(buyer_id.clone(),
@@ -402,7 +490,7 @@ impl Contract {
             1,
             GAS_FOR_BATCH_MINT_BOX
         );
-        self.ubox_sale_pool.replace(sale_pool_len-1, &sale);
+        self.ubox_sale_pool.replace(sale_index, &sale);
         Event::BuyU{caller_id: &env::predecessor_account_id(), buyer_id: &buyer_id, amount: &amount, num}.emit();
         refund
         
@@ -414,7 +502,8 @@ impl Contract {
     // msg->{"type": "buy_s","num": 10}
     //
     // BML-00-03
-    pub fn internal_buy_s(&mut self, amount: U128, buyer_id:AccountId, num: u32) -> u128 {
+    pub fn internal_buy_s(&mut self, token_id: &AccountId, amount: U128, buyer_id:AccountId, num: u32) -> u128 {
+        let token = self.internal_payment_token(token_id, TYPE_S);
         let mut refund: u128 = 0;
         let actual_payamount: u128 = amount.0;
         let ssp_price: u128 = self.get_box_ssp_price();
@@ -426,6 +515,7 @@ impl Contract {
         require!( self.buy_s_switch == true, "buy_s_swith is false");
         require!(totalamount <= actual_payamount + balance,"Error: totalamount more than (payment + balance)");
         // end conditions check
+        self.internal_record_purchase(&buyer_id, TYPE_S, None, num, &None);
 
         if totalamount <= balance {
             self.internal_ship_wallet_sub(buyer_id.clone(), totalamount);
@@ -473,7 +563,7 @@ impl Contract {
         receiver_ids.push(self.ship_pool.clone().to_string());
         amounts.push(U128(ship_reward));
   
-        receiver_ids.push(self.bank.clone().to_string());
+        receiver_ids.push(self.internal_payment_bank(&token).to_string());
         amounts.push(U128(bank_reward));
 
         // empty address
@@ -544,12 +634,31 @@ impl Contract {
         );
         //
 
+        self.internal_record_payment(token_id, token, num, actual_payamount - refund);
         Event::BuyS{caller_id: &env::predecessor_account_id(), buyer_id: &buyer_id, amount: &amount, num}.emit();
 
         refund
         
     }
     
+    // the open round with the highest priority takes the sales, the earliest added on a tie
+    pub fn internal_ubox_sale_priority(&self, index: u64) -> u32 {
+        self.ubox_sale_priorities.get(&index).unwrap_or(0)
+    }
+
+    // index of the round taking U-box sales at `now`
+    pub fn internal_active_ubox_sale(&self, now: TimeStampSec) -> Option<u64> {
+        let mut active: Option<(u64, u32)> = None;
+        for index in 0..self.ubox_sale_pool.len() {
+            let sale = self.ubox_sale_pool.get(index).unwrap();
+            let priority = self.internal_ubox_sale_priority(index);
+            if sale.is_open(now) && active.is_none_or(|(_, active_priority)| priority > active_priority) {
+                active = Some((index, priority));
+            }
+        }
+        active.map(|(index, _)| index)
+    }
+
     // shipwallet functions
     // BML-00-08
     pub fn internal_ship_wallet_add( &mut self, to: AccountId, amount: u128 ) {
@@ -0,0 +1,157 @@
+use crate::*;
+
+pub const GAS_FOR_GET_TWAP_PRICE: Gas = Gas(10 * TGAS);
+pub const GAS_FOR_RESOLVE_TWAP_PRICE: Gas = Gas(10 * TGAS);
+// an oracle price older than one hour is not used
+pub const DEFAULT_MAX_PRICE_AGE: TimeStampSec = 3600;
+// a new price more than 20% away from the last accepted one is rejected
+pub const DEFAULT_MAX_PRICE_DEVIATION_RATE: u8 = 20;
+
+// time weighted average price of an asset, `timestamp` in seconds
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct TwapPrice {
+    pub price: U128,
+    pub timestamp: TimeStampSec,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct OracleConfig {
+    // seconds a price stays usable after the oracle stamped it
+    pub max_price_age: TimeStampSec,
+    // max change in percent between two accepted prices
+    pub max_deviation_rate: u8,
+}
+
+#[ext_contract(ext_oracle)]
+pub trait Oracle {
+    fn get_twap_price(&self, asset_id: AccountId) -> TwapPrice;
+}
+
+#[ext_contract(ext_self)]
+pub trait BoxMallResolver {
+    fn on_tia_twap_price(&mut self) -> bool;
+    fn on_payment_token_rate(&mut self, token_id: AccountId) -> bool;
+}
+
+#[near_bindgen]
+impl Contract {
+    // pull the TIA price from the oracle, anyone can trigger a refresh
+    // BML-00-28
+    pub fn refresh_tia_twap_price(&mut self) -> Promise {
+        ext_oracle::get_twap_price(
+            self.token_tia.clone(),
+            self.oracle.clone(),
+            0,
+            GAS_FOR_GET_TWAP_PRICE
+        )
+        .then(ext_self::on_tia_twap_price(
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_TWAP_PRICE
+        ))
+    }
+
+    // cache the oracle answer if it is fresh and close enough to the last accepted price
+    #[private]
+    pub fn on_tia_twap_price(&mut self) -> bool {
+        let asset_id = self.token_tia.clone();
+        let last = self.tia_twap_price.as_ref().map(|twap| twap.price.0);
+        match self.internal_resolve_twap_price(&asset_id, last) {
+            Some(twap) => {
+                self.tia_twap_price = Some(twap);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // accept a TIA price the deviation bound keeps rejecting, e.g. after a real market move
+    // BML-00-33
+    #[payable]
+    pub fn force_tia_twap_price(&mut self, price: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(price.0 > 0, "ERR_INVALID_PRICE");
+        let asset_id = self.token_tia.clone();
+        self.tia_twap_price = Some(self.internal_force_twap_price(&asset_id, price));
+    }
+
+    #[payable]
+    pub fn set_oracle_config(&mut self, max_price_age: TimeStampSec, max_deviation_rate: u8) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(max_deviation_rate <= RATE_DENOMINATOR, "ERR_INVALID_DEVIATION_RATE");
+        self.oracle_config = OracleConfig{ max_price_age, max_deviation_rate };
+    }
+
+    pub fn get_oracle_config(&self) -> OracleConfig {
+        self.oracle_config
+    }
+
+    // the last accepted oracle price, stale or not
+    pub fn get_tia_twap_price(&self) -> Option<TwapPrice> {
+        self.tia_twap_price.clone()
+    }
+}
+
+impl Contract {
+    // get price by Oracle. getTWapPrice, None when no fresh price is cached
+    // BML-00-18
+    pub fn internal_get_tia_twap_price( &self ) -> Option<u128> {
+        self.internal_fresh_price(&self.tia_twap_price)
+    }
+
+    pub fn internal_fresh_price(&self, twap: &Option<TwapPrice>) -> Option<u128> {
+        let now = nano_to_sec(env::block_timestamp());
+        twap.as_ref()
+            .filter(|twap| now - twap.timestamp <= self.oracle_config.max_price_age)
+            .map(|twap| twap.price.0)
+    }
+
+    // the oracle answer of `asset_id` if it is fresh and close enough to `last`, the last accepted price
+    // however old, so a price can not drift past the bound by going stale first
+    pub fn internal_resolve_twap_price(&self, asset_id: &AccountId, last: Option<u128>) -> Option<TwapPrice> {
+        let twap = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => serde_json::from_slice::<TwapPrice>(&value).ok(),
+            PromiseResult::Failed => None,
+        };
+        let twap = match twap {
+            Some(twap) => twap,
+            None => {
+                Event::OraclePriceRejected{asset_id, price: None, timestamp: None, reason: "unavailable"}.emit();
+                return None;
+            }
+        };
+
+        let now = nano_to_sec(env::block_timestamp());
+        let reason = if twap.price.0 == 0 || twap.timestamp > now {
+            Some("invalid")
+        } else if now - twap.timestamp > self.oracle_config.max_price_age {
+            Some("stale")
+        } else if last.is_some_and(|last| {
+            twap.price.0.abs_diff(last) * RATE_DENOMINATOR as u128 > last * self.oracle_config.max_deviation_rate as u128
+        }) {
+            Some("deviation")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            Event::OraclePriceRejected{asset_id, price: Some(&twap.price), timestamp: Some(twap.timestamp), reason}.emit();
+            return None;
+        }
+
+        Event::OraclePriceUpdated{asset_id, price: &twap.price, timestamp: twap.timestamp}.emit();
+        Some(twap)
+    }
+    // an owner set price, stamped now
+    pub fn internal_force_twap_price(&self, asset_id: &AccountId, price: U128) -> TwapPrice {
+        let timestamp = nano_to_sec(env::block_timestamp());
+        Event::OraclePriceUpdated{asset_id, price: &price, timestamp}.emit();
+        TwapPrice { price, timestamp }
+    }
+}
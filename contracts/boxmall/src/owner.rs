@@ -1,5 +1,36 @@
 use crate::*;
 
+// the state before round limits, the oracle cache and payment tokens were added
+#[derive(BorshDeserialize)]
+pub struct ContractV0 {
+    owner_id: AccountId,
+    ubox_sale_pool: Vector<UBoxSale>,
+    magicbox: AccountId,
+    usn: AccountId,
+    token_tia: AccountId,
+    bank: AccountId,
+    bank_u: AccountId,
+    oracle: AccountId,
+    risker_pool: AccountId,
+    rank_pool: AccountId,
+    ship_pool: AccountId,
+    luck: AccountId,
+    sbox_price_info: SBoxPriceInfo,
+    reward_rate: RewardRate,
+    buy_u_switch: bool,
+    buy_s_switch: bool,
+    s_price_switch: bool,
+    num_limit: u8,
+    ubox_sale_num_limit: u32,
+    ubox_sale_num: u32,
+    ubox_sale_amount: u128,
+    sbox_sale_num: u32,
+    sbox_sale_amount: u128,
+    balances: UnorderedMap<AccountId, Balance>,
+    total_balance_shipwallet: Balance,
+    user_relation: UnorderedMap<AccountId, Relation>,
+}
+
 impl Contract {
     pub fn assert_owner(&self) {
         require!(
@@ -20,15 +51,61 @@ impl Contract {
 
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have, you need to implement migration from old state 
-    /// (keep the old struct with different name to deserialize it first).
-    /// After migration goes live, revert back to this implementation for next updates.
+    /// Migrates from ContractV0, the new limits start empty, USN and TIA stay the base payment tokens.
+    /// After migration goes live, revert back to the NOOP implementation for next updates.
     #[init(ignore_state)]
     #[private]
     pub fn migrate() -> Self {
-        let contract: Contract = env::state_read().expect("ERR_NOT_INIT");
-        contract
+        let old: ContractV0 = env::state_read().expect("ERR_NOT_INIT");
+        let mut this = Contract {
+            owner_id: old.owner_id,
+            ubox_sale_pool: old.ubox_sale_pool,
+            magicbox: old.magicbox,
+            usn: old.usn,
+            token_tia: old.token_tia,
+            bank: old.bank,
+            bank_u: old.bank_u,
+            oracle: old.oracle,
+            risker_pool: old.risker_pool,
+            rank_pool: old.rank_pool,
+            ship_pool: old.ship_pool,
+            luck: old.luck,
+            sbox_price_info: old.sbox_price_info,
+            reward_rate: old.reward_rate,
+            buy_u_switch: old.buy_u_switch,
+            buy_s_switch: old.buy_s_switch,
+            s_price_switch: old.s_price_switch,
+            num_limit: old.num_limit,
+            ubox_sale_num_limit: old.ubox_sale_num_limit,
+            ubox_sale_num: old.ubox_sale_num,
+            ubox_sale_amount: old.ubox_sale_amount,
+            sbox_sale_num: old.sbox_sale_num,
+            sbox_sale_amount: old.sbox_sale_amount,
+            balances: old.balances,
+            total_balance_shipwallet: old.total_balance_shipwallet,
+            user_relation: old.user_relation,
+
+            round_limits: LookupMap::new(StorageKey::RoundLimits),
+            round_whitelists: LookupMap::new(StorageKey::RoundWhitelists),
+            lifetime_caps: LookupMap::new(StorageKey::LifetimeCaps),
+            round_purchases: LookupMap::new(StorageKey::RoundPurchases),
+            lifetime_purchases: LookupMap::new(StorageKey::LifetimePurchases),
+
+            tia_twap_price: None,
+            oracle_config: OracleConfig{
+                max_price_age: DEFAULT_MAX_PRICE_AGE,
+                max_deviation_rate: DEFAULT_MAX_PRICE_DEVIATION_RATE,
+            },
+
+            ubox_sale_pricing: LookupMap::new(StorageKey::UBoxSalePricing),
+
+            payment_tokens: UnorderedMap::new(StorageKey::PaymentTokens),
+
+            ubox_sale_priorities: LookupMap::new(StorageKey::UBoxSalePriorities),
+        };
+        // the sales so far were all paid in USN and TIA
+        this.internal_add_base_payment_tokens();
+        this
     }
 
      /* ========== GOVERNANCE ========== */
@@ -135,7 +212,7 @@ impl Contract {
             price: price.0,
             sale: 0,
             start,
-            end
+            end,
             });
     }
     // setUBoxSale
@@ -148,6 +225,7 @@ impl Contract {
         require!(start > nano_to_sec(env::block_timestamp()), "ERR_INVALID_START_TIME");
 
         let mut ubox_sale = self.ubox_sale_pool.get(index).expect("Invalid index");
+        require!(total >= ubox_sale.sale, "ERR_INVALID_TOTAL");
         ubox_sale.start = start;
         ubox_sale.end = end;
         ubox_sale.total = total;
@@ -155,6 +233,15 @@ impl Contract {
         self.ubox_sale_pool.replace(index, &ubox_sale);
 
     } 
+    // setUBoxSalePriority, an open round with a higher priority takes over overlapping rounds
+    // BML-00-22
+    #[payable]
+    pub fn set_ubox_sale_priority(&mut self, index: u64, priority: u32) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(index < self.ubox_sale_pool.len(), "Invalid index");
+        self.ubox_sale_priorities.insert(&index, &priority);
+    }
 
 
     // setBank
@@ -0,0 +1,203 @@
+use crate::*;
+
+// what a payment token is charged for a box
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub enum PaymentPrice {
+    // the box's own price, USN for U-boxes and the TIA price for S-boxes
+    Base,
+    // a flat price per box in this token, the round's pricing strategy does not apply
+    Fixed { price: U128 },
+    // the base price converted with the token's oracle rate
+    Oracle,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PaymentToken {
+    pub box_type: BoxType,
+    pub price: PaymentPrice,
+    // receives the payment, the bank of the box type when None
+    pub bank: Option<AccountId>,
+    // last oracle rate accepted, base units worth 1e18 units of the token
+    pub rate: Option<TwapPrice>,
+    pub sale_num: u32,
+    pub sale_amount: u128,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct PaymentTokenInfo {
+    pub token_id: AccountId,
+    pub box_type: BoxType,
+    pub price: PaymentPrice,
+    pub bank: AccountId,
+    pub rate: Option<TwapPrice>,
+    pub sale_num: u32,
+    pub sale_amount: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    // accept `token_id` for a box type or update its settings, sale statistics are kept
+    // BML-00-30
+    #[payable]
+    pub fn add_payment_token(&mut self, token_id: AccountId, box_type: BoxType, price: PaymentPrice, bank: Option<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(box_type == TYPE_U || box_type == TYPE_S, "ERR_INVALID_BOX_TYPE");
+        // S-box payments are split into TIA rewards and burns
+        require!(
+            box_type == TYPE_U || (token_id == self.token_tia && matches!(price, PaymentPrice::Base)),
+            "ERR_SBOX_PAID_IN_TIA_ONLY"
+        );
+        if let PaymentPrice::Fixed { price } = &price {
+            require!(price.0 > 0, "ERR_INVALID_PRICE");
+        }
+        let mut token = self.payment_tokens.get(&token_id).unwrap_or(PaymentToken {
+            box_type,
+            price: PaymentPrice::Base,
+            bank: None,
+            rate: None,
+            sale_num: 0,
+            sale_amount: 0,
+        });
+        require!(token.box_type == box_type, "ERR_BOX_TYPE_MISMATCH");
+        token.price = price;
+        token.bank = bank;
+        self.payment_tokens.insert(&token_id, &token);
+    }
+
+    // BML-00-31
+    #[payable]
+    pub fn remove_payment_token(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.payment_tokens.remove(&token_id).expect("ERR_TOKEN_NOT_ACCEPTED");
+    }
+
+    // pull the rate of an oracle priced token, anyone can trigger a refresh
+    // BML-00-32
+    pub fn refresh_payment_token_rate(&mut self, token_id: AccountId) -> Promise {
+        let token = self.payment_tokens.get(&token_id).expect("ERR_TOKEN_NOT_ACCEPTED");
+        require!(matches!(token.price, PaymentPrice::Oracle), "ERR_NOT_ORACLE_PRICED");
+        ext_oracle::get_twap_price(
+            token_id.clone(),
+            self.oracle.clone(),
+            0,
+            GAS_FOR_GET_TWAP_PRICE
+        )
+        .then(ext_self::on_payment_token_rate(
+            token_id,
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_TWAP_PRICE
+        ))
+    }
+
+    #[private]
+    pub fn on_payment_token_rate(&mut self, token_id: AccountId) -> bool {
+        // the token may have been removed while the oracle answered
+        let mut token = match self.payment_tokens.get(&token_id) {
+            Some(token) => token,
+            None => return false,
+        };
+        let last = token.rate.as_ref().map(|twap| twap.price.0);
+        match self.internal_resolve_twap_price(&token_id, last) {
+            Some(twap) => {
+                token.rate = Some(twap);
+                self.payment_tokens.insert(&token_id, &token);
+                true
+            }
+            None => false,
+        }
+    }
+
+    // accept a rate the deviation bound keeps rejecting
+    // BML-00-34
+    #[payable]
+    pub fn force_payment_token_rate(&mut self, token_id: AccountId, rate: U128) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(rate.0 > 0, "ERR_INVALID_PRICE");
+        let mut token = self.payment_tokens.get(&token_id).expect("ERR_TOKEN_NOT_ACCEPTED");
+        require!(matches!(token.price, PaymentPrice::Oracle), "ERR_NOT_ORACLE_PRICED");
+        token.rate = Some(self.internal_force_twap_price(&token_id, rate));
+        self.payment_tokens.insert(&token_id, &token);
+    }
+
+    pub fn get_payment_token(&self, token_id: AccountId) -> Option<PaymentTokenInfo> {
+        self.payment_tokens.get(&token_id).map(|token| self.internal_payment_token_info(token_id, token))
+    }
+
+    pub fn get_payment_tokens(&self) -> Vec<PaymentTokenInfo> {
+        self.payment_tokens
+            .iter()
+            .map(|(token_id, token)| self.internal_payment_token_info(token_id, token))
+            .collect()
+    }
+}
+
+impl Contract {
+    // USN pays for U-boxes and TIA for S-boxes, both at the base price
+    pub fn internal_add_base_payment_tokens(&mut self) {
+        for (token_id, box_type) in [(self.usn.clone(), TYPE_U), (self.token_tia.clone(), TYPE_S)] {
+            self.payment_tokens.insert(&token_id, &PaymentToken{
+                box_type,
+                price: PaymentPrice::Base,
+                bank: None,
+                rate: None,
+                sale_num: 0,
+                sale_amount: 0,
+            });
+        }
+    }
+
+    pub fn internal_payment_token(&self, token_id: &AccountId, box_type: BoxType) -> PaymentToken {
+        self.payment_tokens
+            .get(token_id)
+            .filter(|token| token.box_type == box_type)
+            .expect("ERR_TOKEN_NOT_ACCEPTED")
+    }
+
+    // what `num` boxes worth `base_total` cost in `token`, None without a fresh oracle rate
+    pub fn internal_payment_total(&self, token: &PaymentToken, base_total: u128, num: u32) -> Option<u128> {
+        match &token.price {
+            PaymentPrice::Base => Some(base_total),
+            PaymentPrice::Fixed { price } => Some(num as u128 * price.0),
+            // rounded up so the conversion never undercharges
+            PaymentPrice::Oracle => self
+                .internal_fresh_price(&token.rate)
+                .map(|rate| (base_total * YOCTO18).div_ceil(rate)),
+        }
+    }
+
+    // where the payment of a box type goes
+    pub fn internal_payment_bank(&self, token: &PaymentToken) -> AccountId {
+        match &token.bank {
+            Some(bank) => bank.clone(),
+            None if token.box_type == TYPE_U => self.bank_u.clone(),
+            None => self.bank.clone(),
+        }
+    }
+
+    pub fn internal_record_payment(&mut self, token_id: &AccountId, mut token: PaymentToken, num: u32, amount: u128) {
+        token.sale_num += num;
+        token.sale_amount += amount;
+        self.payment_tokens.insert(token_id, &token);
+    }
+
+    fn internal_payment_token_info(&self, token_id: AccountId, token: PaymentToken) -> PaymentTokenInfo {
+        PaymentTokenInfo {
+            bank: self.internal_payment_bank(&token),
+            token_id,
+            box_type: token.box_type,
+            price: token.price,
+            rate: token.rate,
+            sale_num: token.sale_num,
+            sale_amount: U128(token.sale_amount),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::*;
+
+// how a U-box round prices its boxes, a round without a strategy sells at its fixed `price`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub enum UBoxPricing {
+    Fixed,
+    // falls linearly from `start_price` at the round start to `floor_price` after `duration` seconds
+    DutchAuction {
+        start_price: U128,
+        floor_price: U128,
+        duration: TimeStampSec,
+    },
+    // the n-th box of the round, counted from 0, costs `base_price + n * step`
+    BondingCurve {
+        base_price: U128,
+        step: U128,
+    },
+}
+
+#[near_bindgen]
+impl Contract {
+    // setUBoxSalePricing
+    // BML-00-29
+    #[payable]
+    pub fn set_ubox_sale_pricing(&mut self, index: u64, pricing: UBoxPricing) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(index < self.ubox_sale_pool.len(), "Invalid index");
+        match &pricing {
+            UBoxPricing::Fixed => {}
+            UBoxPricing::DutchAuction { start_price, floor_price, duration } => {
+                require!(start_price.0 >= floor_price.0, "ERR_INVALID_FLOOR_PRICE");
+                require!(*duration > 0, "ERR_INVALID_DURATION");
+            }
+            UBoxPricing::BondingCurve { .. } => {}
+        }
+        self.ubox_sale_pricing.insert(&index, &pricing);
+    }
+
+    pub fn get_ubox_sale_pricing(&self, index: u64) -> UBoxPricing {
+        self.ubox_sale_pricing.get(&index).unwrap_or(UBoxPricing::Fixed)
+    }
+
+    // what `num` U-boxes cost in the open round right now, in `token_id` or USN by default,
+    // None if no round can sell them or the token can not pay
+    pub fn quote_ubox(&self, num: u32, token_id: Option<AccountId>) -> Option<U128> {
+        let now = nano_to_sec(env::block_timestamp());
+        let index = self.internal_active_ubox_sale(now)?;
+        let sale = self.ubox_sale_pool.get(index).unwrap();
+        if num == 0 || sale.sale + num > sale.total {
+            return None;
+        }
+        let base_amount = self.internal_ubox_total_price(index, &sale, num, now);
+        match token_id {
+            Some(token_id) => self.payment_tokens
+                .get(&token_id)
+                .filter(|token| token.box_type == TYPE_U)
+                .and_then(|token| self.internal_payment_total(&token, base_amount, num))
+                .map(U128),
+            None => Some(U128(base_amount)),
+        }
+    }
+}
+
+impl Contract {
+    // exact price of the next `num` boxes of a round
+    pub fn internal_ubox_total_price(&self, index: u64, sale: &UBoxSale, num: u32, now: TimeStampSec) -> u128 {
+        let num = num as u128;
+        match self.ubox_sale_pricing.get(&index).unwrap_or(UBoxPricing::Fixed) {
+            UBoxPricing::Fixed => num * sale.price,
+            UBoxPricing::DutchAuction { start_price, floor_price, duration } => {
+                let elapsed = std::cmp::min(now.saturating_sub(sale.start), duration);
+                let drop = (start_price.0 - floor_price.0) * elapsed as u128 / duration as u128;
+                num * (start_price.0 - drop)
+            }
+            UBoxPricing::BondingCurve { base_price, step } => {
+                // sum of base + (sale + i) * step for i in 0..num
+                let sold = sale.sale as u128;
+                num * base_price.0 + step.0 * (num * sold + num * (num - 1) / 2)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+    use near_sdk::test_utils::VMContextBuilder;
+
+    use super::*;
+
+    fn setup() -> Contract {
+        testing_env!(VMContextBuilder::new().block_timestamp(0).build());
+        let mut contract = Contract::new(accounts(0), accounts(1), accounts(2), accounts(3),
+            accounts(4), accounts(5), accounts(0), accounts(1), accounts(2), accounts(3), accounts(4));
+        contract.ubox_sale_pool.push(&UBoxSale{ total: 100, price: 5, sale: 0, start: 1000, end: 3000 });
+        contract
+    }
+
+    #[test]
+    fn test_ubox_total_price() {
+        let mut contract = setup();
+        let mut sale = contract.ubox_sale_pool.get(0).unwrap();
+        assert_eq!(contract.internal_ubox_total_price(0, &sale, 3, 1000), 15);
+
+        contract.ubox_sale_pricing.insert(&0, &UBoxPricing::DutchAuction {
+            start_price: U128(100), floor_price: U128(20), duration: 800,
+        });
+        assert_eq!(contract.internal_ubox_total_price(0, &sale, 2, 1000), 200);
+        assert_eq!(contract.internal_ubox_total_price(0, &sale, 2, 1400), 120);
+        assert_eq!(contract.internal_ubox_total_price(0, &sale, 2, 2500), 40);
+
+        contract.ubox_sale_pricing.insert(&0, &UBoxPricing::BondingCurve { base_price: U128(10), step: U128(2) });
+        // boxes 0, 1, 2 cost 10, 12, 14
+        assert_eq!(contract.internal_ubox_total_price(0, &sale, 3, 1000), 36);
+        // boxes 3, 4 cost 16, 18
+        sale.sale = 3;
+        assert_eq!(contract.internal_ubox_total_price(0, &sale, 2, 1000), 34);
+    }
+}
@@ -29,6 +29,33 @@ pub struct Metadata {
     pub sbox_sale_num: u32,
     pub sbox_sale_amount: u128,
     pub total_balance_shipwallet: U128,
+    pub payment_tokens: Vec<PaymentTokenInfo>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct UBoxSaleRound {
+    pub index: u64,
+    pub total: u32,
+    pub price: U128,
+    pub sale: u32,
+    pub start: TimeStampSec,
+    pub end: TimeStampSec,
+    pub priority: u32,
+    pub pricing: UBoxPricing,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct UBoxSaleRounds {
+    // the round U-box sales currently go to
+    pub active: Option<UBoxSaleRound>,
+    // rounds not started yet, or open but behind the active round
+    pub upcoming: Vec<UBoxSaleRound>,
+    // rounds ended or sold out
+    pub finished: Vec<UBoxSaleRound>,
 }
 
 #[near_bindgen]
@@ -60,6 +87,7 @@ impl Contract {
             sbox_sale_num: self.sbox_sale_num,
             sbox_sale_amount: self.sbox_sale_amount,
             total_balance_shipwallet: U128(self.total_balance_shipwallet),
+            payment_tokens: self.get_payment_tokens(),
         }
     }
 
@@ -78,32 +106,49 @@ impl Contract {
         self.sbox_price_info.sbox_price
     }
 
-    // get price by Oracle. getTWapPrice
-    // BML-00-18
-    pub fn internal_get_tia_twap_price( &self ) -> u128 {
-        // to do 
-        //return IOracle(oracle).consult(ssp, 1e18);
-        1
-    }
-   
     // getLatestUBoxSalePool
     pub fn get_latest_u_box_sale_pool(&self) -> UBoxSale {
         return self.ubox_sale_pool.get(self.ubox_sale_pool.len()-1).unwrap().clone();
     }
     
+    // U-box sale rounds by state at the current block
+    pub fn get_ubox_sale_rounds(&self) -> UBoxSaleRounds {
+        let now = nano_to_sec(env::block_timestamp());
+        let active_index = self.internal_active_ubox_sale(now);
+        let mut rounds = UBoxSaleRounds { active: None, upcoming: vec![], finished: vec![] };
+        for (index, sale) in self.ubox_sale_pool.iter().enumerate() {
+            let round = UBoxSaleRound {
+                index: index as u64,
+                total: sale.total,
+                price: U128(sale.price),
+                sale: sale.sale,
+                start: sale.start,
+                end: sale.end,
+                priority: self.internal_ubox_sale_priority(index as u64),
+                pricing: self.get_ubox_sale_pricing(index as u64),
+            };
+            if Some(index as u64) == active_index {
+                rounds.active = Some(round);
+            } else if sale.end <= now || sale.sale >= sale.total {
+                rounds.finished.push(round);
+            } else {
+                rounds.upcoming.push(round);
+            }
+        }
+        rounds
+    }
+
     // getBoxSSPPrice
     pub fn get_box_ssp_price( &self ) -> u128 {
         // ssp's decimals is 18
-        let mut twapPrice: u128 = 0;
-        
-        if self.s_price_switch || self.sbox_price_info.ssp_twap_price_min < self.internal_get_tia_twap_price(){
-            twapPrice = self.sbox_price_info.ssp_twap_price_min;
-        }
-        else{
-            twapPrice = self.internal_get_tia_twap_price();
-        }
+        // as before the oracle, TIA is valued at most at ssp_twap_price_min, which is also used
+        // when the switch is on or no fresh oracle price is cached
+        let twap_price: u128 = match self.internal_get_tia_twap_price() {
+            Some(price) if !self.s_price_switch && price < self.sbox_price_info.ssp_twap_price_min => price,
+            _ => self.sbox_price_info.ssp_twap_price_min,
+        };
 
-        self.sbox_price_info.sbox_price*(YOCTO18/twapPrice)
+        self.sbox_price_info.sbox_price * YOCTO18 / twap_price
     }
     // get sprice swith
     pub fn get_s_price_switch(&self) -> bool {
@@ -0,0 +1,254 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+// domain separation of the whitelist merkle tree
+pub const MERKLE_LEAF_PREFIX: u8 = 0x00;
+pub const MERKLE_NODE_PREFIX: u8 = 0x01;
+
+// per-round limits of a U-box sale
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct RoundLimits {
+    // max boxes one account buys in the round, 0 for no cap
+    pub account_cap: u32,
+    // only uploaded or merkle-proven accounts buy in the round, the uploaded list may run empty
+    pub whitelisted: bool,
+    // sha256 merkle root of the allowed accounts, leaves are sha256(0x00 || account_id)
+    pub whitelist_root: Option<Base64VecU8>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct PurchaseAllowance {
+    // the round U-box sales currently go to, None when no round is open
+    pub ubox_round: Option<u64>,
+    // U-boxes the account can still buy in that round
+    pub ubox: u32,
+    // S-boxes the account can still buy, None without a cap, 0 while S-box sales are off
+    pub sbox: Option<u32>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // per-account cap of a U-box round, 0 removes it
+    // BML-00-23
+    #[payable]
+    pub fn set_round_account_cap(&mut self, index: u64, account_cap: u32) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(index < self.ubox_sale_pool.len(), "Invalid index");
+        let mut limits = self.round_limits.get(&index).unwrap_or_default();
+        limits.account_cap = account_cap;
+        self.round_limits.insert(&index, &limits);
+    }
+
+    // per-account cap of a box type over the whole sale, 0 removes it
+    // BML-00-24
+    #[payable]
+    pub fn set_lifetime_account_cap(&mut self, box_type: BoxType, account_cap: u32) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(box_type == TYPE_U || box_type == TYPE_S, "ERR_INVALID_BOX_TYPE");
+        self.lifetime_caps.insert(&box_type, &account_cap);
+    }
+
+    // open a gated U-box round to everyone, or gate it again
+    // BML-00-35
+    #[payable]
+    pub fn set_round_whitelisted(&mut self, index: u64, whitelisted: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(index < self.ubox_sale_pool.len(), "Invalid index");
+        let mut limits = self.round_limits.get(&index).unwrap_or_default();
+        limits.whitelisted = whitelisted;
+        self.round_limits.insert(&index, &limits);
+    }
+
+    // restrict a U-box round to accounts proven against `root`, None lifts the merkle check
+    // BML-00-25
+    #[payable]
+    pub fn set_round_whitelist_root(&mut self, index: u64, root: Option<Base64VecU8>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(index < self.ubox_sale_pool.len(), "Invalid index");
+        require!(root.as_ref().is_none_or(|root| root.0.len() == 32), "ERR_INVALID_MERKLE_ROOT");
+        let mut limits = self.round_limits.get(&index).unwrap_or_default();
+        limits.whitelisted |= root.is_some();
+        limits.whitelist_root = root;
+        self.round_limits.insert(&index, &limits);
+    }
+
+    // upload a batch of accounts allowed to buy in a U-box round, it gates the round
+    // BML-00-26
+    #[payable]
+    pub fn add_round_whitelist(&mut self, index: u64, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(index < self.ubox_sale_pool.len(), "Invalid index");
+        let mut whitelist = self.round_whitelists.get(&index).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::RoundWhitelist { index })
+        });
+        for account_id in account_ids.iter() {
+            whitelist.insert(account_id);
+        }
+        self.round_whitelists.insert(&index, &whitelist);
+        let mut limits = self.round_limits.get(&index).unwrap_or_default();
+        limits.whitelisted = true;
+        self.round_limits.insert(&index, &limits);
+    }
+
+    // the round stays gated when its last uploaded account is removed
+    // BML-00-27
+    #[payable]
+    pub fn remove_round_whitelist(&mut self, index: u64, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(mut whitelist) = self.round_whitelists.get(&index) {
+            for account_id in account_ids.iter() {
+                whitelist.remove(account_id);
+            }
+            self.round_whitelists.insert(&index, &whitelist);
+        }
+    }
+
+    pub fn get_round_limits(&self, index: u64) -> RoundLimits {
+        self.round_limits.get(&index).unwrap_or_default()
+    }
+
+    // what `account_id` can still buy, `proof` is needed for rounds behind a merkle root
+    pub fn get_purchase_allowance(&self, account_id: AccountId, proof: Option<Vec<Base64VecU8>>) -> PurchaseAllowance {
+        let ubox_round = self.internal_active_ubox_sale(nano_to_sec(env::block_timestamp()));
+        let ubox = match ubox_round {
+            Some(index) if self.buy_u_switch && self.internal_is_whitelisted(index, &account_id, &proof) => {
+                let sale = self.ubox_sale_pool.get(index).unwrap();
+                let mut ubox = std::cmp::min(
+                    sale.total - sale.sale,
+                    self.ubox_sale_num_limit.saturating_sub(self.ubox_sale_num),
+                );
+                if let Some(left) = self.internal_round_cap_left(index, &account_id) {
+                    ubox = std::cmp::min(ubox, left);
+                }
+                if let Some(left) = self.internal_lifetime_cap_left(TYPE_U, &account_id) {
+                    ubox = std::cmp::min(ubox, left);
+                }
+                ubox
+            }
+            _ => 0,
+        };
+        PurchaseAllowance {
+            ubox_round,
+            ubox,
+            sbox: if self.buy_s_switch { self.internal_lifetime_cap_left(TYPE_S, &account_id) } else { Some(0) },
+        }
+    }
+}
+
+impl Contract {
+    // check the whitelist and caps of a purchase and count it
+    pub fn internal_record_purchase(&mut self, buyer_id: &AccountId, box_type: BoxType, round: Option<u64>, num: u32, proof: &Option<Vec<Base64VecU8>>) {
+        if let Some(index) = round {
+            require!(self.internal_is_whitelisted(index, buyer_id, proof), "ERR_NOT_WHITELISTED");
+            require!(
+                self.internal_round_cap_left(index, buyer_id).is_none_or(|left| num <= left),
+                "ERR_ROUND_CAP_EXCEEDED"
+            );
+            let key = format!("{}:{}", index, buyer_id);
+            let bought = self.round_purchases.get(&key).unwrap_or(0);
+            self.round_purchases.insert(&key, &(bought + num));
+        }
+        require!(
+            self.internal_lifetime_cap_left(box_type, buyer_id).is_none_or(|left| num <= left),
+            "ERR_LIFETIME_CAP_EXCEEDED"
+        );
+        let key = format!("{}:{}", box_type, buyer_id);
+        let bought = self.lifetime_purchases.get(&key).unwrap_or(0);
+        self.lifetime_purchases.insert(&key, &(bought + num));
+    }
+
+    // a round that is not gated is open to everyone
+    pub fn internal_is_whitelisted(&self, index: u64, account_id: &AccountId, proof: &Option<Vec<Base64VecU8>>) -> bool {
+        let limits = self.round_limits.get(&index).unwrap_or_default();
+        if !limits.whitelisted {
+            return true;
+        }
+        if self.round_whitelists.get(&index).is_some_and(|whitelist| whitelist.contains(account_id)) {
+            return true;
+        }
+        match (limits.whitelist_root, proof) {
+            (Some(root), Some(proof)) => verify_merkle_proof(&root.0, account_id, proof),
+            _ => false,
+        }
+    }
+
+    pub fn internal_round_cap_left(&self, index: u64, account_id: &AccountId) -> Option<u32> {
+        let account_cap = self.round_limits.get(&index).map_or(0, |limits| limits.account_cap);
+        if account_cap == 0 {
+            return None;
+        }
+        let bought = self.round_purchases.get(&format!("{}:{}", index, account_id)).unwrap_or(0);
+        Some(account_cap.saturating_sub(bought))
+    }
+
+    pub fn internal_lifetime_cap_left(&self, box_type: BoxType, account_id: &AccountId) -> Option<u32> {
+        let account_cap = self.lifetime_caps.get(&box_type).unwrap_or(0);
+        if account_cap == 0 {
+            return None;
+        }
+        let bought = self.lifetime_purchases.get(&format!("{}:{}", box_type, account_id)).unwrap_or(0);
+        Some(account_cap.saturating_sub(bought))
+    }
+}
+
+// leaves and inner nodes are hashed under different prefixes so a node can not pass as a leaf,
+// pairs are hashed in sorted order, so a proof is just the sibling hashes from leaf to root
+pub fn verify_merkle_proof(root: &[u8], account_id: &AccountId, proof: &[Base64VecU8]) -> bool {
+    let mut hash = env::sha256(&[&[MERKLE_LEAF_PREFIX], account_id.as_bytes()].concat());
+    for sibling in proof.iter() {
+        let mut pair = vec![MERKLE_NODE_PREFIX];
+        if hash <= sibling.0 {
+            pair.extend_from_slice(&hash);
+            pair.extend_from_slice(&sibling.0);
+        } else {
+            pair.extend_from_slice(&sibling.0);
+            pair.extend_from_slice(&hash);
+        }
+        hash = env::sha256(&pair);
+    }
+    hash == root
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::accounts;
+    use near_sdk::testing_env;
+    use near_sdk::test_utils::VMContextBuilder;
+
+    use super::*;
+
+    fn hash_leaf(account_id: &AccountId) -> Vec<u8> {
+        env::sha256(&[&[MERKLE_LEAF_PREFIX], account_id.as_bytes()].concat())
+    }
+
+    fn hash_pair(a: &[u8], b: &[u8]) -> Vec<u8> {
+        if a <= b { env::sha256(&[&[MERKLE_NODE_PREFIX], a, b].concat()) } else { env::sha256(&[&[MERKLE_NODE_PREFIX], b, a].concat()) }
+    }
+
+    #[test]
+    fn test_verify_merkle_proof() {
+        testing_env!(VMContextBuilder::new().build());
+        let leaves: Vec<Vec<u8>> = (0..3).map(|i| hash_leaf(&accounts(i))).collect();
+        let node = hash_pair(&leaves[0], &leaves[1]);
+        let root = hash_pair(&node, &leaves[2]);
+
+        let proof = vec![Base64VecU8(leaves[1].clone()), Base64VecU8(leaves[2].clone())];
+        assert!(verify_merkle_proof(&root, &accounts(0), &proof));
+        assert!(!verify_merkle_proof(&root, &accounts(3), &proof));
+        assert!(verify_merkle_proof(&root, &accounts(2), &[Base64VecU8(node.clone())]));
+        // unprefixed leaves do not verify
+        let bare: Vec<Vec<u8>> = (0..2).map(|i| env::sha256(accounts(i).as_bytes())).collect();
+        let bare_root = hash_pair(&bare[0], &bare[1]);
+        assert!(!verify_merkle_proof(&bare_root, &accounts(0), &[Base64VecU8(bare[1].clone())]));
+    }
+}
@@ -9,11 +9,13 @@ use near_sdk_sim::{
 use near_contract_standards::non_fungible_token::Token;
 
 use boxmall::ContractContract as boxmall;
+use boxmall::{PaymentPrice, RoundLimits, UBoxPricing};
 use mock_usn::ContractContract as mock_usn;
 use magicbox::ContractContract as magicbox;
 use spaceship::ContractContract as spaceship;
 use shippool::ContractContract as shippool;
 use token_tia::ContractContract as token_tia;
+use mock_oracle::ContractContract as mock_oracle;
 
 near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
     BOXMALL_WASM_BYTES => "../../res/boxmall.wasm",
@@ -22,6 +24,7 @@ near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
     SPACESHIP_WASM_BYTES => "../../res/spaceship.wasm",
     SHIPPOOL_WASM_BYTES => "../../res/shippool.wasm",
     TIA_WASM_BYTES => "../../res/token_tia.wasm",
+    MOCKORACLE_WASM_BYTES => "../../res/mock_oracle.wasm",
 }
 
 pub fn show_promises(r: &ExecutionResult) {
@@ -118,7 +121,8 @@ fn deploy_contracts(
             "shippool".parse().unwrap(),
             "shipmarket".parse().unwrap(),
             "auction".parse().unwrap(),
-            "luckpool".parse().unwrap()
+            "luckpool".parse().unwrap(),
+            "collectpool".parse().unwrap()
         )
     );
 
@@ -203,3 +207,559 @@ fn sim_buys() {
         50 * 10_u128.pow(18)
     );
 }
+
+#[test]
+fn sim_ubox_sale_rounds() {
+    let root = init_simulator(None);
+    let owner = root.create_user("owner".parse().unwrap(), to_yocto("1000"));
+    let user = root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    let (boxmall, mock_usn, _magicbox, _spaceship, _shippool, _token_tia) = deploy_contracts(
+        &root,
+        "bank".parse().unwrap(),
+        "bank_u".parse().unwrap(),
+        "oracle".parse().unwrap(),
+        "risker_pool".parse().unwrap(),
+        "rank_pool".parse().unwrap(),
+        "luck".parse().unwrap(),
+        owner.account_id(),
+    );
+
+    // user mints 100 usn, boxmall gets registered to receive them
+    call!(user, mock_usn.mint(U128(100)), deposit = 1).assert_success();
+    call!(
+        owner,
+        mock_usn.storage_deposit(Some(boxmall.account_id()), None),
+        deposit = to_yocto("0.00125")
+    )
+    .assert_success();
+
+    // round 0 sells 3 boxes in [1000, 2000), round 1 sells 10 in [1500, 3000)
+    call!(owner, boxmall.add_ubox_sale(1000, 2000, 3, U128(5)), deposit = 1).assert_success();
+    call!(owner, boxmall.add_ubox_sale(1500, 3000, 10, U128(7)), deposit = 1).assert_success();
+    let buy_u = |num: u32| {
+        call!(
+            user,
+            mock_usn.ft_transfer_call(
+                boxmall.account_id(),
+                U128(20),
+                None,
+                json!({"box_type": "buy_u", "num": num}).to_string()
+            ),
+            deposit = 1
+        )
+    };
+    let set_time = |sec: u64| {
+        root.borrow_runtime_mut().cur_block.block_timestamp = sec * 10u64.pow(9);
+    };
+    let usn_balance = || view!(mock_usn.ft_balance_of(user.account_id())).unwrap_json::<U128>().0;
+
+    // no round open yet, the payment comes back in full
+    let out_come = buy_u(2);
+    out_come.assert_success();
+    assert!(get_logs(&out_come).iter().any(|log| log.contains(r#""event":"u_box_sale_closed""#)));
+    assert_eq!(usn_balance(), 100);
+    let rounds = view!(boxmall.get_ubox_sale_rounds()).unwrap_json_value();
+    assert!(rounds["active"].is_null());
+    assert_eq!(rounds["upcoming"].as_array().unwrap().len(), 2);
+
+    // round 0 opens and sells out at its total
+    set_time(1000);
+    buy_u(2).assert_success();
+    assert_eq!(usn_balance(), 90);
+    let out_come = buy_u(2);
+    assert!(format!("{:?}", out_come.promise_errors()).contains("ERR_UBOX_SALE_SOLD_OUT"));
+    assert_eq!(usn_balance(), 90);
+    let rounds = view!(boxmall.get_ubox_sale_rounds()).unwrap_json_value();
+    assert_eq!(rounds["active"]["index"], 0);
+    assert_eq!(rounds["active"]["sale"], 2);
+
+    // overlapping round 1 takes over with a higher priority
+    set_time(1500);
+    call!(owner, boxmall.set_ubox_sale_priority(1, 1), deposit = 1).assert_success();
+    buy_u(1).assert_success();
+    assert_eq!(usn_balance(), 83);
+    let rounds = view!(boxmall.get_ubox_sale_rounds()).unwrap_json_value();
+    assert_eq!(rounds["active"]["index"], 1);
+    assert_eq!(rounds["active"]["sale"], 1);
+    assert_eq!(rounds["upcoming"][0]["index"], 0);
+
+    // round 0 ends
+    set_time(2000);
+    let rounds = view!(boxmall.get_ubox_sale_rounds()).unwrap_json_value();
+    assert_eq!(rounds["active"]["index"], 1);
+    assert_eq!(rounds["finished"][0]["index"], 0);
+    assert_eq!(rounds["finished"][0]["sale"], 2);
+}
+
+#[test]
+fn sim_purchase_limits() {
+    let root = init_simulator(None);
+    let owner = root.create_user("owner".parse().unwrap(), to_yocto("1000"));
+    let user = root.create_user("user".parse().unwrap(), to_yocto("100"));
+    let whale = root.create_user("whale".parse().unwrap(), to_yocto("100"));
+
+    let (boxmall, mock_usn, _magicbox, _spaceship, _shippool, _token_tia) = deploy_contracts(
+        &root,
+        "bank".parse().unwrap(),
+        "bank_u".parse().unwrap(),
+        "oracle".parse().unwrap(),
+        "risker_pool".parse().unwrap(),
+        "rank_pool".parse().unwrap(),
+        "luck".parse().unwrap(),
+        owner.account_id(),
+    );
+    call!(user, mock_usn.mint(U128(100)), deposit = 1).assert_success();
+    call!(whale, mock_usn.mint(U128(100)), deposit = 1).assert_success();
+    call!(
+        owner,
+        mock_usn.storage_deposit(Some(boxmall.account_id()), None),
+        deposit = to_yocto("0.00125")
+    )
+    .assert_success();
+
+    call!(owner, boxmall.add_ubox_sale(1000, 2000, 10, U128(5)), deposit = 1).assert_success();
+    call!(owner, boxmall.add_round_whitelist(0, vec![user.account_id()]), deposit = 1).assert_success();
+    call!(owner, boxmall.set_round_account_cap(0, 3), deposit = 1).assert_success();
+    call!(owner, boxmall.set_lifetime_account_cap(2, 1), deposit = 1).assert_success();
+    root.borrow_runtime_mut().cur_block.block_timestamp = 1000 * 10u64.pow(9);
+
+    let buy_u = |buyer: &UserAccount, num: u32| {
+        call!(
+            buyer,
+            mock_usn.ft_transfer_call(
+                boxmall.account_id(),
+                U128(20),
+                None,
+                json!({"box_type": "buy_u", "num": num}).to_string()
+            ),
+            deposit = 1
+        )
+    };
+    let allowance = |account_id: AccountId| {
+        view!(boxmall.get_purchase_allowance(account_id, None)).unwrap_json_value()
+    };
+
+    // only whitelisted accounts buy in the round
+    assert_eq!(allowance(whale.account_id())["ubox"], 0);
+    assert!(format!("{:?}", buy_u(&whale, 1).promise_errors()).contains("ERR_NOT_WHITELISTED"));
+    assert_eq!(view!(mock_usn.ft_balance_of(whale.account_id())).unwrap_json::<U128>().0, 100);
+
+    // the per-round cap stops a second buy
+    let user_allowance = allowance(user.account_id());
+    assert_eq!(user_allowance["ubox_round"], 0);
+    assert_eq!(user_allowance["ubox"], 3);
+    assert_eq!(user_allowance["sbox"], 1);
+    buy_u(&user, 2).assert_success();
+    assert_eq!(allowance(user.account_id())["ubox"], 1);
+    assert!(format!("{:?}", buy_u(&user, 2).promise_errors()).contains("ERR_ROUND_CAP_EXCEEDED"));
+    buy_u(&user, 1).assert_success();
+    assert_eq!(allowance(user.account_id())["ubox"], 0);
+
+    // removing the last uploaded account keeps the round gated
+    call!(owner, boxmall.remove_round_whitelist(0, vec![user.account_id()]), deposit = 1).assert_success();
+    assert!(view!(boxmall.get_round_limits(0)).unwrap_json::<RoundLimits>().whitelisted);
+    assert!(format!("{:?}", buy_u(&whale, 1).promise_errors()).contains("ERR_NOT_WHITELISTED"));
+    call!(owner, boxmall.set_round_whitelisted(0, false), deposit = 1).assert_success();
+    assert_eq!(allowance(whale.account_id())["ubox"], 3);
+
+    // nothing can be bought while the sales are switched off
+    call!(owner, boxmall.set_switch(true, false), deposit = 1).assert_success();
+    assert_eq!(allowance(whale.account_id())["sbox"], 0);
+}
+
+#[test]
+fn sim_tia_twap_oracle() {
+    let root = init_simulator(None);
+    let owner = root.create_user("owner".parse().unwrap(), to_yocto("1000"));
+
+    let (boxmall, _mock_usn, _magicbox, _spaceship, _shippool, _token_tia) = deploy_contracts(
+        &root,
+        "bank".parse().unwrap(),
+        "bank_u".parse().unwrap(),
+        "mock_oracle".parse().unwrap(),
+        "risker_pool".parse().unwrap(),
+        "rank_pool".parse().unwrap(),
+        "luck".parse().unwrap(),
+        owner.account_id(),
+    );
+    let oracle = deploy!(
+        contract: mock_oracle,
+        contract_id: "mock_oracle".to_string(),
+        bytes: &MOCKORACLE_WASM_BYTES,
+        signer_account: root,
+        init_method: new()
+    );
+    call!(owner, boxmall.set_sprice_switch(false), deposit = 1).assert_success();
+
+    let yocto18 = 10u128.pow(18);
+    let set_time = |sec: u64| {
+        root.borrow_runtime_mut().cur_block.block_timestamp = sec * 10u64.pow(9);
+    };
+    let set_price = |price: u128| {
+        call!(root, oracle.set_price("token_tia".parse().unwrap(), U128(price))).assert_success();
+    };
+    let refresh = || {
+        let out_come = call!(root, boxmall.refresh_tia_twap_price(), gas = DEFAULT_GAS);
+        out_come.assert_success();
+        get_logs(&out_come)
+    };
+    let ssp_price = || view!(boxmall.get_box_ssp_price()).unwrap_json::<u128>();
+    set_time(10000);
+
+    // no oracle price yet, the floor price is used
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""reason":"unavailable""#)));
+    assert_eq!(ssp_price(), 30 * yocto18);
+
+    // a fresh price under ssp_twap_price_min sets the box price
+    set_price(yocto18 / 2);
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""event":"oracle_price_updated""#)));
+    assert_eq!(ssp_price(), 60 * yocto18);
+
+    // a jump beyond the allowed deviation keeps the cached price
+    set_time(10100);
+    set_price(10 * yocto18);
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""reason":"deviation""#)));
+    assert_eq!(view!(boxmall.get_tia_twap_price()).unwrap_json_value()["price"], (yocto18 / 2).to_string());
+    assert_eq!(ssp_price(), 60 * yocto18);
+
+    // once the cached price gets stale ssp_twap_price_min is back, stale oracle answers are refused
+    set_time(10100 + 3601);
+    assert_eq!(ssp_price(), 30 * yocto18);
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""reason":"stale""#)));
+
+    // an oracle that does not answer changes nothing
+    call!(root, oracle.set_available(false)).assert_success();
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""reason":"unavailable""#)));
+    assert_eq!(ssp_price(), 30 * yocto18);
+
+    // a stale cached price still bounds the next one
+    call!(root, oracle.set_available(true)).assert_success();
+    set_price(2 * yocto18);
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""reason":"deviation""#)));
+
+    // until the owner accepts a new price
+    let out_come = call!(root, boxmall.force_tia_twap_price(U128(yocto18 * 3 / 4)), deposit = 1);
+    assert!(format!("{:?}", out_come.promise_errors()).contains("ERR_NOT_ALLOWED"));
+    call!(owner, boxmall.force_tia_twap_price(U128(yocto18 * 3 / 4)), deposit = 1).assert_success();
+    assert_eq!(ssp_price(), 40 * yocto18);
+    set_price(yocto18 * 4 / 5);
+    let logs = refresh();
+    assert!(logs.iter().any(|log| log.contains(r#""event":"oracle_price_updated""#)));
+    assert_eq!(ssp_price(), 75 * yocto18 / 2);
+
+    // TIA is never valued above ssp_twap_price_min
+    call!(owner, boxmall.force_tia_twap_price(U128(2 * yocto18)), deposit = 1).assert_success();
+    assert_eq!(ssp_price(), 30 * yocto18);
+}
+
+#[test]
+fn sim_ubox_pricing() {
+    let root = init_simulator(None);
+    let owner = root.create_user("owner".parse().unwrap(), to_yocto("1000"));
+    let user = root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    let (boxmall, mock_usn, _magicbox, _spaceship, _shippool, _token_tia) = deploy_contracts(
+        &root,
+        "bank".parse().unwrap(),
+        "bank_u".parse().unwrap(),
+        "oracle".parse().unwrap(),
+        "risker_pool".parse().unwrap(),
+        "rank_pool".parse().unwrap(),
+        "luck".parse().unwrap(),
+        owner.account_id(),
+    );
+    call!(user, mock_usn.mint(U128(1000)), deposit = 1).assert_success();
+    call!(
+        owner,
+        mock_usn.storage_deposit(Some(boxmall.account_id()), None),
+        deposit = to_yocto("0.00125")
+    )
+    .assert_success();
+
+    // round 0 is a dutch auction from 100 down to 20 over 800s, round 1 a bonding curve
+    call!(owner, boxmall.add_ubox_sale(1000, 2000, 10, U128(5)), deposit = 1).assert_success();
+    call!(owner, boxmall.add_ubox_sale(2000, 3000, 10, U128(5)), deposit = 1).assert_success();
+    call!(
+        owner,
+        boxmall.set_ubox_sale_pricing(
+            0,
+            UBoxPricing::DutchAuction { start_price: U128(100), floor_price: U128(20), duration: 800 }
+        ),
+        deposit = 1
+    )
+    .assert_success();
+    call!(
+        owner,
+        boxmall.set_ubox_sale_pricing(1, UBoxPricing::BondingCurve { base_price: U128(10), step: U128(2) }),
+        deposit = 1
+    )
+    .assert_success();
+
+    let buy_u = |amount: u128, num: u32| {
+        call!(
+            user,
+            mock_usn.ft_transfer_call(
+                boxmall.account_id(),
+                U128(amount),
+                None,
+                json!({"box_type": "buy_u", "num": num}).to_string()
+            ),
+            deposit = 1
+        )
+    };
+    let set_time = |sec: u64| {
+        root.borrow_runtime_mut().cur_block.block_timestamp = sec * 10u64.pow(9);
+    };
+    let quote = |num: u32| view!(boxmall.quote_ubox(num, None)).unwrap_json::<Option<U128>>();
+    let usn_balance = || view!(mock_usn.ft_balance_of(user.account_id())).unwrap_json::<U128>().0;
+
+    // nothing to quote before a round opens
+    assert_eq!(quote(1), None);
+
+    // halfway through the auction a box costs 60, the overpayment comes back
+    set_time(1400);
+    assert_eq!(quote(2), Some(U128(120)));
+    buy_u(200, 2).assert_success();
+    assert_eq!(usn_balance(), 880);
+
+    // past the duration the floor price holds
+    set_time(1900);
+    assert_eq!(quote(1), Some(U128(20)));
+    assert_eq!(quote(9), None);
+
+    // the bonding curve prices boxes 0, 1, 2 at 10, 12, 14 then box 3 at 16
+    set_time(2000);
+    assert_eq!(quote(3), Some(U128(36)));
+    buy_u(50, 3).assert_success();
+    assert_eq!(usn_balance(), 844);
+    assert_eq!(quote(1), Some(U128(16)));
+    let out_come = buy_u(15, 1);
+    assert!(format!("{:?}", out_come.promise_errors()).contains("payment less than actual amount"));
+    assert_eq!(usn_balance(), 844);
+    // an empty order never reaches the curve
+    assert_eq!(quote(0), None);
+    let out_come = buy_u(10, 0);
+    assert!(format!("{:?}", out_come.promise_errors()).contains("ERR_INVALID_NUM"));
+    assert_eq!(usn_balance(), 844);
+}
+
+#[test]
+fn sim_payment_tokens() {
+    let root = init_simulator(None);
+    let owner = root.create_user("owner".parse().unwrap(), to_yocto("1000"));
+    let user = root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    let (boxmall, _mock_usn, _magicbox, _spaceship, _shippool, _token_tia) = deploy_contracts(
+        &root,
+        "bank".parse().unwrap(),
+        "bank_u".parse().unwrap(),
+        "mock_oracle".parse().unwrap(),
+        "risker_pool".parse().unwrap(),
+        "rank_pool".parse().unwrap(),
+        "luck".parse().unwrap(),
+        owner.account_id(),
+    );
+    let oracle = deploy!(
+        contract: mock_oracle,
+        contract_id: "mock_oracle".to_string(),
+        bytes: &MOCKORACLE_WASM_BYTES,
+        signer_account: root,
+        init_method: new()
+    );
+    let usdc = deploy!(
+        contract: mock_usn,
+        contract_id: "mock_usdc".to_string(),
+        bytes: &MOCKUSN_WASM_BYTES,
+        signer_account: root,
+        init_method: new()
+    );
+    call!(user, usdc.mint(U128(100)), deposit = 1).assert_success();
+    call!(
+        owner,
+        usdc.storage_deposit(Some(boxmall.account_id()), None),
+        deposit = to_yocto("0.00125")
+    )
+    .assert_success();
+    call!(owner, boxmall.add_ubox_sale(1000, 2000, 10, U128(5)), deposit = 1).assert_success();
+    root.borrow_runtime_mut().cur_block.block_timestamp = 1000 * 10u64.pow(9);
+
+    let buy_u = |amount: u128, num: u32| {
+        call!(
+            user,
+            usdc.ft_transfer_call(
+                boxmall.account_id(),
+                U128(amount),
+                None,
+                json!({"box_type": "buy_u", "num": num}).to_string()
+            ),
+            deposit = 1
+        )
+    };
+    let quote = |num: u32| view!(boxmall.quote_ubox(num, Some(usdc.account_id()))).unwrap_json::<Option<U128>>();
+    let usdc_balance = |account_id: AccountId| view!(usdc.ft_balance_of(account_id)).unwrap_json::<U128>().0;
+
+    // USN and TIA are accepted from the start, USDC is not
+    let metadata = view!(boxmall.get_metadata()).unwrap_json_value();
+    assert_eq!(metadata["payment_tokens"].as_array().unwrap().len(), 2);
+    assert!(format!("{:?}", buy_u(20, 2).promise_errors()).contains("Invalid contract Id"));
+    assert_eq!(usdc_balance(user.account_id()), 100);
+
+    // USDC priced by the oracle, paid to its own bank
+    call!(
+        owner,
+        boxmall.add_payment_token(usdc.account_id(), 1, PaymentPrice::Oracle, Some("usdc_bank".parse().unwrap())),
+        deposit = 1
+    )
+    .assert_success();
+    assert_eq!(quote(2), None);
+    assert!(format!("{:?}", buy_u(20, 2).promise_errors()).contains("ERR_NO_PAYMENT_RATE"));
+
+    // one USDC unit is worth two USN units, two boxes at 5 USN cost 5 USDC
+    call!(root, oracle.set_price(usdc.account_id(), U128(2 * 10u128.pow(18)))).assert_success();
+    call!(root, boxmall.refresh_payment_token_rate(usdc.account_id()), gas = DEFAULT_GAS).assert_success();
+    assert_eq!(quote(2), Some(U128(5)));
+    buy_u(20, 2).assert_success();
+    assert_eq!(usdc_balance(user.account_id()), 95);
+    assert_eq!(usdc_balance("usdc_bank".parse().unwrap()), 5);
+    let token = view!(boxmall.get_payment_token(usdc.account_id())).unwrap_json_value();
+    assert_eq!(token["sale_num"], 2);
+    assert_eq!(token["sale_amount"], "5");
+
+    // a rate far from the last accepted one needs the owner
+    call!(root, oracle.set_price(usdc.account_id(), U128(4 * 10u128.pow(18)))).assert_success();
+    call!(root, boxmall.refresh_payment_token_rate(usdc.account_id()), gas = DEFAULT_GAS).assert_success();
+    assert_eq!(quote(2), Some(U128(5)));
+    call!(
+        owner,
+        boxmall.force_payment_token_rate(usdc.account_id(), U128(4 * 10u128.pow(18))),
+        deposit = 1
+    )
+    .assert_success();
+    assert_eq!(quote(2), Some(U128(3)));
+
+    // a flat USDC price keeps the statistics
+    call!(
+        owner,
+        boxmall.add_payment_token(usdc.account_id(), 1, PaymentPrice::Fixed { price: U128(3) }, None),
+        deposit = 1
+    )
+    .assert_success();
+    assert_eq!(quote(2), Some(U128(6)));
+    let metadata = view!(boxmall.get_metadata()).unwrap_json_value();
+    let token = metadata["payment_tokens"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|token| token["token_id"] == "mock_usdc")
+        .unwrap()
+        .clone();
+    assert_eq!(token["bank"], "bank_u");
+    assert_eq!(token["sale_num"], 2);
+
+    // S-boxes are only paid in TIA
+    let out_come = call!(
+        owner,
+        boxmall.add_payment_token(usdc.account_id(), 2, PaymentPrice::Base, None),
+        deposit = 1
+    );
+    assert!(format!("{:?}", out_come.promise_errors()).contains("ERR_SBOX_PAID_IN_TIA_ONLY"));
+
+    call!(owner, boxmall.remove_payment_token(usdc.account_id()), deposit = 1).assert_success();
+    assert_eq!(quote(1), None);
+    assert!(format!("{:?}", buy_u(20, 1).promise_errors()).contains("Invalid contract Id"));
+    assert_eq!(usdc_balance(user.account_id()), 95);
+
+    // only TIA transfers from the pools credit a ship wallet, even for a registered token
+    call!(owner, boxmall.add_payment_token(usdc.account_id(), 1, PaymentPrice::Base, None), deposit = 1).assert_success();
+    let out_come = call!(
+        user,
+        usdc.ft_transfer_call(
+            boxmall.account_id(),
+            U128(10),
+            None,
+            json!({"contract_id": "trialpool", "user_id": user.account_id()}).to_string()
+        ),
+        deposit = 1
+    );
+    assert!(format!("{:?}", out_come.promise_errors()).contains("Invalid contract Id"));
+    assert_eq!(usdc_balance(user.account_id()), 95);
+    assert_eq!(view!(boxmall.ship_wallet_balance_of(user.account_id())).unwrap_json::<u128>(), 0);
+
+    // a U-box token does not pay for S-boxes
+    let out_come = call!(
+        user,
+        usdc.ft_transfer_call(
+            boxmall.account_id(),
+            U128(10),
+            None,
+            json!({"box_type": "buy_s", "num": 1}).to_string()
+        ),
+        deposit = 1
+    );
+    assert!(format!("{:?}", out_come.promise_errors()).contains("Invalid contract Id"));
+    assert_eq!(usdc_balance(user.account_id()), 95);
+}
+
+#[test]
+fn sim_refund_open() {
+    let root = init_simulator(None);
+    let owner = root.create_user("owner".parse().unwrap(), to_yocto("1000"));
+    let user = root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    let (_boxmall, _mock_usn, magicbox, _spaceship, _shippool, _token_tia) = deploy_contracts(
+        &root,
+        "bank".parse().unwrap(),
+        "bank_u".parse().unwrap(),
+        "oracle".parse().unwrap(),
+        "risker_pool".parse().unwrap(),
+        "rank_pool".parse().unwrap(),
+        "luck".parse().unwrap(),
+        owner.account_id(),
+    );
+
+    call!(owner, magicbox.batch_mint(user.account_id(), 1, 2)).assert_success();
+    call!(owner, magicbox.set_open_timeout_blocks(5), deposit = 1).assert_success();
+    let box_ids = vec!["0:1".to_string(), "1:1".to_string()];
+    let box_owner = |box_id: &String| {
+        view!(magicbox.nft_token(box_id.clone()))
+            .unwrap_json::<Token>()
+            .owner_id
+    };
+
+    // the boxes are locked in magicbox until the request is fulfilled or refunded
+    let out_come = call!(user, magicbox.request_open(box_ids.clone()), deposit = 1);
+    out_come.assert_success();
+    let request_id: u64 = out_come.unwrap_json();
+    assert_eq!(box_owner(&box_ids[0]), magicbox.account_id());
+
+    let out_come = call!(user, magicbox.refund_open(request_id));
+    assert!(get_error_status(&out_come).contains("ERR_OPEN_REQUEST_NOT_EXPIRED"));
+
+    // nobody fulfilled it in time
+    root.borrow_runtime_mut().produce_blocks(6).unwrap();
+    let out_come = call!(user, magicbox.fulfill_open(request_id), gas = DEFAULT_GAS);
+    assert!(get_error_status(&out_come).contains("ERR_OPEN_REQUEST_EXPIRED"));
+
+    let out_come = call!(user, magicbox.refund_open(request_id));
+    out_come.assert_success();
+    assert!(get_logs(&out_come)
+        .iter()
+        .any(|log| log.contains(r#""event":"open_refund""#)));
+    for box_id in box_ids.iter() {
+        assert_eq!(box_owner(box_id), user.account_id());
+    }
+    assert!(view!(magicbox.get_open_request(request_id))
+        .unwrap_json_value()
+        .is_null());
+
+    // a refunded request is gone
+    let out_come = call!(user, magicbox.refund_open(request_id));
+    assert!(get_error_status(&out_come).contains("ERR_OPEN_REQUEST_NOT_EXIST"));
+}
@@ -179,7 +179,8 @@ fn deploy_contracts(
             "shippool".parse().unwrap(),
             "shipmarket".parse().unwrap(),
             "auction".parse().unwrap(),
-            "luckpool".parse().unwrap()
+            "luckpool".parse().unwrap(),
+            "collectpool".parse().unwrap()
         )
     );
     println!("deploy spaceship ... done");
@@ -455,6 +456,8 @@ fn test_callback() {
     );
     out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
+    // move into the sale window
+    owner.borrow_runtime_mut().cur_block.block_timestamp = 1655208000 * 10u64.pow(9);
     
 
     // 5. test buy ubox for boxmall.ft_on_transfer on usn token
@@ -608,10 +611,17 @@ fn test_callback() {
     "7:2".to_string()];
     out_come = call!(
         user,
-        magicbox.open_box(box_token_ids),
+        magicbox.request_open(box_token_ids),
         deposit = 1
     );
     out_come.assert_success();
+    let request_id: u64 = out_come.unwrap_json();
+    out_come = call!(
+        user,
+        magicbox.fulfill_open(request_id),
+        gas = near_sdk_sim::DEFAULT_GAS
+    );
+    out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
     println!("{:#?}", get_logs(&out_come));
     
@@ -206,7 +206,8 @@ fn deploy_contracts(
             "shippool".parse().unwrap(),
             "shipmarket".parse().unwrap(),
             "auction".parse().unwrap(),
-            "luckpool".parse().unwrap()
+            "luckpool".parse().unwrap(),
+            "collectpool".parse().unwrap()
         )
     );
     println!("deploy spaceship ... done");
@@ -503,6 +504,16 @@ fn test_shipmarket() {
         deposit = 1
     );
     out_come.assert_success();
+    // the mint is timelocked, run it once the delay passed
+    let action_id: u64 = out_come.unwrap_json();
+    let delay: u64 = view!(spaceship.get_timelock_delay()).unwrap_json();
+    owner.borrow_runtime_mut().cur_block.block_timestamp += delay * 10u64.pow(9);
+    out_come = call!(
+        owner,
+        spaceship.execute_action(action_id),
+        deposit = 1
+    );
+    out_come.assert_success();
     //println!("{:#?}", out_come.promise_results());
     println!("{:#?}", get_logs(&out_come));
 
@@ -566,6 +577,8 @@ fn test_shipmarket() {
     );
     out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
+    // move into the sale window
+    owner.borrow_runtime_mut().cur_block.block_timestamp = 1655208000 * 10u64.pow(9);
     
 
     // 5. test buy ubox for boxmall.ft_on_transfer on usn token
@@ -721,10 +734,17 @@ fn test_shipmarket() {
     //"7:2".to_string(),"8:2".to_string(),"9:2".to_string()];
     out_come = call!(
         user,
-        magicbox.open_box(box_token_ids),
+        magicbox.request_open(box_token_ids),
         deposit = 1
     );
     out_come.assert_success();
+    let request_id: u64 = out_come.unwrap_json();
+    out_come = call!(
+        user,
+        magicbox.fulfill_open(request_id),
+        gas = near_sdk_sim::DEFAULT_GAS
+    );
+    out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
     println!("{:#?}", get_logs(&out_come));
     
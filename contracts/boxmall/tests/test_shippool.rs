@@ -179,7 +179,8 @@ fn deploy_contracts(
             "shippool".parse().unwrap(),
             "shipmarket".parse().unwrap(),
             "auction".parse().unwrap(),
-            "luckpool".parse().unwrap()
+            "luckpool".parse().unwrap(),
+            "collectpool".parse().unwrap()
         )
     );
     println!("deploy spaceship ... done");
@@ -463,6 +464,8 @@ fn test_shippool() {
     );
     out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
+    // move into the sale window
+    owner.borrow_runtime_mut().cur_block.block_timestamp = 1655208000 * 10u64.pow(9);
     
 
     // 5. test buy ubox for boxmall.ft_on_transfer on usn token
@@ -640,7 +643,7 @@ fn test_shippool() {
     println!("{:#?}", get_logs(&out_come));
 
     // 7.3 openbox
-    println!("------------------------------ 7.3.1.1 magicbox.open_box");
+    println!("------------------------------ 7.3.1.1 magicbox.request_open + fulfill_open");
     //let box_token_ids: Vec<String> = vec!["0:1".to_string(),"1:1".to_string(),"2:2".to_string(),"3:2".to_string()];
     let box_token_ids: Vec<String> = vec![
         "0:2".to_string(),"1:2".to_string(),"2:2".to_string(),"3:2".to_string(),"4:2".to_string(),
@@ -648,38 +651,59 @@ fn test_shippool() {
         ];
     out_come = call!(
         user1,
-        magicbox.open_box(box_token_ids),
+        magicbox.request_open(box_token_ids),
         deposit = 1
     );
     out_come.assert_success();
+    let request_id: u64 = out_come.unwrap_json();
+    out_come = call!(
+        user1,
+        magicbox.fulfill_open(request_id),
+        gas = near_sdk_sim::DEFAULT_GAS
+    );
+    out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
     println!("{:#?}", get_logs(&out_come));
 
-    println!("------------------------------ 7.3.1.2 magicbox.open_box");
+    println!("------------------------------ 7.3.1.2 magicbox.request_open + fulfill_open");
     let box_token_ids: Vec<String> = vec![
         "10:2".to_string(),"11:2".to_string(),"12:2".to_string(),"13:2".to_string(),"14:2".to_string(),
         "15:2".to_string(),"16:2".to_string(),"17:2".to_string(),"18:2".to_string(),"19:2".to_string()
         ];
     out_come = call!(
         user1,
-        magicbox.open_box(box_token_ids),
+        magicbox.request_open(box_token_ids),
         deposit = 1
     );
     out_come.assert_success();
+    let request_id: u64 = out_come.unwrap_json();
+    out_come = call!(
+        user1,
+        magicbox.fulfill_open(request_id),
+        gas = near_sdk_sim::DEFAULT_GAS
+    );
+    out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
     println!("{:#?}", get_logs(&out_come));
 
-    println!("------------------------------ 7.3.1.3 magicbox.open_box");
+    println!("------------------------------ 7.3.1.3 magicbox.request_open + fulfill_open");
     let box_token_ids: Vec<String> = vec![
         "20:2".to_string(),"21:2".to_string(),"22:2".to_string(),"23:2".to_string(),"24:2".to_string(),
         "25:2".to_string(),"26:2".to_string(),"27:2".to_string(),"28:2".to_string(),"29:2".to_string()
         ];
     out_come = call!(
         user1,
-        magicbox.open_box(box_token_ids),
+        magicbox.request_open(box_token_ids),
         deposit = 1
     );
     out_come.assert_success();
+    let request_id: u64 = out_come.unwrap_json();
+    out_come = call!(
+        user1,
+        magicbox.fulfill_open(request_id),
+        gas = near_sdk_sim::DEFAULT_GAS
+    );
+    out_come.assert_success();
     println!("{:#?}", out_come.promise_results());
     println!("{:#?}", get_logs(&out_come));
 
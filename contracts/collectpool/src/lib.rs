@@ -8,7 +8,7 @@ use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, UnorderedSet, LookupMap, UnorderedMap, Vector};
+use near_sdk::collections::{LazyOption, UnorderedSet, LookupMap, LookupSet, UnorderedMap, Vector};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
@@ -30,6 +30,7 @@ pub const TYPE_SHIP_C: u8 = 2;
 pub const TYPE_SHIP_D: u8 = 1;
 
 
+// subtype counts of the first slots, spaceship pushes later tier changes
 pub const TYPE_S_MAX: u8 = 4;
 pub const TYPE_A_MAX: u8 = 4;
 pub const TYPE_B_MAX: u8 = 8;
@@ -44,6 +45,7 @@ pub const TGAS: u64 = 1_000_000_000_000;
 pub const GAS_FOR_TRANSFER: Gas = Gas(25* TGAS);
 pub const GAS_FOR_NFT_TRANSFER: Gas = Gas(25* TGAS);
 pub const GAS_FOR_TRANSFER_ON_CALL: Gas = Gas(45 * TGAS);
+pub const GAS_FOR_ADD_SHIP_XP: Gas = Gas(30 * TGAS);
 
 pub const YOCTO18: u128 = 1_000_000_000_000_000_000;
 pub const CALC_PRECISION: u128 = 1_000_000_000_000;
@@ -96,6 +98,14 @@ pub(crate) enum StorageKey {
     Rewards,
     SlotInfo,
     CapacityInfo,
+    ShipOwner,
+    StakeTime,
+    LockedShip,
+    ShipStaker,
+    ExtraCapacity,
+    ShipCapacity,
+    StakedCapacity,
+    CollectBonus,
 }
 
 #[ext_contract(ext_nft)]
@@ -109,6 +119,12 @@ trait NonFungibleToken {
     );
 }
 
+#[ext_contract(ext_spaceship)]
+trait SpaceShip {
+    fn add_ship_xp(&mut self, token_id: TokenId, staked_seconds: u64);
+    fn unlock(&mut self, token_id: TokenId);
+}
+
 #[ext_contract(ext_boxmall)]
 trait boxmall{
     fn ship_wallet_add( &mut self, to: AccountId, amount: U128 );
@@ -150,6 +166,14 @@ pub struct Contract {
     is_init_slot: bool,
     balance: Balance,
 
+    ship_owners: LookupMap<TokenId, AccountId>, // rented ship => its owner, the ship goes back there on withdraw
+    stake_times: LookupMap<TokenId, u64>, // staked ship => timestamp in seconds it was staked, reported to spaceship as xp
+    locked_ships: LookupSet<TokenId>, // ships staked by a spaceship lock, they stay with their owner
+    ship_stakers: LookupMap<TokenId, AccountId>, // rented ship => the user who staked it, its slot is freed when the rental expires
+    extra_capacity: LookupMap<String, u32>, // "account:ship type" => full-set bonus granted, removed as granted
+    ship_capacity: LookupMap<TokenId, u32>, // ship => capacity raised by its level, pushed by spaceship
+    staked_capacity: LookupMap<TokenId, u32>, // staked ship => capacity it counts with, taken back on withdraw
+    collect_bonus: LookupMap<u8, u32>, // ship type => full-set bonus, pushed by spaceship with the tier
 }
 
 #[near_bindgen]
@@ -184,8 +208,18 @@ impl Contract {
 
             balance: 0,
             is_init_slot: false,
+
+            ship_owners: LookupMap::new(StorageKey::ShipOwner),
+            stake_times: LookupMap::new(StorageKey::StakeTime),
+            locked_ships: LookupSet::new(StorageKey::LockedShip),
+            ship_stakers: LookupMap::new(StorageKey::ShipStaker),
+            extra_capacity: LookupMap::new(StorageKey::ExtraCapacity),
+            ship_capacity: LookupMap::new(StorageKey::ShipCapacity),
+            staked_capacity: LookupMap::new(StorageKey::StakedCapacity),
+            collect_bonus: LookupMap::new(StorageKey::CollectBonus),
         };
         this.internal_init_decay_table();
+        this.internal_init_collect_bonus();
         this
     }
 
@@ -215,40 +249,7 @@ impl Contract {
     #[payable]
     pub fn withdraw( &mut self, slot_index: u64) {
         assert_one_yocto();
-        let predecessor_id = env::predecessor_account_id();
-        self.internal_check_start();
-        self.internal_update_reward(predecessor_id.clone());
-
-        let mut stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&predecessor_id).expect("No_StakeSlot");
-
-        let mut withdraw_slot: StakeSlot = stake_slot_vec[slot_index as usize].clone();
-        require!(withdraw_slot.token_id != "".to_string(), "No ship can withdraw");
-
-        ext_nft::nft_transfer(
-            predecessor_id.clone(), 
-            withdraw_slot.token_id.clone(),
-            None,
-            None,
-            self.spaceship.clone(),
-            1,
-            GAS_FOR_NFT_TRANSFER
-        );
-
-
-        let mut capacity = self.capacity_info.get(&predecessor_id.clone()).unwrap_or(0);
-        // check extra capacity and remove
-        let extra: u32 = self.get_extra_capacity(predecessor_id.clone(), slot_index);
-        capacity -= extra;
-        self.total_capacity -= extra; 
-
-        let ship_capacity = self.internal_get_ship_capacity_by_token_id(withdraw_slot.token_id.clone());
-        capacity -= ship_capacity as u32;
-        self.capacity_info.insert(&predecessor_id.clone(), &capacity);
-        self.total_capacity -= ship_capacity as u32;       
-        withdraw_slot.token_id = "".to_string();
-        stake_slot_vec[slot_index as usize] = withdraw_slot;
-
-        self.slot_info.insert(&predecessor_id,&stake_slot_vec);
+        self.internal_withdraw(env::predecessor_account_id(), slot_index, true);
     }
 
     #[payable]
@@ -298,26 +299,66 @@ impl Contract {
 
         require!(!self.is_init_slot, "already init");
 
-        for d in 1..(TYPE_D_MAX+1) {
-            self.slot.push(&Slot{price : 0, enable : true, ship_type : TYPE_SHIP_D, ship_sub_type : d});
-        }
+        self.internal_add_type_slots(TYPE_SHIP_D, TYPE_D_MAX);
+        self.internal_add_type_slots(TYPE_SHIP_C, TYPE_C_MAX);
+        self.internal_add_type_slots(TYPE_SHIP_B, TYPE_B_MAX);
+        self.internal_add_type_slots(TYPE_SHIP_A, TYPE_A_MAX);
+        self.internal_add_type_slots(TYPE_SHIP_S, TYPE_S_MAX);
+        self.is_init_slot = true;
+    }
+}
 
-        for c in 1..(TYPE_C_MAX+1) {
-            self.slot.push(&Slot{price : 0, enable : true, ship_type : TYPE_SHIP_C, ship_sub_type : c});
-        }
+#[near_bindgen]
+impl Contract {
+    /// Callback on a ship locked into this pool by spaceship, it is staked without being transferred.
+    pub fn nft_on_lock(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let predecessor_id = env::predecessor_account_id();
+        require!(predecessor_id == self.spaceship, "Invalid contract Id");
 
-        for b in 1..(TYPE_B_MAX+1) {
-            self.slot.push(&Slot{price : 0, enable : true, ship_type : TYPE_SHIP_B, ship_sub_type : b});
-        }
+        let slot_index: SlotIndex = msg.parse::<SlotIndex>().expect("msg must contain all digits");
+        log!("{} locks ship {} of {}", sender_id, token_id, owner_id);
 
-        for a in 1..(TYPE_A_MAX+1) {
-            self.slot.push(&Slot{price : 0, enable : true, ship_type : TYPE_SHIP_A, ship_sub_type : a});
+        self.locked_ships.insert(&token_id);
+        if sender_id != owner_id {
+            self.ship_stakers.insert(&token_id, &sender_id);
         }
+        self.stake_times.insert(&token_id, &(env::block_timestamp() / 10u64.pow(9)));
+
+        self.internal_stake(sender_id, slot_index, token_id.clone());
+
+        PromiseOrValue::Value(true)
+    }
 
-        for s in 1..(TYPE_S_MAX+1) {
-            self.slot.push(&Slot{price : 0, enable : true, ship_type : TYPE_SHIP_S, ship_sub_type : s});
+    /// Callback on a spaceship tier change, new subtypes get their slots and the tier its full-set bonus
+    #[allow(unused_variables)]
+    pub fn on_ship_tier_updated(&mut self, ship_type: u8, sub_type_count: u8, upgrade_target: Option<u8>, collect_bonus: u32) {
+        require!(env::predecessor_account_id() == self.spaceship, "Invalid contract Id");
+        self.internal_add_type_slots(ship_type, sub_type_count);
+        self.collect_bonus.insert(&ship_type, &collect_bonus);
+    }
+
+    /// Callback on a rented ship spaceship gave back to its owner after the rental expired,
+    /// the user's slot is freed without sending the ship.
+    pub fn nft_on_rental_expired(&mut self, token_id: TokenId) {
+        require!(env::predecessor_account_id() == self.spaceship, "Invalid contract Id");
+        if let Some(staker_id) = self.ship_stakers.get(&token_id) {
+            let stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&staker_id).expect("No_StakeSlot");
+            if let Some(slot_index) = stake_slot_vec.iter().position(|slot| slot.token_id == token_id) {
+                self.internal_withdraw(staker_id, slot_index as u64, false);
+            }
         }
-        self.is_init_slot = true;
+    }
+
+    /// Callback on a ship leveling up in spaceship, it stakes with the raised capacity from then on
+    pub fn on_ship_level_up(&mut self, token_id: TokenId, effective_capacity: u32) {
+        require!(env::predecessor_account_id() == self.spaceship, "Invalid contract Id");
+        self.ship_capacity.insert(&token_id, &effective_capacity);
     }
 }
 
@@ -338,6 +379,13 @@ impl NonFungibleTokenReceiver for Contract {
 
         let slot_index: SlotIndex = msg.parse::<SlotIndex>().expect("msg must contain all digits");
 
+        // a rented ship staked by its user goes back to the owner on withdraw
+        if sender_id != previous_owner_id {
+            self.ship_owners.insert(&token_id, &previous_owner_id);
+            self.ship_stakers.insert(&token_id, &sender_id);
+        }
+        self.stake_times.insert(&token_id, &(env::block_timestamp() / 10u64.pow(9)));
+
         self.internal_stake(sender_id, slot_index, token_id.clone());
 
         PromiseOrValue::Value(false)
@@ -345,6 +393,68 @@ impl NonFungibleTokenReceiver for Contract {
 }
 
 impl Contract{
+    /// frees the slot, `release_ship` false when the ship already left the pool
+    pub fn internal_withdraw( &mut self, account_id: AccountId, slot_index: u64, release_ship: bool) {
+        self.internal_check_start();
+        self.internal_update_reward(account_id.clone());
+
+        let mut stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&account_id).expect("No_StakeSlot");
+
+        let mut withdraw_slot: StakeSlot = stake_slot_vec[slot_index as usize].clone();
+        require!(withdraw_slot.token_id != "".to_string(), "No ship can withdraw");
+
+        self.ship_stakers.remove(&withdraw_slot.token_id);
+        if !release_ship {
+            // spaceship already gave the ship back to its owner
+            self.locked_ships.remove(&withdraw_slot.token_id);
+            self.ship_owners.remove(&withdraw_slot.token_id);
+        } else if self.locked_ships.remove(&withdraw_slot.token_id) {
+            ext_spaceship::unlock(
+                withdraw_slot.token_id.clone(),
+                self.spaceship.clone(),
+                1,
+                GAS_FOR_NFT_TRANSFER
+            );
+        } else {
+            let receiver_id = self.ship_owners.remove(&withdraw_slot.token_id).unwrap_or(account_id.clone());
+            ext_nft::nft_transfer(
+                receiver_id,
+                withdraw_slot.token_id.clone(),
+                None,
+                None,
+                self.spaceship.clone(),
+                1,
+                GAS_FOR_NFT_TRANSFER
+            );
+        }
+
+        if let Some(staked_at) = self.stake_times.remove(&withdraw_slot.token_id) {
+            ext_spaceship::add_ship_xp(
+                withdraw_slot.token_id.clone(),
+                env::block_timestamp() / 10u64.pow(9) - staked_at,
+                self.spaceship.clone(),
+                0,
+                GAS_FOR_ADD_SHIP_XP
+            );
+        }
+
+
+        let mut capacity = self.capacity_info.get(&account_id.clone()).unwrap_or(0);
+        // check extra capacity and remove
+        let extra: u32 = self.internal_revoke_extra_capacity(&account_id, slot_index);
+        capacity -= extra;
+        self.total_capacity -= extra; 
+
+        let ship_capacity = self.internal_take_staked_capacity(&withdraw_slot.token_id);
+        capacity -= ship_capacity;
+        self.capacity_info.insert(&account_id.clone(), &capacity);
+        self.total_capacity -= ship_capacity;       
+        withdraw_slot.token_id = "".to_string();
+        stake_slot_vec[slot_index as usize] = withdraw_slot;
+
+        self.slot_info.insert(&account_id,&stake_slot_vec);
+    }
+
     pub fn internal_stake( &mut self, sender_id: AccountId, slot_index: u64, token_id: TokenId) {
         self.internal_check_start();
         self.internal_check_slot(sender_id.clone(), slot_index, token_id.clone());
@@ -354,9 +464,13 @@ impl Contract{
             vec![StakeSlot{
                 token_id:"".to_string(),
                 enable: false};
-                ( TYPE_A_MAX + TYPE_B_MAX + TYPE_C_MAX + TYPE_D_MAX + TYPE_S_MAX ) as usize
+                self.slot.len() as usize
                 ]
         );
+        // slots added for new subtypes since the user first staked
+        if stake_slot_vec.len() < self.slot.len() as usize {
+            stake_slot_vec.resize(self.slot.len() as usize, StakeSlot{token_id: "".to_string(), enable: false});
+        }
 
         let mut stake_slot: StakeSlot = stake_slot_vec[slot_index as usize].clone();
         if stake_slot.token_id != "".to_string() {
@@ -368,18 +482,58 @@ impl Contract{
         self.slot_info.insert(&sender_id,& stake_slot_vec);
 
         let mut capacity = self.capacity_info.get(&sender_id).unwrap_or(0);
-        let ship_capacity = self.internal_get_ship_capacity_by_token_id(token_id.clone());
-        capacity += ship_capacity as u32;
-        self.total_capacity += ship_capacity as u32;
+        let ship_capacity = self.internal_ship_capacity(&token_id);
+        self.staked_capacity.insert(&token_id, &ship_capacity);
+        capacity += ship_capacity;
+        self.total_capacity += ship_capacity;
 
         // check extra capacity and add
-        let extra: u32 = self.get_extra_capacity(sender_id.clone(), slot_index);
+        let extra: u32 = self.internal_grant_extra_capacity(&sender_id, slot_index);
         capacity += extra;
         self.capacity_info.insert(&sender_id, &capacity);
         self.total_capacity += extra;
     }
 
 
+    /// full-set bonus of the tiers spaceship starts with, later pushed by spaceship on every tier change
+    pub fn internal_init_collect_bonus(&mut self) {
+        self.collect_bonus.insert(&TYPE_SHIP_D, &5);
+        self.collect_bonus.insert(&TYPE_SHIP_C, &10);
+        self.collect_bonus.insert(&TYPE_SHIP_B, &15);
+        self.collect_bonus.insert(&TYPE_SHIP_A, &20);
+    }
+
+    /// push the missing slots of `ship_type` up to `sub_type_count`
+    pub fn internal_add_type_slots(&mut self, ship_type: u8, sub_type_count: u8) {
+        let existing = self.slot.iter().filter(|slot| slot.ship_type == ship_type).count() as u8;
+        for sub_type in (existing + 1)..(sub_type_count + 1) {
+            self.slot.push(&Slot{price : 0, enable : true, ship_type, ship_sub_type : sub_type});
+        }
+    }
+
+    /// full-set bonus of the slot's ship type, recorded so the same bonus is removed on withdraw
+    pub fn internal_grant_extra_capacity(&mut self, account_id: &AccountId, slot_index: u64) -> u32 {
+        let key = self.internal_extra_capacity_key(account_id, slot_index);
+        if self.extra_capacity.get(&key).is_some() {
+            return 0;
+        }
+        let extra: u32 = self.get_extra_capacity(account_id.clone(), slot_index);
+        if extra > 0 {
+            self.extra_capacity.insert(&key, &extra);
+        }
+        extra
+    }
+
+    pub fn internal_revoke_extra_capacity(&mut self, account_id: &AccountId, slot_index: u64) -> u32 {
+        let key = self.internal_extra_capacity_key(account_id, slot_index);
+        self.extra_capacity.remove(&key).unwrap_or(0)
+    }
+
+    fn internal_extra_capacity_key(&self, account_id: &AccountId, slot_index: u64) -> String {
+        let slot: Slot = self.slot.get(slot_index).expect("Invalid slot_index");
+        format!("{}:{}", account_id, slot.ship_type)
+    }
+
     pub fn internal_check_slot( &self, from: AccountId, slot_index: u64, token_id: TokenId) {
         let slot_temp: Slot = self.slot.get(slot_index).expect("Invalid slot_index");
         let ship_type = self.internal_get_ship_type_by_token_id(token_id.clone());
@@ -437,6 +591,22 @@ impl Contract{
         capacity
     }
 
+    /// capacity a ship stakes with, its level bonus included
+    pub fn internal_ship_capacity(&self, token_id: &TokenId) -> u32 {
+        self.ship_capacity.get(token_id).unwrap_or(self.internal_get_ship_capacity_by_token_id(token_id.clone()) as u32)
+    }
+
+    /// capacity a staked ship counts with, ships staked before levels counted their rolled capacity
+    pub fn internal_staked_capacity(&self, token_id: &TokenId) -> u32 {
+        self.staked_capacity.get(token_id).unwrap_or(self.internal_get_ship_capacity_by_token_id(token_id.clone()) as u32)
+    }
+
+    pub fn internal_take_staked_capacity(&mut self, token_id: &TokenId) -> u32 {
+        let capacity = self.internal_staked_capacity(token_id);
+        self.staked_capacity.remove(token_id);
+        capacity
+    }
+
     pub fn get_last_block_applicable( &self ) -> u64 {
         return cmp::min(env::block_height(), self.end_block);
     }
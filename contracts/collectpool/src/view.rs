@@ -52,6 +52,11 @@ impl Contract {
     }
 
     /* ========== VIEW FUNCTION ========== */
+    // spaceship asks before the owner releases a lock this pool may still hold
+    pub fn is_locked_ship(&self, token_id: TokenId) -> bool {
+        self.locked_ships.contains(&token_id)
+    }
+
     pub fn get_slot_list( &self, from: AccountId) -> Vec<SlotList> {
         let mut list: Vec<SlotList> = vec![];
         log!("collectpool.get_slot_list. self.slot.len = {:#?}", self.slot.len());
@@ -69,14 +74,15 @@ impl Contract {
             let slot_node = self.slot.get(i).expect("No Slot");
             slot_list_node.slot_index = i as u32;
             slot_list_node.price = slot_node.price;
-            slot_list_node.enable = slot_node.enable || stake_slot_vec[i as usize].enable;
-            let token_id: TokenId = stake_slot_vec[i as usize].token_id.clone();
+            // slots added after the user staked are empty
+            let stake_slot: StakeSlot = stake_slot_vec.get(i as usize).cloned().unwrap_or(StakeSlot{token_id: "".to_string(), enable: false});
+            slot_list_node.enable = slot_node.enable || stake_slot.enable;
+            let token_id: TokenId = stake_slot.token_id.clone();
             slot_list_node.token_id = token_id.clone();
             if token_id != "".to_string(){
                 let ship_type = self.internal_get_ship_type_by_token_id(token_id.clone());
                 let ship_subtype = self.internal_get_ship_subtype_by_token_id(token_id.clone());
-                let ship_capacity = self.internal_get_ship_capacity_by_token_id(token_id.clone());
-                slot_list_node.capacity = ship_capacity as u32;
+                slot_list_node.capacity = self.internal_staked_capacity(&token_id);
                 slot_list_node.ship_type = ship_type;
                 slot_list_node.ship_subtype = ship_subtype;
             }
@@ -89,36 +95,25 @@ impl Contract {
     pub fn get_extra_capacity(&self, from: AccountId, slot_index: u64) -> u32 {
         let slot_temp: Slot = self.slot.get(slot_index).expect("Invalid slot_index");
         let mut num: u8 = 0;
-        let mut extra: u32 = 0;
+        let mut total: u8 = 0;
 
+        let stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&from).expect("No_StakeSlot");
         for i in 0..self.slot.len() {
             if let Some(slot) = self.slot.get(i) {
                 if slot_temp.ship_type == slot.ship_type {
-                    let stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&from).expect("No_StakeSlot");
-                    let stake_slot: StakeSlot = stake_slot_vec[i as usize].clone();
-
-                    if stake_slot.token_id != "".to_string(){
+                    total += 1;
+                    if stake_slot_vec.get(i as usize).is_some_and(|stake_slot| !stake_slot.token_id.is_empty()) {
                         num += 1;
                     }
                 }                
             }
         }
 
-        if slot_temp.ship_type == TYPE_SHIP_D && num == 32 {
-            extra = 5;
-        }
-
-        if slot_temp.ship_type == TYPE_SHIP_C && num == 16 {
-            extra = 10;
-        }
-
-        if slot_temp.ship_type == TYPE_SHIP_B && num == 8 {
-            extra = 15;
+        // the bonus needs every subtype of the type, new subtypes included
+        if num < total {
+            return 0;
         }
 
-        if slot_temp.ship_type == TYPE_SHIP_A && num == 4 {
-            extra = 20;
-        }
-        extra
+        self.collect_bonus.get(&slot_temp.ship_type).unwrap_or(0)
     }
 }
\ No newline at end of file
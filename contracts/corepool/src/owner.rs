@@ -1,5 +1,40 @@
 use crate::*;
 
+// the state before rented, locked and leveled ships were tracked
+#[derive(BorshDeserialize)]
+pub struct ContractV0 {
+    owner_id: AccountId,
+    burned_addr: AccountId,
+    token_tia: AccountId,
+    usn: AccountId,
+    spaceship: AccountId,
+    boxmall: AccountId,
+    slot: Vector<Slot>,
+    start_block: u64,
+    end_block: u64,
+    last_reward_block: u64,
+    total_capacity: u32,
+    total_supply: u128,
+    per_block_reward: u128,
+    total_period: u32,
+    decay_period: u64,
+    decay_rate: u64,
+    reward_rate: u64,
+    format_rate: u64,
+    slot_fee2: u128,
+    slot_fee3: u128,
+    reward_per_token_stored: u128,
+    user_reward_per_token_paid: LookupMap<AccountId, u128>,
+    rewards: LookupMap<AccountId, u128>,
+    slot_info: LookupMap<AccountId, Vec<StakeSlot>>,
+    balance_info: LookupMap<AccountId, u128>,
+    balance_buffer: LookupMap<AccountId, u128>,
+    capacity_buffer: LookupMap<AccountId, u32>,
+    capacity_info: LookupMap<AccountId, u32>,
+    decay_table: Vec<u128>,
+    balance: Balance,
+}
+
 impl Contract {
     pub fn assert_owner(&self) {
         require!(
@@ -21,15 +56,51 @@ impl Contract {
 
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have, you need to implement migration from old state 
-    /// (keep the old struct with different name to deserialize it first).
-    /// After migration goes live, revert back to this implementation for next updates.
+    /// Migrates from ContractV0, ships staked before keep counting with the capacity of their token id.
+    /// After migration goes live, revert back to the NOOP implementation for next updates.
     #[init(ignore_state)]
     #[private]
     pub fn migrate() -> Self {
-        let contract: Contract = env::state_read().expect("ERR_NOT_INIT");
-        contract
+        let old: ContractV0 = env::state_read().expect("ERR_NOT_INIT");
+        Contract {
+            owner_id: old.owner_id,
+            burned_addr: old.burned_addr,
+            token_tia: old.token_tia,
+            usn: old.usn,
+            spaceship: old.spaceship,
+            boxmall: old.boxmall,
+            slot: old.slot,
+            start_block: old.start_block,
+            end_block: old.end_block,
+            last_reward_block: old.last_reward_block,
+            total_capacity: old.total_capacity,
+            total_supply: old.total_supply,
+            per_block_reward: old.per_block_reward,
+            total_period: old.total_period,
+            decay_period: old.decay_period,
+            decay_rate: old.decay_rate,
+            reward_rate: old.reward_rate,
+            format_rate: old.format_rate,
+            slot_fee2: old.slot_fee2,
+            slot_fee3: old.slot_fee3,
+            reward_per_token_stored: old.reward_per_token_stored,
+            user_reward_per_token_paid: old.user_reward_per_token_paid,
+            rewards: old.rewards,
+            slot_info: old.slot_info,
+            balance_info: old.balance_info,
+            balance_buffer: old.balance_buffer,
+            capacity_buffer: old.capacity_buffer,
+            capacity_info: old.capacity_info,
+            decay_table: old.decay_table,
+            balance: old.balance,
+
+            ship_owners: LookupMap::new(StorageKey::ShipOwner),
+            stake_times: LookupMap::new(StorageKey::StakeTime),
+            locked_ships: LookupSet::new(StorageKey::LockedShip),
+            ship_stakers: LookupMap::new(StorageKey::ShipStaker),
+            ship_capacity: LookupMap::new(StorageKey::ShipCapacity),
+            staked_capacity: LookupMap::new(StorageKey::StakedCapacity),
+        }
     }
 
     /* ========== GOVERNANCE ========== */
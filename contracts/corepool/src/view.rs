@@ -60,6 +60,11 @@ impl Contract {
     }
 
     /* ========== VIEW FUNCTION ========== */
+    // spaceship asks before the owner releases a lock this pool may still hold
+    pub fn is_locked_ship(&self, token_id: TokenId) -> bool {
+        self.locked_ships.contains(&token_id)
+    }
+
     pub fn get_slot_list( &self, from: AccountId) -> Vec<SlotList> {
         let mut list: Vec<SlotList> = vec![];
 
@@ -83,8 +88,7 @@ impl Contract {
             if token_id != "".to_string(){
                 let ship_type = self.internal_get_ship_type_by_token_id(token_id.clone());
                 let ship_subtype = self.internal_get_ship_subtype_by_token_id(token_id.clone());
-                let ship_capacity = self.internal_get_ship_capacity_by_token_id(token_id.clone());
-                slot_list_node.capacity = ship_capacity as u32;
+                slot_list_node.capacity = self.internal_staked_capacity(&token_id);
                 slot_list_node.ship_type = ship_type;
                 slot_list_node.ship_subtype = ship_subtype;
             }
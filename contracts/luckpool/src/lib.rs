@@ -15,8 +15,6 @@ use near_contract_standards::non_fungible_token::{
 };
 use near_contract_standards::non_fungible_token::enumeration::NonFungibleTokenEnumeration;
 use near_contract_standards::non_fungible_token::core::NonFungibleTokenResolver;
-use rand::{Rng, SeedableRng};
-use rand::rngs::StdRng;
 
 mod view;
 mod utils;
@@ -28,6 +26,7 @@ pub mod sortition_sum_tree;
 use mynft::MyNonFungibleToken;
 pub use crate::utils::*;
 pub use crate::events::*;
+pub use random::*;
 
 pub const RATE_DENOMINATOR: u8 = 100;
 pub const TGAS: u64 = 1_000_000_000_000;
@@ -103,7 +102,8 @@ pub struct Contract {
     duration: u64,
 
     id: u64,
-    shift: usize,
+    // bumped on every draw so draws in the same block differ
+    random_nonce: u64,
     reward: u128,
     settle_reward: u128,
     claimed_reward: u128,
@@ -130,7 +130,7 @@ impl Contract {
             duration: DEFAULT_DURATION_IN_SEC, // 七天，单位秒
 
             id: 0,
-            shift: 0,
+            random_nonce: 0,
             reward: 0,
             settle_reward: 0,
             claimed_reward: 0,
@@ -436,8 +436,7 @@ impl MyNonFungibleToken {
         if 0 == bound.0 {
             AccountId::new_unchecked("00".to_string())
         } else {
-            let value = self.random(random_number, bound.0) as u128;
-            self.sortition_sum_trees.draw(TREE_KEY.to_string(), value)
+            self.sortition_sum_trees.draw(TREE_KEY.to_string(), random_number)
         }
     }
 
@@ -468,28 +467,6 @@ impl MyNonFungibleToken {
             self.balances.insert(to.clone(), to_balance);
         }
     }
-
-    pub fn random(&self, lower_bound: u128, mut upper_bound: u128) -> u64 {
-        if lower_bound >= upper_bound {
-            upper_bound += 1
-        }
-
-        let seeds: Vec<u8> = env::random_seed();
-        let mut seed: u128 = 0;
-
-        for i in lower_bound..upper_bound {
-            let mut temp_seed: u128 = 0;
-            for j in 0..8 {
-                temp_seed = temp_seed * 10 + seeds[(i * 4 + j) as usize] as u128;
-            }
-            seed += temp_seed;
-        }
-
-        let mut r = StdRng::seed_from_u64(seed as u64);
-        let rnd: u64 = r.gen();
-
-        rnd
-    }
 }
 
 impl NonFungibleTokenCore for MyNonFungibleToken {
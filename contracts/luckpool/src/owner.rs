@@ -68,8 +68,10 @@ impl Contract {
     }
 
     #[private]
-    pub fn draw(&mut self, index: u64, reward: u128, rate: u8) -> (AccountId, u128) {
-        let winner = self.tokens.draw(index as u128);
+    pub fn draw(&mut self, reward: u128, rate: u8) -> (AccountId, u128) {
+        self.random_nonce += 1;
+        let rnd = random_u64(DOMAIN_LOTTERY, self.random_nonce);
+        let winner = self.tokens.draw(rnd as u128);
 
         if winner == AccountId::new_unchecked("00".to_string()) {
             return (AccountId::new_unchecked("00".to_string()), 0);
@@ -96,9 +98,9 @@ impl Contract {
         // 本次应发奖励
         self.reward = self.reward + self.claimed_reward - self.settle_reward;
         let reward: u128 = self.reward;
-        let (winner1, reward1) = self.draw(index, reward, self.reward_rate1);
-        let (winner2, reward2) = self.draw(index, reward, self.reward_rate2);
-        let (winner3, reward3) = self.draw(index, reward, self.reward_rate3);
+        let (winner1, reward1) = self.draw(reward, self.reward_rate1);
+        let (winner2, reward2) = self.draw(reward, self.reward_rate2);
+        let (winner3, reward3) = self.draw(reward, self.reward_rate3);
 
         round.winner1 = winner1;
         round.winner2 = winner2;
@@ -18,6 +18,21 @@ pub enum Event<'a> {
         num: u64,
         token_ids: Vec<String>,
     },
+    OpenRequest {
+        caller_id: &'a AccountId,
+        request_id: u64,
+        token_ids: Vec<String>,
+    },
+    OpenRefund {
+        caller_id: &'a AccountId,
+        request_id: u64,
+        token_ids: Vec<String>,
+    },
+    OpenRestore {
+        caller_id: &'a AccountId,
+        request_id: u64,
+        token_ids: Vec<String>,
+    },
 }
 
 impl Event<'_> {
@@ -67,4 +82,16 @@ mod tests {
         );
     }
 
+    #[test]
+    fn event_open_request() {
+        let caller_id = &alice();
+        let token_ids:Vec<String> = vec!["1:1".to_string(),"2:2".to_string()];
+
+        Event::OpenRequest { caller_id, request_id: 3, token_ids }.emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"magicbox","version":"1.0.0","event":"open_request","data":[{"caller_id":"alice","request_id":3,"token_ids":["1:1","2:2"]}]}"#
+        );
+    }
+
 }
\ No newline at end of file
@@ -15,11 +15,9 @@ use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     assert_one_yocto, env, near_bindgen, require, AccountId, BorshStorageKey, PanicOnDefault,
-    PromiseOrValue, Gas, ext_contract, Promise, log
+    PromiseOrValue, Gas, ext_contract, Promise, PromiseResult, log
 };
 use rand_distr::{Normal, Distribution};
-use rand::{Rng,SeedableRng};
-use rand::rngs::StdRng;
 
 mod view;
 mod owner;
@@ -29,6 +27,7 @@ mod events;
 
 pub use crate::utils::*;
 pub use crate::events::*;
+pub use random::*;
 // 
 pub type BoxType = u8;
 pub type AccountIdIndex = String;
@@ -56,6 +55,16 @@ pub struct Open {
     capacity: u32,
 }
 
+/// Boxes locked by `request_open`, waiting for `fulfill_open` or `refund_open`
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OpenRequest {
+    pub request_id: u64,
+    pub owner_id: AccountId,
+    pub token_ids: Vec<TokenId>,
+    pub block_height: u64,
+}
+
 #[derive(Serialize, Deserialize,Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct MagicBox {
@@ -77,6 +86,9 @@ pub struct ProbInfo {
 
 pub const TGAS: u64 = 1_000_000_000_000;
 pub const GAS_FOR_MINT_SHIP: Gas = Gas(180 * TGAS);
+pub const GAS_FOR_RESOLVE_OPEN: Gas = Gas(20 * TGAS);
+// an unfulfilled open request can be refunded after this many blocks
+pub const DEFAULT_OPEN_TIMEOUT_BLOCKS: u64 = 1000;
 #[ext_contract(ext_spaceship)]
 pub trait Spaceship {
     fn batch_mint(
@@ -87,6 +99,11 @@ pub trait Spaceship {
     );
 }
 
+#[ext_contract(ext_self)]
+pub trait OpenResolver {
+    fn resolve_open(&mut self, request: OpenRequest, sender_id: AccountId, deposit: U128) -> bool;
+}
+
 #[derive(BorshStorageKey, BorshSerialize)]
 pub(crate) enum StorageKey {
     TokensPerOwner { account_hash: Vec<u8> },
@@ -99,6 +116,10 @@ pub(crate) enum StorageKey {
     TypeBurnBalance,
     OwnedTokens,
     OwnedTokensIndex,
+    OpenRequests,
+    RestoredOpens,
+    SubTypeCount,
+    TierTypeProb,
 }
 
 #[near_bindgen]
@@ -112,7 +133,8 @@ pub struct Contract {
     spaceship_contract_id: AccountId,
     next_token_idx: u64,
     
-    shift: usize, 
+    // bumped on every roll so rolls in the same block differ
+    random_nonce: u64,
     // numLimit.  
     num_limit: u8, //default: 10,
 
@@ -134,6 +156,17 @@ pub struct Contract {
     
     // type_prob
     type_prob: ProbInfo,
+
+    // request_id => boxes locked for a two-phase open
+    open_requests: UnorderedMap<u64, OpenRequest>,
+    next_open_request_id: u64,
+    open_timeout_blocks: u64,
+    // request_id => opens whose ship minting failed and got their boxes back
+    restored_opens: UnorderedMap<u64, OpenRequest>,
+    // ship type => number of subtypes, pushed by spaceship on every tier change
+    sub_type_count: UnorderedMap<u8, u8>,
+    // ship type => [box U prob, box S prob] of tiers added to the registry, rolled after D
+    tier_type_prob: UnorderedMap<u8, Vec<u32>>,
 }
 
 #[near_bindgen]
@@ -141,7 +174,7 @@ impl Contract {
     #[init]
     pub fn new(owner_id: AccountId, spaceship_contract_id: AccountId) -> Self {
         assert!(!env::state_exists(), "Already initialized");
-        Contract {
+        let mut this = Contract {
             owner_id: owner_id.clone().into(),
             tokens: MyNonFungibleToken::new(
                 StorageKey::NonFungibleToken,
@@ -164,7 +197,7 @@ impl Contract {
             ),
             spaceship_contract_id,
             next_token_idx: 0,
-            shift: 0,
+            random_nonce: 0,
             num_limit:  5, // default: 10. Currently set to 5. keep same value as boxmall
             burn_balance: 0,
 
@@ -187,56 +220,154 @@ impl Contract {
                 box_s_ship_b: 400,
                 box_s_ship_c: 1500,
                 box_s_ship_d: 8000},
-        }
- 
+
+            open_requests: UnorderedMap::new(StorageKey::OpenRequests),
+            next_open_request_id: 0,
+            open_timeout_blocks: DEFAULT_OPEN_TIMEOUT_BLOCKS,
+            restored_opens: UnorderedMap::new(StorageKey::RestoredOpens),
+            sub_type_count: UnorderedMap::new(StorageKey::SubTypeCount),
+            tier_type_prob: UnorderedMap::new(StorageKey::TierTypeProb),
+        };
+        this.internal_init_sub_type_count();
+        this
     }
 
     // MBX-00-02
+    /// Phase one of opening: lock the boxes in this contract and record the request block.
+    /// Ship types are rolled later in `fulfill_open`, from a block seed unknown at this point.
     #[payable]
-    pub fn open_box(&mut self, token_ids: Vec<TokenId>) {
+    pub fn request_open(&mut self, token_ids: Vec<TokenId>) -> u64 {
         assert_one_yocto();
         require!(token_ids.len() > 0 && token_ids.len() <= self.num_limit.into(), "invalid open num");
         let owner_id = env::predecessor_account_id();
 
-        let mut ship_types: Vec<String> = vec![];
-        let mut ship_sub_types: Vec<String> = vec![];
-        let mut open_list: Vec<Open> = vec![];
-
         for i in 0..token_ids.len() {
             let token_id: TokenId = token_ids[i].clone();
             let token = self.tokens.nft_token(token_id.clone()).expect("ERR_BOX_NOT_EXIST");
             require!(token.owner_id==owner_id, "ERR_BOX_NOT_EXIST");
-    
+
+            self.tokens.internal_transfer(&owner_id, &env::current_account_id(), &token_id, None, None);
+        }
+
+        let request_id = self.next_open_request_id;
+        self.next_open_request_id += 1;
+        self.open_requests.insert(&request_id, &OpenRequest {
+            request_id,
+            owner_id: owner_id.clone(),
+            token_ids: token_ids.clone(),
+            block_height: env::block_height(),
+        });
+
+        Event::OpenRequest {
+            caller_id: &owner_id,
+            request_id,
+            token_ids,
+        }.emit();
+
+        request_id
+    }
+
+    // MBX-00-16
+    /// Phase two of opening: roll the ships of a pending request, burn its boxes and mint the ships.
+    /// Must be called in a later block than the request and before the request times out.
+    /// The attached deposit pays the spaceship storage of the minted ships.
+    #[payable]
+    pub fn fulfill_open(&mut self, request_id: u64) {
+        let request = self.open_requests.get(&request_id).expect("ERR_OPEN_REQUEST_NOT_EXIST");
+        let block_height = env::block_height();
+        require!(block_height > request.block_height, "ERR_OPEN_REQUEST_NOT_READY");
+        require!(block_height <= request.block_height + self.open_timeout_blocks, "ERR_OPEN_REQUEST_EXPIRED");
+        self.open_requests.remove(&request_id);
+
+        let mut ship_types: Vec<String> = vec![];
+        let mut ship_sub_types: Vec<String> = vec![];
+
+        for token_id in request.token_ids.iter() {
             let box_type: BoxType = self.get_type_by_token_id(token_id.clone());
 
-            
-            // get random data. 
-            let rnd: u64 = self.internal_random();
+            // get random data.
+            let rnd: u64 = self.internal_random(DOMAIN_TYPE);
             let ship_type = format!("{}", self.internal_random_spaceship_type(rnd, box_type));
             let ship_sub_type = format!("{}", self.internal_random_spaceship_subtype(ship_type.parse::<u8>().unwrap()));
 
             ship_types.push(ship_type);
             ship_sub_types.push(ship_sub_type);
-    
-            self.internal_burn(token_id.clone());
+
+            self.internal_burn(&env::current_account_id(), token_id.clone());
         }
 
-        // cross-contract call mint spaceship
+        Event::OpenBox {
+            caller_id: &request.owner_id,
+            num: request.token_ids.len() as u64,
+            token_ids: request.token_ids.clone(),
+        }.emit();
+
+        // cross-contract call mint spaceship, the boxes come back if it fails
         ext_spaceship::batch_mint(
-            owner_id.clone(),
+            request.owner_id.clone(),
             ship_types,
             ship_sub_types,
             self.spaceship_contract_id.clone(),
-            0,
+            env::attached_deposit(),
             GAS_FOR_MINT_SHIP,
+        ).then(ext_self::resolve_open(
+            request,
+            env::predecessor_account_id(),
+            U128(env::attached_deposit()),
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_OPEN,
+        ));
+    }
+
+    /// Give the burned boxes back to their owner if spaceship failed to mint the ships,
+    /// the storage deposit goes back to the caller of `fulfill_open`
+    #[private]
+    pub fn resolve_open(&mut self, request: OpenRequest, sender_id: AccountId, deposit: U128) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => true,
+            PromiseResult::Failed => {
+                if deposit.0 > 0 {
+                    Promise::new(sender_id).transfer(deposit.0);
+                }
+                let burn_id = AccountId::new_unchecked("".to_string());
+                for token_id in request.token_ids.iter() {
+                    self.tokens.internal_transfer(&burn_id, &request.owner_id, token_id, None, None);
+                }
+                self.restored_opens.insert(&request.request_id, &request);
+
+                Event::OpenRestore {
+                    caller_id: &request.owner_id,
+                    request_id: request.request_id,
+                    token_ids: request.token_ids,
+                }.emit();
+                false
+            }
+        }
+    }
+
+    // MBX-00-17
+    /// Give the locked boxes back to their owner once a request has timed out unfulfilled.
+    /// Approvals are dropped when the boxes are locked and are not set again here,
+    /// boxes have none as magicbox does not implement `nft_approve`.
+    pub fn refund_open(&mut self, request_id: u64) {
+        let request = self.open_requests.get(&request_id).expect("ERR_OPEN_REQUEST_NOT_EXIST");
+        require!(
+            env::block_height() > request.block_height + self.open_timeout_blocks,
+            "ERR_OPEN_REQUEST_NOT_EXPIRED"
         );
+        self.open_requests.remove(&request_id);
 
-        Event::OpenBox {
-            caller_id: &owner_id.clone(),
-            num: token_ids.len() as u64,
-            token_ids,
-        }.emit();
+        for token_id in request.token_ids.iter() {
+            self.tokens.internal_transfer(&env::current_account_id(), &request.owner_id, token_id, None, None);
+        }
 
+        Event::OpenRefund {
+            caller_id: &request.owner_id,
+            request_id,
+            token_ids: request.token_ids,
+        }.emit();
     }
 
     // MBX-00-03
@@ -371,8 +502,8 @@ impl Contract {
     }
  
     // MBX-00-05
-    pub fn internal_burn(&mut self, token_id: TokenId ) {
-        self.nft_transfer(AccountId::new_unchecked("".to_string()),token_id,None,None);
+    pub fn internal_burn(&mut self, owner_id: &AccountId, token_id: TokenId ) {
+        self.tokens.internal_transfer(owner_id, &AccountId::new_unchecked("".to_string()), &token_id, None, None);
     }
 
 }
@@ -390,7 +521,8 @@ impl Contract {
  
     // MBX-00-07
     pub fn set_spaceship_type_prob(&mut self, box_type: BoxType, prob_a: u32, prob_b: u32, prob_c: u32, prob_d: u32) {
-        require!(u64::from( prob_a + prob_b + prob_c + prob_d ) > PROB_DENOMINATOR, "MagicBox: prob is not enough");
+        let tier_prob: u32 = self.tier_type_prob.values().map(|probs| if box_type == TYPE_U { probs[0] } else { probs[1] }).sum();
+        require!(u64::from( prob_a + prob_b + prob_c + prob_d + tier_prob ) > PROB_DENOMINATOR, "MagicBox: prob is not enough");
         require!( box_type == TYPE_U || box_type == TYPE_S, "MagicBox: type is invalid");
 
         if box_type == TYPE_U {
@@ -410,6 +542,25 @@ impl Contract {
         
     }
 
+    // MBX-00-19
+    /// roll probabilities of a tier added to the spaceship registry, zero for both box types stops rolling it
+    pub fn set_tier_type_prob(&mut self, ship_type: u8, prob_u: u32, prob_s: u32) {
+        require!(env::predecessor_account_id() == self.owner_id, "ERR_NOT_ALLOWED");
+        require!(
+            ![TYPE_SHIP_A, TYPE_SHIP_B, TYPE_SHIP_C, TYPE_SHIP_D].contains(&ship_type) && self.sub_type_count.get(&ship_type).is_some(),
+            "ERR_ILLEGAL_SHIP_TYPE"
+        );
+        if prob_u == 0 && prob_s == 0 {
+            self.tier_type_prob.remove(&ship_type);
+        } else {
+            self.tier_type_prob.insert(&ship_type, &vec![prob_u, prob_s]);
+        }
+    }
+
+    pub fn get_tier_type_probs(&self) -> Vec<(u8, Vec<u32>)> {
+        self.tier_type_prob.to_vec()
+    }
+
     // MBX-00-08
     pub fn balance_of( &self, account_id: AccountId ) -> u32 {
         let tokens_for_owner = self.nft_tokens_for_owner(account_id,  Some(U128(0)), Some(10000000 as u64));
@@ -463,45 +614,35 @@ impl Contract {
 
     // MBX-00-15
     pub fn internal_random_spaceship_subtype( &mut self, ship_type: u8 ) -> u8{
-        let mut sub_type: u8 = 0;
-        let rnd: u64 = self.internal_random();
-        if ship_type == TYPE_SHIP_A {
-            sub_type = 1 + (rnd % 4) as u8; // 4 subtypes for A
-        }
-        if ship_type == TYPE_SHIP_B {
-            sub_type = 1 + (rnd % 8) as u8; // 8 subtypes for B
-        }
-        if ship_type == TYPE_SHIP_C {
-            sub_type = 1 + (rnd % 16) as u8; // 16 subtypes for C
-        }
-        if ship_type == TYPE_SHIP_D {
-            sub_type = 1 + (rnd % 32) as u8; // 32 subtypes for D
-        }
-        sub_type
+        let count = self.sub_type_count.get(&ship_type).expect("ERR_ILLEGAL_SHIP_TYPE");
+        let rnd: u64 = self.internal_random(DOMAIN_SUBTYPE);
+        1 + (rnd % count as u64) as u8
     }
 
-    pub fn internal_random( &mut self ) -> u64 {
-        // get random data
-        let seeds: Vec<u8> = env::random_seed();
-        let mut seed: u128 = 0;
-
-        if self.shift > 24 {
-            self.shift = 0;
-        }
-
-        for i in self.shift..self.shift+8{
-            seed = seed * 10 + seeds[i] as u128;
-        }
-        self.shift += 2;
-
-        let mut r = StdRng::seed_from_u64(seed as u64); // <- Here we set the seed
-        let rnd: u64 = r.gen();
+    // MBX-00-18
+    /// keep the subtype counts in sync with the spaceship tier registry
+    #[allow(unused_variables)]
+    pub fn on_ship_tier_updated(&mut self, ship_type: u8, sub_type_count: u8, upgrade_target: Option<u8>) {
+        require!(env::predecessor_account_id() == self.spaceship_contract_id, "ERR_NOT_ALLOWED");
+        self.sub_type_count.insert(&ship_type, &sub_type_count);
+    }
 
-        rnd // random number
+    /// Next random number for `domain`, every call consumes a fresh nonce
+    pub fn internal_random( &mut self, domain: &str ) -> u64 {
+        self.random_nonce += 1;
+        random_u64(domain, self.random_nonce)
     }
 }
 
 impl Contract {
+   /// subtypes of the tiers spaceship starts with, later pushed by spaceship on every tier change
+   pub fn internal_init_sub_type_count(&mut self) {
+      self.sub_type_count.insert(&TYPE_SHIP_A, &4);
+      self.sub_type_count.insert(&TYPE_SHIP_B, &8);
+      self.sub_type_count.insert(&TYPE_SHIP_C, &16);
+      self.sub_type_count.insert(&TYPE_SHIP_D, &32);
+   }
+
    // MBX-00-06
    pub fn internal_random_spaceship_type( &self, random: u64, box_type: BoxType) -> u8 {
       let prob = random % PROB_DENOMINATOR;
@@ -538,6 +679,16 @@ impl Contract {
           ship_type = TYPE_SHIP_D;
       }
 
+      // tiers added to the registry roll after D
+      let mut block_tier = block_d;
+      for (tier_type, probs) in self.tier_type_prob.iter() {
+          let tier_prob = if box_type == TYPE_U { probs[0] } else if box_type == TYPE_S { probs[1] } else { 0 };
+          if ship_type == 0 && u64::from(block_tier) <= prob && prob < (block_tier + tier_prob).into() {
+              ship_type = tier_type;
+          }
+          block_tier += tier_prob;
+      }
+
       ship_type
    }
 }
\ No newline at end of file
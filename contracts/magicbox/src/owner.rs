@@ -1,5 +1,23 @@
 use crate::*;
 
+// the state before two-phase opens and the tier registry copy were added
+#[derive(BorshDeserialize)]
+pub struct ContractV0 {
+    owner_id: AccountId,
+    tokens: MyNonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    spaceship_contract_id: AccountId,
+    next_token_idx: u64,
+    shift: usize,
+    num_limit: u8,
+    burn_balance: u64,
+    type_balance: UnorderedMap<BoxType, u128>,
+    type_burn_balance: UnorderedMap<BoxType, u128>,
+    owned_tokens: UnorderedMap<AccountIdIndex, TokenId>,
+    owned_tokens_index: UnorderedMap<TokenId, u64>,
+    type_prob: ProbInfo,
+}
+
 impl Contract {
     pub fn assert_owner(&self) {
         require!(
@@ -18,17 +36,47 @@ impl Contract {
         self.owner_id = owner_id;
     }
 
+    #[payable]
+    pub fn set_open_timeout_blocks(&mut self, blocks: u64) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(blocks > 0, "ERR_INVALID_TIMEOUT");
+        self.open_timeout_blocks = blocks;
+    }
+
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have, you need to implement migration from old state 
-    /// (keep the old struct with different name to deserialize it first).
-    /// After migration goes live, revert back to this implementation for next updates.
+    /// Migrates from ContractV0, the open requests start empty and the subtypes take the spaceship defaults.
+    /// After migration goes live, revert back to the NOOP implementation for next updates.
     #[init(ignore_state)]
     #[private]
     pub fn migrate() -> Self {
-        let contract: Contract = env::state_read().expect("ERR_NOT_INIT");
-        contract
+        let old: ContractV0 = env::state_read().expect("ERR_NOT_INIT");
+        let mut this = Contract {
+            owner_id: old.owner_id,
+            tokens: old.tokens,
+            metadata: old.metadata,
+            spaceship_contract_id: old.spaceship_contract_id,
+            next_token_idx: old.next_token_idx,
+            // rolls are no longer derived from the shift, the nonce only has to keep moving
+            random_nonce: old.shift as u64,
+            num_limit: old.num_limit,
+            burn_balance: old.burn_balance,
+            type_balance: old.type_balance,
+            type_burn_balance: old.type_burn_balance,
+            owned_tokens: old.owned_tokens,
+            owned_tokens_index: old.owned_tokens_index,
+            type_prob: old.type_prob,
+
+            open_requests: UnorderedMap::new(StorageKey::OpenRequests),
+            next_open_request_id: 0,
+            open_timeout_blocks: DEFAULT_OPEN_TIMEOUT_BLOCKS,
+            restored_opens: UnorderedMap::new(StorageKey::RestoredOpens),
+            sub_type_count: UnorderedMap::new(StorageKey::SubTypeCount),
+            tier_type_prob: UnorderedMap::new(StorageKey::TierTypeProb),
+        };
+        this.internal_init_sub_type_count();
+        this
     }
 }
 
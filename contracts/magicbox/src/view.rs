@@ -25,4 +25,29 @@ impl Contract {
             burn_balance: self.burn_balance,
         }
     }
-}
\ No newline at end of file
+
+    pub fn get_open_request(&self, request_id: u64) -> Option<OpenRequest> {
+        self.open_requests.get(&request_id)
+    }
+
+    pub fn get_open_requests(&self, from_index: u64, limit: u64) -> Vec<OpenRequest> {
+        self.open_requests
+            .values()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    /// opens whose ship minting failed, their boxes were given back
+    pub fn get_restored_opens(&self, from_index: u64, limit: u64) -> Vec<OpenRequest> {
+        self.restored_opens
+            .values()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    pub fn get_open_timeout_blocks(&self) -> u64 {
+        self.open_timeout_blocks
+    }
+}
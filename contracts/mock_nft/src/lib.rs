@@ -92,7 +92,7 @@ impl Contract {
         }
     }
 
-    pub fn nft_payout(
+    pub fn auction_distribute(
         &mut self,
         receiver_id: AccountId,
         token_id: TokenId,
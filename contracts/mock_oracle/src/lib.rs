@@ -0,0 +1,49 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedMap;
+use near_sdk::json_types::U128;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, require, AccountId, PanicOnDefault};
+
+/// time weighted average price of an asset, `timestamp` in seconds
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TwapPrice {
+    pub price: U128,
+    pub timestamp: u64,
+}
+
+#[near_bindgen]
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+pub struct Contract {
+    prices: UnorderedMap<AccountId, TwapPrice>,
+    available: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        Self {
+            prices: UnorderedMap::new(b"p".to_vec()),
+            available: true,
+        }
+    }
+
+    /// anyone can feed the mock, the price is stamped with the current block time
+    pub fn set_price(&mut self, asset_id: AccountId, price: U128) {
+        self.prices.insert(&asset_id, &TwapPrice {
+            price,
+            timestamp: env::block_timestamp() / 10u64.pow(9),
+        });
+    }
+
+    /// lets tests simulate an oracle that does not answer
+    pub fn set_available(&mut self, available: bool) {
+        self.available = available;
+    }
+
+    pub fn get_twap_price(&self, asset_id: AccountId) -> TwapPrice {
+        require!(self.available, "ERR_ORACLE_UNAVAILABLE");
+        self.prices.get(&asset_id).expect("ERR_NO_PRICE")
+    }
+}
@@ -1,6 +1,7 @@
 use near_contract_standards::non_fungible_token::{TokenId};
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::non_fungible_token::core::NonFungibleTokenReceiver;
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApprovalReceiver;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::json_types::U128;
@@ -14,6 +15,7 @@ use near_sdk::{
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     sender_contract_id: AccountId,
+    locked_ships: Vec<TokenId>,
  }
 
 #[near_bindgen]
@@ -23,14 +25,60 @@ impl Contract {
         require!(!env::state_exists(), "Already initialized");
         Contract {
             sender_contract_id,
+            locked_ships: vec![],
          }
     }
 
     #[allow(unused_variables)]
     pub fn batch_register_ships(&mut self, token_ids: Vec<String>, token_owner_id: AccountId) {
         require!(env::predecessor_account_id()==self.sender_contract_id, "ERR_NOT_ALLOWED");
+        // lets tests simulate a failed registration
+        require!(!token_owner_id.as_str().starts_with("reject"), "ERR_MOCK_REJECTED");
         log!("[MOCK_RECEIVER] batch_register_ships");
     }
+
+    #[allow(unused_variables)]
+    pub fn nft_on_lock(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        require!(env::predecessor_account_id()==self.sender_contract_id, "ERR_NOT_ALLOWED");
+        log!("[MOCK_RECEIVER] nft_on_lock, msg: {}", msg);
+        // lets tests simulate a pool refusing the ship
+        if msg == "reject" {
+            return PromiseOrValue::Value(false);
+        }
+        self.locked_ships.push(token_id);
+        PromiseOrValue::Value(true)
+    }
+
+    pub fn is_locked_ship(&self, token_id: TokenId) -> bool {
+        self.locked_ships.contains(&token_id)
+    }
+
+    // lets tests simulate a pool that dropped the ship while spaceship still holds the lock
+    pub fn release_ship(&mut self, token_id: TokenId) {
+        self.locked_ships.retain(|id| *id != token_id);
+    }
+
+    #[allow(unused_variables)]
+    pub fn on_ship_tier_updated(&mut self, ship_type: u8, sub_type_count: u8, upgrade_target: Option<u8>) {
+        require!(env::predecessor_account_id()==self.sender_contract_id, "ERR_NOT_ALLOWED");
+        log!("[MOCK_RECEIVER] on_ship_tier_updated, ship_type: {}, sub_type_count: {}", ship_type, sub_type_count);
+    }
+
+    pub fn nft_on_rental_expired(&mut self, token_id: TokenId) {
+        require!(env::predecessor_account_id()==self.sender_contract_id, "ERR_NOT_ALLOWED");
+        log!("[MOCK_RECEIVER] nft_on_rental_expired, token_id: {}", token_id);
+    }
+
+    pub fn on_ship_level_up(&mut self, token_id: TokenId, effective_capacity: u32) {
+        require!(env::predecessor_account_id()==self.sender_contract_id, "ERR_NOT_ALLOWED");
+        log!("[MOCK_RECEIVER] on_ship_level_up, token_id: {}, effective_capacity: {}", token_id, effective_capacity);
+    }
 }
 
 #[near_bindgen]
@@ -53,6 +101,22 @@ impl NonFungibleTokenReceiver for Contract {
     }
 }
 
+#[near_bindgen]
+#[allow(unused_variables)]
+impl NonFungibleTokenApprovalReceiver for Contract {
+    fn nft_on_approve(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        approval_id: u64,
+        msg: String,
+    ) -> PromiseOrValue<String> {
+        require!(env::predecessor_account_id()==self.sender_contract_id, "ERR_NOT_ALLOWED");
+        log!("[MOCK_RECEIVER] nft_on_approve, approval_id: {}, msg: {}", approval_id, msg);
+        PromiseOrValue::Value(msg)
+    }
+}
+
 #[near_bindgen]
 #[allow(unused_variables)]
 impl FungibleTokenReceiver for Contract {
@@ -0,0 +1,80 @@
+//! Domain separated randomness shared by the contracts that roll ships and lotteries.
+
+use near_sdk::{env, log};
+
+/// Domains keep rolls made for different purposes independent, even when they
+/// are derived from the same block seed and nonce.
+pub const DOMAIN_CAPACITY: &str = "capacity";
+pub const DOMAIN_SUBTYPE: &str = "subtype";
+pub const DOMAIN_TYPE: &str = "type";
+pub const DOMAIN_LOTTERY: &str = "lottery";
+
+/// Derive a random number as the first 8 bytes (little endian) of
+/// `sha256(seed | domain | nonce)`, `nonce` in little endian.
+/// Anyone can recompute a roll from the values logged by `random_u64`.
+pub fn derive_random(seed: &[u8], domain: &str, nonce: u64) -> u64 {
+    let mut input: Vec<u8> = Vec::with_capacity(seed.len() + domain.len() + 8);
+    input.extend_from_slice(seed);
+    input.extend_from_slice(domain.as_bytes());
+    input.extend_from_slice(&nonce.to_le_bytes());
+
+    let hash = env::sha256(&input);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+/// Roll a random number for `domain` from the current block seed, the caller
+/// must pass a nonce it never used before. The derivation trail is logged.
+pub fn random_u64(domain: &str, nonce: u64) -> u64 {
+    let seed: Vec<u8> = env::random_seed();
+    let rnd = derive_random(&seed, domain, nonce);
+
+    log!(
+        "RANDOM: domain: {}, nonce: {}, block: {}, seed: {}, value: {}",
+        domain,
+        nonce,
+        env::block_height(),
+        to_hex(&seed),
+        rnd
+    );
+    rnd
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::test_utils::{get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    #[test]
+    fn test_derive_random_domain_and_nonce() {
+        testing_env!(VMContextBuilder::new().build());
+        let seed = [7u8; 32];
+
+        let rnd = derive_random(&seed, DOMAIN_CAPACITY, 1);
+        assert_eq!(rnd, derive_random(&seed, DOMAIN_CAPACITY, 1));
+        assert_ne!(rnd, derive_random(&seed, DOMAIN_CAPACITY, 2));
+        assert_ne!(rnd, derive_random(&seed, DOMAIN_SUBTYPE, 1));
+    }
+
+    #[test]
+    fn test_random_u64_logs_trail() {
+        testing_env!(VMContextBuilder::new().random_seed([1u8; 32]).build());
+
+        let rnd = random_u64(DOMAIN_TYPE, 3);
+        assert_eq!(rnd, derive_random(&[1u8; 32], DOMAIN_TYPE, 3));
+        assert_eq!(
+            get_logs()[0],
+            format!(
+                "RANDOM: domain: type, nonce: 3, block: 0, seed: {}, value: {}",
+                "01".repeat(32),
+                rnd
+            )
+        );
+    }
+}
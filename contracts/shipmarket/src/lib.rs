@@ -1,5 +1,5 @@
 use std::borrow::Borrow;
-use near_sdk::{near_bindgen, BorshStorageKey, PanicOnDefault, serde_json, env, AccountId, PromiseOrValue, Gas, ext_contract, assert_one_yocto, require, log};
+use near_sdk::{near_bindgen, BorshStorageKey, PanicOnDefault, serde_json, env, AccountId, Balance, Promise, PromiseOrValue, PromiseResult, Gas, ext_contract, assert_one_yocto, require, log};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::collections::{UnorderedMap};
@@ -9,15 +9,16 @@ use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 use near_contract_standards::non_fungible_token::{TokenId};
 use std::collections::HashMap;
-use rand::{Rng,SeedableRng};
-use rand::rngs::StdRng;
 
 mod view;
 mod owner;
 mod utils;
 mod events;
+mod recipe;
 pub use crate::utils::*;
 pub use crate::events::*;
+pub use random::*;
+pub use crate::recipe::*;
 
 pub type  TimeStampSec = u64;
 pub const YOCTO18: u128 = 1_000_000_000_000_000_000;
@@ -25,7 +26,8 @@ pub const TGAS: u64 = 1_000_000_000_000;
 pub const GAS_FOR_NFT_TRANSFER: Gas = Gas(30 * TGAS);
 pub const GAS_FOR_BATCH_TRANSFER: Gas = Gas(30 * TGAS);
 pub const GAS_FOR_BATCH_TRANSFER_CALL: Gas = Gas(35 * TGAS);
-pub const GAS_FOR_SPACESHIP_UPGRADE: Gas = Gas(100 * TGAS);
+pub const GAS_FOR_SPACESHIP_UPGRADE: Gas = Gas(130 * TGAS);
+pub const GAS_FOR_RESOLVE_UPGRADE: Gas = Gas(110 * TGAS);
 
 pub const RATE_DENOMINATOR: u8 = 100;
 
@@ -55,7 +57,22 @@ pub trait Spaceship {
         token_id_2: TokenId, 
         target_sub_type: u8, 
         eng_amount: U128
-    );
+    ) -> bool;
+
+    fn fuse_spaceship(
+        &mut self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+        eng_amount: U128
+    ) -> bool;
+}
+
+#[ext_contract(ext_self)]
+pub trait ShipMarket {
+    fn resolve_spaceship_upgrade(&mut self, sender_id: AccountId, tia_fee: U128, storage_deposit: U128) -> U128;
 }
 
 #[ext_contract(ext_tokentia)]
@@ -81,6 +98,7 @@ pub enum OrderStatus {
 enum TransferCallInfo {
     BuyInfo{ order_id: u64 },
     UpgradeInfo{ token_id_1: TokenId, token_id_2: TokenId, target_sub_type: u8},
+    FuseInfo{ recipe_id: u32, token_ids: Vec<TokenId> },
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
@@ -104,7 +122,11 @@ pub(crate) enum StorageKey {
     BuyOrderMapKey,
     NoTargetMintFeeKey,
     TargetMintFeeKey,
-    TargetSubTypeKey
+    SubTypeCountKey,
+    UpgradeTargetKey,
+    CapacityRangeKey,
+    RecipeKey,
+    ShipStorage,
 }
 
 #[near_bindgen]
@@ -119,7 +141,8 @@ pub struct Contract {
     luck_reward_rate: u8,
     rank_reward_rate: u8,
     fee_rate: u8,
-    shift: usize,
+    // bumped on every roll so rolls in the same block differ
+    random_nonce: u64,
 
     spaceship: AccountId,
     token_tia: AccountId,
@@ -131,10 +154,17 @@ pub struct Contract {
 
     no_target_mint_fee: UnorderedMap<u8, Vec<u128>>, // arr[0] is token_tia fee arr[1] is eng fee
     target_mint_fee: UnorderedMap<u8, Vec<u128>>, // arr[0] is token_tia fee arr[1] is eng fee
-    target_sub_type: UnorderedMap<u8, Vec<u8>>, // array is [min, max] subType range
+    // copy of the spaceship tier registry, pushed by spaceship on every tier change
+    sub_type_count: UnorderedMap<u8, u8>, // ship type => number of subtypes
+    upgrade_target: UnorderedMap<u8, u8>, // material ship type => upgraded ship type
     order_map: UnorderedMap<u64, Order>,
     sell_order_map: UnorderedMap<AccountId, Vec<u64>>,
     buy_order_map: UnorderedMap<AccountId, Vec<u64>>,
+
+    capacity_range: UnorderedMap<u8, CapacityRange>, // ship type => capacity, copy of the spaceship tier registry
+    next_recipe_id: u32,
+    recipes: UnorderedMap<u32, UpgradeRecipe>, // recipe_id => recipe
+    ship_storage: UnorderedMap<AccountId, Balance>, // NEAR passed to spaceship with the user's next upgrade or fusion
 }
 
 #[near_bindgen]
@@ -153,12 +183,7 @@ impl Contract {
         temp_target_mint_fee.insert(&TYPE_C, &vec![300 * YOCTO18, 4 * YOCTO18].into());
         temp_target_mint_fee.insert(&TYPE_D, &vec![150 * YOCTO18, 2 * YOCTO18].into());
 
-        let mut temp_target_sub_type: UnorderedMap<u8, Vec<u8>> = UnorderedMap::new(StorageKey::TargetSubTypeKey);
-        temp_target_sub_type.insert(&TYPE_B, &vec![1, 4]);
-        temp_target_sub_type.insert(&TYPE_C, &vec![1, 8]);
-        temp_target_sub_type.insert(&TYPE_D, &vec![1, 16]);
-
-        Contract {
+        let mut this = Contract {
             owner_id,
 
             //
@@ -169,7 +194,7 @@ impl Contract {
             bank_reward_rate: 5,
             luck_reward_rate: 5,
             rank_reward_rate: 5,
-            shift: 0,
+            random_nonce: 0,
 
             // other contract(account)
             spaceship,
@@ -183,11 +208,19 @@ impl Contract {
 
             no_target_mint_fee: temp_no_target_mint_fee,
             target_mint_fee: temp_target_mint_fee,
-            target_sub_type: temp_target_sub_type,
+            sub_type_count: UnorderedMap::new(StorageKey::SubTypeCountKey),
+            upgrade_target: UnorderedMap::new(StorageKey::UpgradeTargetKey),
             order_map: UnorderedMap::new(StorageKey::OrderMapKey),
             sell_order_map: UnorderedMap::new(StorageKey::SellOrderMapKey),
             buy_order_map: UnorderedMap::new(StorageKey::BuyOrderMapKey),
-        }
+
+            capacity_range: UnorderedMap::new(StorageKey::CapacityRangeKey),
+            next_recipe_id: 0,
+            recipes: UnorderedMap::new(StorageKey::RecipeKey),
+            ship_storage: UnorderedMap::new(StorageKey::ShipStorage),
+        };
+        this.internal_init_tier_registry();
+        this
     }
 
     // 取消出售
@@ -222,6 +255,25 @@ impl Contract {
             }.emit();
         }
     }
+
+    /// NEAR for the spaceship storage of upgraded and fused ships,
+    /// upgrades are paid in TIA and can not carry it themselves
+    #[payable]
+    pub fn deposit_ship_storage(&mut self) {
+        let amount = env::attached_deposit();
+        require!(amount > 0, "ERR_ZERO_DEPOSIT");
+        let account_id = env::predecessor_account_id();
+        let balance = self.ship_storage.get(&account_id).unwrap_or(0);
+        self.ship_storage.insert(&account_id, &(balance + amount));
+    }
+
+    #[payable]
+    pub fn withdraw_ship_storage(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let amount = self.ship_storage.remove(&account_id).expect("ERR_NO_SHIP_STORAGE");
+        Promise::new(account_id).transfer(amount);
+    }
 }
 
 #[near_bindgen]
@@ -257,13 +309,43 @@ impl FungibleTokenReceiver for Contract {
         match info {
             TransferCallInfo::BuyInfo{order_id} => {
                 self.internal_buy_spaceship(sender_id, order_id, amount);
+                PromiseOrValue::Value(U128(0))
             },
             TransferCallInfo::UpgradeInfo{ token_id_1, token_id_2, target_sub_type} => {
-                self.internal_upgrade_spaceship(sender_id, token_id_1, token_id_2, target_sub_type, amount);
+                self.internal_upgrade_spaceship(sender_id, token_id_1, token_id_2, target_sub_type, amount).into()
+            },
+            TransferCallInfo::FuseInfo{ recipe_id, token_ids } => {
+                self.internal_fuse_spaceship(sender_id, recipe_id, token_ids, amount).into()
             },
         }
+    }
+}
 
-        PromiseOrValue::Value(U128(0))
+#[near_bindgen]
+impl Contract {
+    /// pays out the TIA of an upgrade or fusion once spaceship made the ship,
+    /// otherwise returns it as unused so the TIA token refunds the sender
+    #[private]
+    pub fn resolve_spaceship_upgrade(&mut self, sender_id: AccountId, tia_fee: U128, storage_deposit: U128) -> U128 {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                if serde_json::from_slice::<bool>(&value).unwrap_or(false) {
+                    self.internal_distribute_upgrade_fee(tia_fee.0);
+                    return U128(0);
+                }
+                // spaceship kept the attached NEAR as the sender's NFT storage
+                tia_fee
+            },
+            PromiseResult::Failed => {
+                // the attached NEAR bounced back here, keep it for the next upgrade
+                if storage_deposit.0 > 0 {
+                    let balance = self.ship_storage.get(&sender_id).unwrap_or(0);
+                    self.ship_storage.insert(&sender_id, &(balance + storage_deposit.0));
+                }
+                tia_fee
+            },
+        }
     }
 }
 
@@ -378,21 +460,21 @@ impl Contract {
     }
 
     /// upgrade
-    pub fn internal_upgrade_spaceship(&mut self, sender_id: AccountId, token_id_1: TokenId, token_id_2: TokenId, sub_type: u8, amount: U128) {
+    pub fn internal_upgrade_spaceship(&mut self, sender_id: AccountId, token_id_1: TokenId, token_id_2: TokenId, sub_type: u8, amount: U128) -> Promise {
         let mut target_sub_type = sub_type;
  
         let (ship1_type, _) = self.internal_get_ship_type_subtype_by_token_id(token_id_1.clone());
         let (ship2_type, _) = self.internal_get_ship_type_subtype_by_token_id(token_id_2.clone());
         require!(ship1_type == ship2_type, "ShipFactory: material is must same type");
 
-        require!(ship1_type == TYPE_B|| ship1_type == TYPE_C || ship1_type == TYPE_D, "ShipFactory: material is not allow type");
+        let target_type = self.upgrade_target.get(&ship1_type).expect("ShipFactory: material is not allow type");
 
         let temp_mint: Vec<u128>;
         if target_sub_type == 0 {
             temp_mint = self.no_target_mint_fee.get(ship1_type.borrow()).unwrap();
         } else {
-            let target_sub_type_limit = self.target_sub_type.get(ship1_type.borrow()).unwrap();
-            require!( target_sub_type >= *target_sub_type_limit.get(0).unwrap() && target_sub_type <= *target_sub_type_limit.get(1).unwrap(), "Invalid ship subtype");
+            let target_sub_type_count = self.sub_type_count.get(&target_type).unwrap();
+            require!( target_sub_type >= 1 && target_sub_type <= target_sub_type_count, "Invalid ship subtype");
             temp_mint = self.target_mint_fee.get(ship1_type.borrow()).unwrap();
         }
         let tia_fee: u128 = *temp_mint.get(0).unwrap();
@@ -400,6 +482,57 @@ impl Contract {
 
         require!(amount.0 == tia_fee, "ShipFactory: invalid amount paid");
 
+        if target_sub_type == 0 {
+            target_sub_type = self.internal_random_spaceship_subtype(target_type);
+        }
+
+        let storage_deposit = self.internal_take_ship_storage(&sender_id);
+        ext_spaceship::upgrade_spaceship(
+            sender_id.clone(), 
+            token_id_1, 
+            token_id_2, 
+            target_sub_type, 
+            U128(eng_fee), 
+            self.spaceship.clone(), 
+            1 + storage_deposit, 
+            GAS_FOR_SPACESHIP_UPGRADE
+        ).then(ext_self::resolve_spaceship_upgrade(
+            sender_id,
+            U128(tia_fee),
+            U128(storage_deposit),
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_UPGRADE
+        ))
+    }
+}
+
+impl Contract {
+    /// the tiers spaceship starts with, later pushed by spaceship on every tier change
+    pub fn internal_init_tier_registry(&mut self) {
+        self.sub_type_count.insert(&TYPE_A, &4);
+        self.sub_type_count.insert(&TYPE_B, &8);
+        self.sub_type_count.insert(&TYPE_C, &16);
+        self.sub_type_count.insert(&TYPE_D, &32);
+
+        self.upgrade_target.insert(&TYPE_B, &TYPE_A);
+        self.upgrade_target.insert(&TYPE_C, &TYPE_B);
+        self.upgrade_target.insert(&TYPE_D, &TYPE_C);
+
+        self.capacity_range.insert(&TYPE_S, &CapacityRange { min: 240, max: 240 });
+        self.capacity_range.insert(&TYPE_A, &CapacityRange { min: 80, max: 120 });
+        self.capacity_range.insert(&TYPE_B, &CapacityRange { min: 40, max: 60 });
+        self.capacity_range.insert(&TYPE_C, &CapacityRange { min: 20, max: 30 });
+        self.capacity_range.insert(&TYPE_D, &CapacityRange { min: 10, max: 15 });
+    }
+
+    /// the storage deposit of `account_id` goes with its upgrade to spaceship
+    pub fn internal_take_ship_storage(&mut self, account_id: &AccountId) -> Balance {
+        self.ship_storage.remove(account_id).unwrap_or(0)
+    }
+
+    /// split the TIA paid for an upgrade among the pools and burn the rest
+    pub fn internal_distribute_upgrade_fee(&mut self, tia_fee: u128) {
         // 批量转 start
         let ship_reward = tia_fee * self.ship_reward_rate as u128 / RATE_DENOMINATOR as u128;
         let risker_reward = tia_fee * self.risker_reward_rate as u128 / RATE_DENOMINATOR as u128;
@@ -477,26 +610,8 @@ impl Contract {
             GAS_FOR_BATCH_TRANSFER_CALL
         );
         //
-
-        if target_sub_type == 0 {
-            target_sub_type = self.internal_random_spaceship_subtype(ship1_type);
-        }
-
-        ext_spaceship::upgrade_spaceship(
-            sender_id, 
-            token_id_1, 
-            token_id_2, 
-            target_sub_type, 
-            U128(eng_fee), 
-            self.spaceship.clone(), 
-            1, 
-            GAS_FOR_SPACESHIP_UPGRADE
-        );
-
     }
-}
 
-impl Contract {
     /// 通过token_id获取类型信息
     /// * `token_id`: 待查询的token_id
     pub fn internal_get_ship_type_subtype_by_token_id( &self, token_id: TokenId ) -> (u8, u8) {
@@ -507,41 +622,15 @@ impl Contract {
     }
 
     pub fn internal_random_spaceship_subtype(&mut self, ship_type: u8 ) -> u8{
-        let mut sub_type: u8 = 0;
-        let rnd: u64 = self.random();
-        if ship_type == TYPE_A {
-            sub_type = 1 + (rnd % 4) as u8; // 4 subtypes for A
-        }
-        if ship_type == TYPE_B {
-            sub_type = 1 + (rnd % 8) as u8; // 8 subtypes for B
-        }
-        if ship_type == TYPE_C {
-            sub_type = 1 + (rnd % 16) as u8; // 16 subtypes for C
-        }
-        if ship_type == TYPE_D {
-            sub_type = 1 + (rnd % 32) as u8; // 32 subtypes for D
-        }
-        sub_type
+        let count = self.sub_type_count.get(&ship_type).expect("Invalid ship type");
+        let rnd: u64 = self.random(DOMAIN_SUBTYPE);
+        1 + (rnd % count as u64) as u8
     }
 
-    pub fn random( &mut self ) -> u64 {
-        // get random data
-        let seeds: Vec<u8> = env::random_seed();
-        let mut seed: u128 = 0;
-
-        if self.shift > 24 {
-            self.shift = 0;
-        }
-
-        for i in self.shift..self.shift+8{
-            seed = seed * 10 + seeds[i] as u128;
-        }
-        self.shift += 2;
-
-        let mut r = StdRng::seed_from_u64(seed as u64);
-        let rnd: u64 = r.gen();
-
-        rnd
+    /// Next random number for `domain`, every call consumes a fresh nonce
+    pub fn random( &mut self, domain: &str ) -> u64 {
+        self.random_nonce += 1;
+        random_u64(domain, self.random_nonce)
     }
 }
 
@@ -1,5 +1,33 @@
 use crate::*;
 
+// the state before recipes and the tier registry copy were added
+#[derive(BorshDeserialize)]
+pub struct ContractV0 {
+    owner_id: AccountId,
+    next_order_id: u64,
+    ship_reward_rate: u8,
+    risker_reward_rate: u8,
+    bank_reward_rate: u8,
+    luck_reward_rate: u8,
+    rank_reward_rate: u8,
+    fee_rate: u8,
+    shift: usize,
+    spaceship: AccountId,
+    token_tia: AccountId,
+    ship_pool: AccountId,
+    bank: AccountId,
+    risker_pool: AccountId,
+    rank_pool: AccountId,
+    luck_pool: AccountId,
+    no_target_mint_fee: UnorderedMap<u8, Vec<u128>>,
+    target_mint_fee: UnorderedMap<u8, Vec<u128>>,
+    // [min, max] subtype range of the upgraded ship, shares its prefix with `sub_type_count`
+    target_sub_type: UnorderedMap<u8, Vec<u8>>,
+    order_map: UnorderedMap<u64, Order>,
+    sell_order_map: UnorderedMap<AccountId, Vec<u64>>,
+    buy_order_map: UnorderedMap<AccountId, Vec<u64>>,
+}
+
 impl Contract {
     pub fn assert_owner(&self) {
         require!(
@@ -18,6 +46,53 @@ impl Contract {
         self.owner_id = owner_id;
     }
 
+    /// Should only be called by this contract on migration.
+    /// Migrates from ContractV0, the tier registry copy takes the spaceship defaults and recipes start empty.
+    /// After migration goes live, revert back to the NOOP implementation for next updates.
+    #[init(ignore_state)]
+    #[private]
+    pub fn migrate() -> Self {
+        let old: ContractV0 = env::state_read().expect("ERR_NOT_INIT");
+        // `sub_type_count` took over the storage key of `target_sub_type`, drop the old entries first
+        let mut target_sub_type = old.target_sub_type;
+        target_sub_type.clear();
+        let mut this = Contract {
+            owner_id: old.owner_id,
+            next_order_id: old.next_order_id,
+            fee_rate: old.fee_rate,
+            ship_reward_rate: old.ship_reward_rate,
+            risker_reward_rate: old.risker_reward_rate,
+            bank_reward_rate: old.bank_reward_rate,
+            luck_reward_rate: old.luck_reward_rate,
+            rank_reward_rate: old.rank_reward_rate,
+            // rolls are no longer derived from the shift, the nonce only has to keep moving
+            random_nonce: old.shift as u64,
+
+            spaceship: old.spaceship,
+            token_tia: old.token_tia,
+            ship_pool: old.ship_pool,
+            bank: old.bank,
+            risker_pool: old.risker_pool,
+            rank_pool: old.rank_pool,
+            luck_pool: old.luck_pool,
+
+            no_target_mint_fee: old.no_target_mint_fee,
+            target_mint_fee: old.target_mint_fee,
+            sub_type_count: UnorderedMap::new(StorageKey::SubTypeCountKey),
+            upgrade_target: UnorderedMap::new(StorageKey::UpgradeTargetKey),
+            order_map: old.order_map,
+            sell_order_map: old.sell_order_map,
+            buy_order_map: old.buy_order_map,
+
+            capacity_range: UnorderedMap::new(StorageKey::CapacityRangeKey),
+            next_recipe_id: 0,
+            recipes: UnorderedMap::new(StorageKey::RecipeKey),
+            ship_storage: UnorderedMap::new(StorageKey::ShipStorage),
+        };
+        this.internal_init_tier_registry();
+        this
+    }
+
     #[payable]
     pub fn set_buy_fee_rate(&mut self, fee_rate: u8) {
         assert_one_yocto();
@@ -25,6 +100,17 @@ impl Contract {
         self.fee_rate = fee_rate;
     }
 
+    /// keep the local copy of the spaceship tier registry in sync
+    pub fn on_ship_tier_updated(&mut self, ship_type: u8, sub_type_count: u8, upgrade_target: Option<u8>, capacity: CapacityRange) {
+        require!(env::predecessor_account_id() == self.spaceship, "ERR_NOT_ALLOWED");
+        self.sub_type_count.insert(&ship_type, &sub_type_count);
+        self.capacity_range.insert(&ship_type, &capacity);
+        match upgrade_target {
+            Some(target) => self.upgrade_target.insert(&ship_type, &target),
+            None => self.upgrade_target.remove(&ship_type),
+        };
+    }
+
     #[payable]
     pub fn set_target_fee(&mut self, ship_type: u8, ssp_fee: U128, eng_fee: U128) {
         assert_one_yocto();
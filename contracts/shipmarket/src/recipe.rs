@@ -0,0 +1,331 @@
+use crate::*;
+
+// keep in line with spaceship, which rejects a larger fuse bonus
+pub const MAX_CAPACITY_BONUS: u32 = 1_000;
+
+/// copy of the spaceship tier capacity, pushed together with the subtype count
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct CapacityRange {
+    pub min: u32,
+    pub max: u32,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct SubTypeWeight {
+    pub sub_type: u8,
+    pub weight: u32,
+}
+
+/// Fuses `material_count` ships of `material_type` into one ship of `target_type`
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct UpgradeRecipe {
+    pub material_type: u8,
+    pub material_count: u8,
+    /// subtypes the materials are allowed to have, empty accepts any
+    pub material_sub_types: Vec<u8>,
+    pub tia_cost: U128,
+    pub eng_cost: U128,
+    pub target_type: u8,
+    /// chance of each target subtype, empty rolls all subtypes evenly
+    pub sub_type_weights: Vec<SubTypeWeight>,
+    /// percent added on top of the rolled capacity
+    pub capacity_bonus: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct UpgradePreview {
+    pub recipe_id: u32,
+    /// why the materials can not be fused, None if they can
+    pub error: Option<String>,
+    pub tia_cost: U128,
+    pub eng_cost: U128,
+    pub target_type: u8,
+    /// every possible subtype with its weight out of `total_weight`
+    pub sub_type_weights: Vec<SubTypeWeight>,
+    pub total_weight: u32,
+    /// capacity range of the fused ship, bonus included
+    pub capacity_min: u32,
+    pub capacity_max: u32,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn add_upgrade_recipe(&mut self, recipe: UpgradeRecipe) -> u32 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_check_recipe(&recipe);
+        self.next_recipe_id += 1;
+        self.recipes.insert(&self.next_recipe_id, &recipe);
+        self.next_recipe_id
+    }
+
+    #[payable]
+    pub fn remove_upgrade_recipe(&mut self, recipe_id: u32) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.recipes.remove(&recipe_id).expect("ERR_RECIPE_NOT_FOUND");
+    }
+
+    pub fn get_upgrade_recipes(&self) -> Vec<(u32, UpgradeRecipe)> {
+        self.recipes.to_vec()
+    }
+
+    /// costs and possible outcomes of fusing `token_ids` with a recipe, nothing is rolled
+    pub fn preview_upgrade(&self, recipe_id: u32, token_ids: Vec<TokenId>) -> UpgradePreview {
+        let recipe = self.recipes.get(&recipe_id).expect("ERR_RECIPE_NOT_FOUND");
+        let sub_type_weights = self.internal_sub_type_weights(&recipe);
+        let capacity = self.capacity_range.get(&recipe.target_type).expect("Invalid ship type");
+        // spaceship rolls in [min, max)
+        let roll_max = if capacity.max > capacity.min { capacity.max - 1 } else { capacity.min };
+
+        UpgradePreview {
+            recipe_id,
+            error: self.internal_check_materials(&recipe, &token_ids).err().map(String::from),
+            tia_cost: recipe.tia_cost,
+            eng_cost: recipe.eng_cost,
+            target_type: recipe.target_type,
+            total_weight: sub_type_weights.iter().map(|w| w.weight).sum(),
+            sub_type_weights,
+            capacity_min: capacity.min + capacity.min * recipe.capacity_bonus / 100,
+            capacity_max: roll_max + roll_max * recipe.capacity_bonus / 100,
+        }
+    }
+}
+
+impl Contract {
+    pub fn internal_check_recipe(&self, recipe: &UpgradeRecipe) {
+        // spaceship trusts the target type, a recipe only moves up the upgrade chain and never to S
+        require!(
+            recipe.target_type != TYPE_S && self.internal_is_upgrade_of(recipe.material_type, recipe.target_type),
+            "ERR_ILLEGAL_RECIPE"
+        );
+        let material_count = self.sub_type_count.get(&recipe.material_type).expect("Invalid ship type");
+        let target_count = self.sub_type_count.get(&recipe.target_type).expect("Invalid ship type");
+        require!(recipe.material_count > 0, "ERR_ILLEGAL_RECIPE");
+        require!(recipe.capacity_bonus <= MAX_CAPACITY_BONUS, "ERR_ILLEGAL_RECIPE");
+        require!(
+            recipe.material_sub_types.iter().all(|s| *s >= 1 && *s <= material_count),
+            "ERR_ILLEGAL_RECIPE"
+        );
+        require!(
+            recipe.sub_type_weights.iter().all(|w| w.sub_type >= 1 && w.sub_type <= target_count && w.weight > 0),
+            "ERR_ILLEGAL_RECIPE"
+        );
+    }
+
+    /// whether `target_type` is reached from `ship_type` by following the upgrade targets
+    pub fn internal_is_upgrade_of(&self, ship_type: u8, target_type: u8) -> bool {
+        let mut current = ship_type;
+        // every tier is visited at most once, a misconfigured cycle ends the walk
+        for _ in 0..self.upgrade_target.len() {
+            match self.upgrade_target.get(&current) {
+                Some(next) if next == target_type => return true,
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    pub fn internal_check_materials(&self, recipe: &UpgradeRecipe, token_ids: &[TokenId]) -> Result<(), &'static str> {
+        if token_ids.len() != recipe.material_count as usize {
+            return Err("ERR_ILLEGAL_MATERIAL_COUNT");
+        }
+        for (index, token_id) in token_ids.iter().enumerate() {
+            if token_ids[..index].contains(token_id) {
+                return Err("ERR_DUPLICATE_MATERIAL");
+            }
+            let (ship_type, ship_subtype) = self.internal_get_ship_type_subtype_by_token_id(token_id.clone());
+            if ship_type != recipe.material_type {
+                return Err("ERR_ILLEGAL_MATERIAL_TYPE");
+            }
+            if !recipe.material_sub_types.is_empty() && !recipe.material_sub_types.contains(&ship_subtype) {
+                return Err("ERR_ILLEGAL_MATERIAL_SUB_TYPE");
+            }
+        }
+        Ok(())
+    }
+
+    pub fn internal_sub_type_weights(&self, recipe: &UpgradeRecipe) -> Vec<SubTypeWeight> {
+        if !recipe.sub_type_weights.is_empty() {
+            return recipe.sub_type_weights.clone();
+        }
+        let count = self.sub_type_count.get(&recipe.target_type).expect("Invalid ship type");
+        (1..=count).map(|sub_type| SubTypeWeight { sub_type, weight: 1 }).collect()
+    }
+
+    pub fn internal_random_recipe_subtype(&mut self, recipe: &UpgradeRecipe) -> u8 {
+        let weights = self.internal_sub_type_weights(recipe);
+        let total: u64 = weights.iter().map(|w| w.weight as u64).sum();
+        let mut rnd = self.random(DOMAIN_SUBTYPE) % total;
+        for w in weights.iter() {
+            if rnd < w.weight as u64 {
+                return w.sub_type;
+            }
+            rnd -= w.weight as u64;
+        }
+        unreachable!()
+    }
+
+    /// fuse
+    pub fn internal_fuse_spaceship(&mut self, sender_id: AccountId, recipe_id: u32, token_ids: Vec<TokenId>, amount: U128) -> Promise {
+        let recipe = self.recipes.get(&recipe_id).expect("ERR_RECIPE_NOT_FOUND");
+        if let Err(err) = self.internal_check_materials(&recipe, &token_ids) {
+            env::panic_str(err);
+        }
+        require!(amount.0 == recipe.tia_cost.0, "ShipFactory: invalid amount paid");
+
+        let target_sub_type = self.internal_random_recipe_subtype(&recipe);
+        let storage_deposit = self.internal_take_ship_storage(&sender_id);
+        ext_spaceship::fuse_spaceship(
+            sender_id.clone(),
+            token_ids,
+            recipe.target_type,
+            target_sub_type,
+            recipe.capacity_bonus,
+            recipe.eng_cost,
+            self.spaceship.clone(),
+            1 + storage_deposit,
+            GAS_FOR_SPACESHIP_UPGRADE
+        ).then(ext_self::resolve_spaceship_upgrade(
+            sender_id,
+            recipe.tia_cost,
+            U128(storage_deposit),
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_UPGRADE
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    fn setup_contract() -> (VMContextBuilder, Contract) {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(1).build());
+        let contract = Contract::new(accounts(0), accounts(1), accounts(2), accounts(3),
+                                     accounts(4), accounts(5), accounts(0), accounts(1));
+        (context, contract)
+    }
+
+    fn d_to_c_recipe() -> UpgradeRecipe {
+        UpgradeRecipe {
+            material_type: TYPE_D,
+            material_count: 3,
+            material_sub_types: vec![1, 2],
+            tia_cost: U128(100 * YOCTO18),
+            eng_cost: U128(YOCTO18),
+            target_type: TYPE_C,
+            sub_type_weights: vec![
+                SubTypeWeight { sub_type: 1, weight: 3 },
+                SubTypeWeight { sub_type: 2, weight: 1 },
+            ],
+            capacity_bonus: 10,
+        }
+    }
+
+    #[test]
+    fn test_preview_upgrade() {
+        let (_, mut contract) = setup_contract();
+        let recipe_id = contract.add_upgrade_recipe(d_to_c_recipe());
+        assert_eq!(recipe_id, 1);
+
+        let token_ids = vec!["1:1:1:10".to_string(), "2:1:2:11".to_string(), "3:1:1:12".to_string()];
+        let preview = contract.preview_upgrade(recipe_id, token_ids.clone());
+        assert_eq!(preview.error, None);
+        assert_eq!(preview.tia_cost, U128(100 * YOCTO18));
+        assert_eq!(preview.total_weight, 4);
+        // C rolls in [20, 30), plus 10%
+        assert_eq!(preview.capacity_min, 22);
+        assert_eq!(preview.capacity_max, 31);
+
+        let preview = contract.preview_upgrade(recipe_id, token_ids[..2].to_vec());
+        assert_eq!(preview.error, Some("ERR_ILLEGAL_MATERIAL_COUNT".to_string()));
+        let preview = contract.preview_upgrade(
+            recipe_id,
+            vec!["1:1:1:10".to_string(), "2:1:3:11".to_string(), "3:1:1:12".to_string()],
+        );
+        assert_eq!(preview.error, Some("ERR_ILLEGAL_MATERIAL_SUB_TYPE".to_string()));
+        let preview = contract.preview_upgrade(
+            recipe_id,
+            vec!["1:1:1:10".to_string(), "1:1:1:10".to_string(), "3:1:1:12".to_string()],
+        );
+        assert_eq!(preview.error, Some("ERR_DUPLICATE_MATERIAL".to_string()));
+    }
+
+    #[test]
+    fn test_random_recipe_subtype() {
+        let (_, mut contract) = setup_contract();
+        let recipe = d_to_c_recipe();
+        for _ in 0..20 {
+            let sub_type = contract.internal_random_recipe_subtype(&recipe);
+            assert!(sub_type == 1 || sub_type == 2);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ILLEGAL_RECIPE")]
+    fn test_add_illegal_recipe() {
+        let (_, mut contract) = setup_contract();
+        let mut recipe = d_to_c_recipe();
+        recipe.sub_type_weights = vec![SubTypeWeight { sub_type: 17, weight: 1 }];
+        contract.add_upgrade_recipe(recipe);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ILLEGAL_RECIPE")]
+    fn test_add_downgrade_recipe() {
+        let (_, mut contract) = setup_contract();
+        let mut recipe = d_to_c_recipe();
+        recipe.material_type = TYPE_B;
+        contract.add_upgrade_recipe(recipe);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ILLEGAL_RECIPE")]
+    fn test_add_recipe_above_max_bonus() {
+        let (_, mut contract) = setup_contract();
+        let mut recipe = d_to_c_recipe();
+        recipe.capacity_bonus = MAX_CAPACITY_BONUS + 1;
+        contract.add_upgrade_recipe(recipe);
+    }
+
+    #[test]
+    fn test_resolve_spaceship_upgrade_refund() {
+        let (mut context, mut contract) = setup_contract();
+        let tia_cost = d_to_c_recipe().tia_cost;
+
+        // spaceship made nothing, it kept the NEAR as NFT storage
+        testing_env!(context.predecessor_account_id(accounts(0)).attached_deposit(0).build(), near_sdk::VMConfig::test(),
+                     near_sdk::RuntimeFeesConfig::test(), Default::default(),
+                     vec![PromiseResult::Successful(b"false".to_vec())]);
+        assert_eq!(contract.resolve_spaceship_upgrade(accounts(2), tia_cost, U128(5)), tia_cost);
+        assert_eq!(contract.ship_storage.get(&accounts(2)), None);
+
+        // spaceship panicked, the NEAR came back to shipmarket
+        testing_env!(context.predecessor_account_id(accounts(0)).build(), near_sdk::VMConfig::test(),
+                     near_sdk::RuntimeFeesConfig::test(), Default::default(),
+                     vec![PromiseResult::Failed]);
+        assert_eq!(contract.resolve_spaceship_upgrade(accounts(2), tia_cost, U128(5)), tia_cost);
+        assert_eq!(contract.ship_storage.get(&accounts(2)), Some(5));
+
+        testing_env!(context.predecessor_account_id(accounts(0)).build(), near_sdk::VMConfig::test(),
+                     near_sdk::RuntimeFeesConfig::test(), Default::default(),
+                     vec![PromiseResult::Successful(b"true".to_vec())]);
+        assert_eq!(contract.resolve_spaceship_upgrade(accounts(2), tia_cost, U128(0)), U128(0));
+    }
+}
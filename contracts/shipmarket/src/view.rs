@@ -48,6 +48,10 @@ impl Contract {
         }
     }
 
+    pub fn get_ship_storage(&self, account_id: AccountId) -> U128 {
+        U128(self.ship_storage.get(&account_id).unwrap_or(0))
+    }
+
     pub fn get_target_fee(&self, ship_type: u8) -> Vec<U128> {
         let temp = self.target_mint_fee.get(&ship_type).unwrap();
         let mut fees: Vec<U128> = vec![];
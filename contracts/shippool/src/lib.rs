@@ -34,6 +34,7 @@ pub const TYPE_B: u8 = 3;
 pub const TYPE_C: u8 = 2;
 pub const TYPE_D: u8 = 1;
 
+// subtype counts a new pool starts with, spaceship pushes later tier changes
 pub const TYPE_A_MAX: u8 = 4;
 pub const TYPE_B_MAX: u8 = 8;
 pub const TYPE_C_MAX: u8 = 16;
@@ -104,6 +105,7 @@ pub(crate) enum StorageKey {
     PioneerHistory,
     PioneerToken,
     UserInfo,
+    SubTypeCount,
 }
 
 #[ext_contract(ext_ft)]
@@ -142,6 +144,9 @@ pub struct Contract {
     pioneer_max: u32,
 
     balance: Balance,
+
+    // ship type => number of subtypes, pushed by spaceship on every tier change
+    sub_type_count: UnorderedMap<u8, u8>,
 }
 
 #[near_bindgen]
@@ -149,7 +154,7 @@ impl Contract {
     #[init]
     pub fn new(owner_id: AccountId, ship_contract_id: AccountId, token_tia: AccountId) -> Self {
         require!(!env::state_exists(), "Already initialized");
-        Contract {
+        let mut this = Contract {
             owner_id,
             ship_contract_id,
             token_tia,
@@ -162,7 +167,10 @@ impl Contract {
             total_claimed_reward: 0,
             pioneer_max: 5, // default: 5
             balance: 0,
-        }
+            sub_type_count: UnorderedMap::new(StorageKey::SubTypeCount),
+        };
+        this.internal_init_sub_type_count();
+        this
     }
 
     pub fn register_ship(&mut self, token_id: String, token_owner_id: AccountId) {
@@ -265,6 +273,14 @@ impl FungibleTokenReceiver for Contract {
 
 /* ========== INTERNAL FUNCTION ========== */
 impl Contract{
+    /// subtypes of the tiers spaceship starts with, later pushed by spaceship on every tier change
+    pub fn internal_init_sub_type_count(&mut self) {
+        self.sub_type_count.insert(&TYPE_A, &TYPE_A_MAX);
+        self.sub_type_count.insert(&TYPE_B, &TYPE_B_MAX);
+        self.sub_type_count.insert(&TYPE_C, &TYPE_C_MAX);
+        self.sub_type_count.insert(&TYPE_D, &TYPE_D_MAX);
+    }
+
     // SPL-00-03
     pub fn internal_init_user(&mut self, pioneer: AccountId) {
         let mut user: UserInfo = self.user_info.get(&pioneer).unwrap_or(
@@ -273,27 +289,27 @@ impl Contract{
                 burned: 0,
                 pioneer_token_amount: U128(0),
                 need_init: false,
-                a: vec![0;TYPE_A_MAX.into()], // record shipsubtype
-                b: vec![0;TYPE_B_MAX.into()], // record shipsubtype
-                c: vec![0;TYPE_C_MAX.into()], // record shipsubtype
-                d: vec![0;TYPE_D_MAX.into()], // record shipsubtype  
+                a: vec![0; self.internal_sub_type_count(TYPE_A)], // record shipsubtype
+                b: vec![0; self.internal_sub_type_count(TYPE_B)], // record shipsubtype
+                c: vec![0; self.internal_sub_type_count(TYPE_C)], // record shipsubtype
+                d: vec![0; self.internal_sub_type_count(TYPE_D)], // record shipsubtype  
             }
         ).clone();
 
         let total_burned: u32 = user.total_burned;
 
         let mut pioneer_token: PioneerToken = self.pioneer_token.get(&pioneer).unwrap().clone();
-        for i in 0..user.a.len(){
-            pioneer_token.pioneer_token_a[user.a[i] as usize - 1] = 0;
-        }
-        for j in 0..user.b.len(){
-            pioneer_token.pioneer_token_b[user.b[j] as usize - 1] = 0;
-        }
-        for m in 0..user.c.len(){
-            pioneer_token.pioneer_token_c[user.c[m] as usize - 1] = 0;
-        }
-        for n in 0..user.d.len(){
-            pioneer_token.pioneer_token_d[user.d[n] as usize - 1] = 0;
+        self.internal_fit_sub_types(&mut user, &mut pioneer_token);
+        for (subtypes, amounts) in [
+            (&user.a, &mut pioneer_token.pioneer_token_a),
+            (&user.b, &mut pioneer_token.pioneer_token_b),
+            (&user.c, &mut pioneer_token.pioneer_token_c),
+            (&user.d, &mut pioneer_token.pioneer_token_d),
+        ] {
+            // subtypes added since the user collected the others are still 0
+            for sub_type in subtypes.iter().filter(|sub_type| **sub_type > 0) {
+                amounts[*sub_type as usize - 1] = 0;
+            }
         }
         self.pioneer_token.insert(&pioneer, &pioneer_token);
 
@@ -302,10 +318,10 @@ impl Contract{
         user.burned = 0;
         user.pioneer_token_amount = U128(0);
         user.need_init = false;
-        user.a = vec![0;TYPE_A_MAX.into()];
-        user.b = vec![0;TYPE_B_MAX.into()];
-        user.c = vec![0;TYPE_C_MAX.into()];
-        user.d = vec![0;TYPE_D_MAX.into()];
+        user.a = vec![0; self.internal_sub_type_count(TYPE_A)];
+        user.b = vec![0; self.internal_sub_type_count(TYPE_B)];
+        user.c = vec![0; self.internal_sub_type_count(TYPE_C)];
+        user.d = vec![0; self.internal_sub_type_count(TYPE_D)];
         self.user_info.insert(&pioneer, &user);
 
     }
@@ -352,23 +368,33 @@ impl Contract{
                 burned: 0,
                 pioneer_token_amount: U128(0),
                 need_init: false,
-                a: vec![0;TYPE_A_MAX.into()], // record shipsubtype
-                b: vec![0;TYPE_B_MAX.into()], // record shipsubtype
-                c: vec![0;TYPE_C_MAX.into()], // record shipsubtype
-                d: vec![0;TYPE_D_MAX.into()], // record shipsubtype 
+                a: vec![0; self.internal_sub_type_count(TYPE_A)], // record shipsubtype
+                b: vec![0; self.internal_sub_type_count(TYPE_B)], // record shipsubtype
+                c: vec![0; self.internal_sub_type_count(TYPE_C)], // record shipsubtype
+                d: vec![0; self.internal_sub_type_count(TYPE_D)], // record shipsubtype 
             }
         ).clone();
 
         require!(ship_type != TYPE_S, "ShipPool: not support type");
+        // tiers added to the registry later do not count toward pioneer progress
+        if ![TYPE_A, TYPE_B, TYPE_C, TYPE_D].contains(&ship_type) {
+            self.user_info.insert(&to, &user);
+            return;
+        }
+        require!(
+            ship_subtype >= 1 && ship_subtype as usize <= self.internal_sub_type_count(ship_type),
+            "ShipPool: invalid ship subtype"
+        );
         let mut pioneer_token_amount: u128 = 0;
         let mut pioneer_token: PioneerToken = self.pioneer_token.get(&to).unwrap_or(
             PioneerToken{
-                pioneer_token_a: vec![0;TYPE_A_MAX.into()], // 4 subtypes
-                pioneer_token_b: vec![0;TYPE_B_MAX.into()], // 8 subtypes
-                pioneer_token_c: vec![0;TYPE_C_MAX.into()], // 16 subtypes
-                pioneer_token_d: vec![0;TYPE_D_MAX.into()], // 32 subtypes
+                pioneer_token_a: vec![0; self.internal_sub_type_count(TYPE_A)], // 4 subtypes
+                pioneer_token_b: vec![0; self.internal_sub_type_count(TYPE_B)], // 8 subtypes
+                pioneer_token_c: vec![0; self.internal_sub_type_count(TYPE_C)], // 16 subtypes
+                pioneer_token_d: vec![0; self.internal_sub_type_count(TYPE_D)], // 32 subtypes
             }
         ).clone();
+        self.internal_fit_sub_types(&mut user, &mut pioneer_token);
         if ship_type == TYPE_A {
             pioneer_token_amount = pioneer_token.pioneer_token_a[(ship_subtype-1) as usize];
             if pioneer_token_amount == 0 {
@@ -423,7 +449,11 @@ impl Contract{
     // SPL-00-05
     pub fn internal_update_epoch_progress(&mut self,  to: AccountId) -> bool {
         let user: UserInfo = self.user_info.get(&to).unwrap().clone();
-        if user.burned < ( TYPE_A_MAX + TYPE_B_MAX + TYPE_C_MAX + TYPE_D_MAX ).into() {
+        let sub_type_total: usize = [TYPE_A, TYPE_B, TYPE_C, TYPE_D]
+            .iter()
+            .map(|ship_type| self.internal_sub_type_count(*ship_type))
+            .sum();
+        if (user.burned as usize) < sub_type_total {
             return false;
         }
 
@@ -493,6 +523,28 @@ impl Contract{
         }        
     }
 
+    pub fn internal_sub_type_count(&self, ship_type: u8) -> usize {
+        self.sub_type_count.get(&ship_type).unwrap_or(0) as usize
+    }
+
+    /// grow the per-subtype records of a user to the subtype counts of the tier registry
+    pub fn internal_fit_sub_types(&self, user: &mut UserInfo, pioneer_token: &mut PioneerToken) {
+        for (ship_type, subtypes, amounts) in [
+            (TYPE_A, &mut user.a, &mut pioneer_token.pioneer_token_a),
+            (TYPE_B, &mut user.b, &mut pioneer_token.pioneer_token_b),
+            (TYPE_C, &mut user.c, &mut pioneer_token.pioneer_token_c),
+            (TYPE_D, &mut user.d, &mut pioneer_token.pioneer_token_d),
+        ] {
+            let count = self.internal_sub_type_count(ship_type);
+            if subtypes.len() < count {
+                subtypes.resize(count, 0);
+            }
+            if amounts.len() < count {
+                amounts.resize(count, 0);
+            }
+        }
+    }
+
     pub fn internal_get_pioneer_token(& self, token_id: TokenId) -> u128 {
         let items :Vec<&str> = token_id.split(":").collect();    
         let token_id: u128 = items[0].parse::<u128>().unwrap();
@@ -1,5 +1,21 @@
 use crate::*;
 
+// the state before the tier registry copy was added
+#[derive(BorshDeserialize)]
+pub struct ContractV0 {
+    owner_id: AccountId,
+    ship_contract_id: AccountId,
+    token_tia: AccountId,
+    round_history: Vector<Round>,
+    pioneer_history: UnorderedMap<RoundIdAcc, Pioneer>,
+    pioneer_token: UnorderedMap<AccountId, PioneerToken>,
+    user_info: UnorderedMap<AccountId, UserInfo>,
+    total_reward: u128,
+    total_claimed_reward: u128,
+    pioneer_max: u32,
+    balance: Balance,
+}
+
 impl Contract {
     pub fn assert_owner(&self) {
         require!(
@@ -20,15 +36,28 @@ impl Contract {
 
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have, you need to implement migration from old state 
-    /// (keep the old struct with different name to deserialize it first).
-    /// After migration goes live, revert back to this implementation for next updates.
+    /// Migrates from ContractV0, the subtypes take the spaceship defaults.
+    /// After migration goes live, revert back to the NOOP implementation for next updates.
     #[init(ignore_state)]
     #[private]
     pub fn migrate() -> Self {
-        let contract: Contract = env::state_read().expect("ERR_NOT_INIT");
-        contract
+        let old: ContractV0 = env::state_read().expect("ERR_NOT_INIT");
+        let mut this = Contract {
+            owner_id: old.owner_id,
+            ship_contract_id: old.ship_contract_id,
+            token_tia: old.token_tia,
+            round_history: old.round_history,
+            pioneer_history: old.pioneer_history,
+            pioneer_token: old.pioneer_token,
+            user_info: old.user_info,
+            total_reward: old.total_reward,
+            total_claimed_reward: old.total_claimed_reward,
+            pioneer_max: old.pioneer_max,
+            balance: old.balance,
+            sub_type_count: UnorderedMap::new(StorageKey::SubTypeCount),
+        };
+        this.internal_init_sub_type_count();
+        this
     }
 
     // SPL-00-08
@@ -37,6 +66,14 @@ impl Contract {
         require!(max > 0, "invalid num");
         self.pioneer_max = max;
     }
+
+    // SPL-00-12
+    /// keep the subtype counts in sync with the spaceship tier registry
+    #[allow(unused_variables)]
+    pub fn on_ship_tier_updated(&mut self, ship_type: u8, sub_type_count: u8, upgrade_target: Option<u8>) {
+        require!(env::predecessor_account_id() == self.ship_contract_id, "ERR_NOT_ALLOWED");
+        self.sub_type_count.insert(&ship_type, &sub_type_count);
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -78,10 +78,10 @@ impl Contract {
             u.burned = 0;
             u.pioneer_token_amount = U128(0);
             u.need_init = false;
-            u.a = vec![0;TYPE_A_MAX.into()];
-            u.b = vec![0;TYPE_B_MAX.into()];
-            u.c = vec![0;TYPE_C_MAX.into()];
-            u.d = vec![0;TYPE_D_MAX.into()];
+            u.a = vec![0; self.internal_sub_type_count(TYPE_A)];
+            u.b = vec![0; self.internal_sub_type_count(TYPE_B)];
+            u.c = vec![0; self.internal_sub_type_count(TYPE_C)];
+            u.d = vec![0; self.internal_sub_type_count(TYPE_D)];
 
             self.user_info.insert(&to,&u);
         }
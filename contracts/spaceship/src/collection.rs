@@ -0,0 +1,97 @@
+use crate::*;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct TierCollection {
+    pub ship_type: u8,
+    /// distinct subtypes of the tier the owner holds
+    pub sub_types_held: u8,
+    pub sub_type_count: u8,
+    pub completed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct CollectionProgress {
+    pub tiers: Vec<TierCollection>,
+    pub completed_tiers: Vec<u8>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-33]
+    /// Return how many distinct subtypes of each tier `account_id` holds
+    pub fn get_collection_progress(&self, account_id: AccountId) -> CollectionProgress {
+        let owner_supplies = self.supply_per_owner.get(&account_id);
+        let mut tiers: Vec<TierCollection> = self
+            .tiers
+            .iter()
+            .map(|(ship_type, tier)| {
+                let sub_types_held = owner_supplies.as_ref().map_or(0, |supplies| {
+                    internal_sub_types_held(supplies, ship_type, tier.sub_type_count)
+                });
+                TierCollection {
+                    ship_type,
+                    sub_types_held,
+                    sub_type_count: tier.sub_type_count,
+                    completed: sub_types_held >= tier.sub_type_count,
+                }
+            })
+            .collect();
+        tiers.sort_by_key(|tier| tier.ship_type);
+        let completed_tiers = tiers
+            .iter()
+            .filter(|tier| tier.completed)
+            .map(|tier| tier.ship_type)
+            .collect();
+        CollectionProgress {
+            tiers,
+            completed_tiers,
+        }
+    }
+}
+
+fn internal_sub_types_held(
+    owner_supplies: &UnorderedMap<String, ShipSupply>,
+    ship_type: u8,
+    sub_type_count: u8,
+) -> u8 {
+    (1..=sub_type_count)
+        .filter(|sub_type| {
+            owner_supplies
+                .get(&format!("{}:{}", ship_type, sub_type))
+                .is_some_and(|supply| supply.active > 0)
+        })
+        .count() as u8
+}
+
+impl Contract {
+    /// recount the subtypes of `ship_type` held by `owner_id` after one of them
+    /// was gained or lost for good, emits `collection_completed` when the set gets full
+    pub(crate) fn internal_update_collection(
+        &mut self,
+        owner_id: &AccountId,
+        ship_type: u8,
+        owner_supplies: &UnorderedMap<String, ShipSupply>,
+    ) {
+        let sub_type_count = match self.tiers.get(&ship_type) {
+            Some(tier) => tier.sub_type_count,
+            None => return,
+        };
+        let sub_types_held = internal_sub_types_held(owner_supplies, ship_type, sub_type_count);
+
+        let mut collections = self.collections.get(owner_id).unwrap_or_default();
+        let previous = collections.insert(ship_type, sub_types_held).unwrap_or(0);
+        if sub_types_held >= sub_type_count && previous < sub_type_count {
+            Event::CollectionCompleted {
+                owner_id,
+                ship_type,
+                sub_type_count,
+            }
+            .emit();
+        }
+        self.collections.insert(owner_id, &collections);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::*;
+use near_sdk::Promise;
+
+/// Where newly minted ENG comes from
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub enum EngSource {
+    BurnReward,
+    OwnerMint,
+    /// the burned ENG of an upgrade whose materials moved before it ran
+    UpgradeRefund,
+}
+
+/// ENG supply accounting, every mint and burn of ENG goes through it
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct EngEmission {
+    // ENG in circulation before the emission was tracked, carried over by `migrate`
+    legacy_supply: u128,
+    burn_reward: u128,
+    owner_mint: u128,
+    upgrade_refund: u128,
+    total_burned: u128,
+
+    // None means no limit
+    total_cap: Option<u128>,
+    epoch_cap: Option<u128>,
+    // length of an emission epoch in blocks, epoch index is block_height / epoch_blocks
+    epoch_blocks: u64,
+    current_epoch: u64,
+    epoch_minted: u128,
+}
+
+impl Default for EngEmission {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngEmission {
+    pub fn new() -> Self {
+        Self {
+            legacy_supply: 0,
+            burn_reward: 0,
+            owner_mint: 0,
+            upgrade_refund: 0,
+            total_burned: 0,
+            total_cap: None,
+            epoch_cap: None,
+            epoch_blocks: DEFAULT_EMISSION_EPOCH_BLOCKS,
+            current_epoch: 0,
+            epoch_minted: 0,
+        }
+    }
+
+    /// start the emission from the supply of a contract that did not track it
+    pub fn with_legacy_supply(legacy_supply: u128) -> Self {
+        Self {
+            legacy_supply,
+            ..Self::new()
+        }
+    }
+
+    pub fn total_minted(&self) -> u128 {
+        self.legacy_supply + self.burn_reward + self.owner_mint + self.upgrade_refund
+    }
+
+    fn epoch_at(&self, block_height: u64) -> u64 {
+        block_height / self.epoch_blocks
+    }
+
+    /// minted amount of the epoch `block_height` falls in
+    fn epoch_minted_at(&self, block_height: u64) -> u128 {
+        if self.epoch_at(block_height) == self.current_epoch {
+            self.epoch_minted
+        } else {
+            0
+        }
+    }
+
+    pub fn record_mint(&mut self, source: EngSource, amount: u128) {
+        let total_minted = self.total_minted() + amount;
+        if let Some(cap) = self.total_cap {
+            require!(total_minted <= cap, "ERR_ENG_EMISSION_CAP_EXCEEDED");
+        }
+
+        let block_height = env::block_height();
+        let epoch_minted = self.epoch_minted_at(block_height) + amount;
+        if let Some(cap) = self.epoch_cap {
+            require!(epoch_minted <= cap, "ERR_ENG_EPOCH_CAP_EXCEEDED");
+        }
+        self.current_epoch = self.epoch_at(block_height);
+        self.epoch_minted = epoch_minted;
+
+        match source {
+            EngSource::BurnReward => self.burn_reward += amount,
+            EngSource::OwnerMint => self.owner_mint += amount,
+            EngSource::UpgradeRefund => self.upgrade_refund += amount,
+        }
+    }
+
+    pub fn record_burn(&mut self, amount: u128) {
+        self.total_burned += amount;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct EngEmissionStats {
+    pub legacy_supply: U128,
+    pub burn_reward: U128,
+    pub owner_mint: U128,
+    pub upgrade_refund: U128,
+    pub total_minted: U128,
+    pub total_burned: U128,
+    pub total_supply: U128,
+    pub total_cap: Option<U128>,
+    pub epoch_cap: Option<U128>,
+    pub epoch_blocks: u64,
+    pub current_epoch: u64,
+    pub epoch_minted: U128,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// set the ENG emission caps, `None` removes a cap.
+    /// `epoch_cap` limits the ENG minted within every `epoch_blocks` blocks.
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn set_eng_emission_cap(
+        &mut self,
+        total_cap: Option<U128>,
+        epoch_cap: Option<U128>,
+        epoch_blocks: u64,
+    ) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(epoch_blocks > 0, "ERR_INVALID_EPOCH_BLOCKS");
+        self.internal_assert_eng_total_cap(total_cap);
+        self.internal_queue_action(TimelockAction::SetEngEmissionCap {
+            total_cap,
+            epoch_cap,
+            epoch_blocks,
+        })
+    }
+
+    /// [SSP-00-41] mint again the ENG the standalone token failed to mint to the caller
+    #[payable]
+    pub fn claim_eng(&mut self) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let eng_token_id = self.eng_token_id.clone().expect("ERR_ENG_TOKEN_NOT_SET");
+        let amount = self
+            .eng_claimable
+            .remove(&account_id)
+            .filter(|amount| *amount > 0)
+            .expect("ERR_NOTHING_TO_CLAIM");
+        // already counted by the emission when it was first minted
+        self.internal_send_eng_mint(eng_token_id, account_id, U128(amount))
+    }
+
+    /// credit the claimable ENG of `receiver_id` if the mint failed
+    #[private]
+    pub fn resolve_mint_eng(&mut self, receiver_id: AccountId, amount: U128) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => true,
+            PromiseResult::Failed => {
+                let claimable = self.eng_claimable.get(&receiver_id).unwrap_or(0) + amount.0;
+                self.eng_claimable.insert(&receiver_id, &claimable);
+                Event::EngClaimable {
+                    account_id: &receiver_id,
+                    amount,
+                }
+                .emit();
+                false
+            }
+        }
+    }
+
+    pub fn get_eng_claimable(&self, account_id: AccountId) -> U128 {
+        U128(self.eng_claimable.get(&account_id).unwrap_or(0))
+    }
+
+    /// `total_supply` is the local ledger until ENG moved to the standalone token,
+    /// the ledger is left behind then and the supply follows the emission
+    pub fn eng_emission_stats(&self) -> EngEmissionStats {
+        let emission = &self.eng_emission;
+        let total_supply = match self.eng_token_id {
+            Some(_) => emission
+                .total_minted()
+                .checked_sub(emission.total_burned)
+                .expect("ERR_ENG_SUPPLY_UNDERFLOW"),
+            None => self.eng.total_supply,
+        };
+        EngEmissionStats {
+            legacy_supply: U128(emission.legacy_supply),
+            burn_reward: U128(emission.burn_reward),
+            owner_mint: U128(emission.owner_mint),
+            upgrade_refund: U128(emission.upgrade_refund),
+            total_minted: U128(emission.total_minted()),
+            total_burned: U128(emission.total_burned),
+            total_supply: U128(total_supply),
+            total_cap: emission.total_cap.map(U128),
+            epoch_cap: emission.epoch_cap.map(U128),
+            epoch_blocks: emission.epoch_blocks,
+            current_epoch: emission.epoch_at(env::block_height()),
+            epoch_minted: U128(emission.epoch_minted_at(env::block_height())),
+        }
+    }
+
+    /// ENG burned by `burn_eng`, `burn_eng_for_user` and `batch_eng_transfer`
+    pub fn eng_total_burned(&self) -> U128 {
+        U128(self.eng_emission.total_burned)
+    }
+}
+
+impl Contract {
+    /// the only way ENG enters circulation, counted per source and checked against the caps
+    pub fn internal_mint_eng(&mut self, receiver_id: AccountId, amount: U128, source: EngSource) {
+        self.eng_emission.record_mint(source, amount.0);
+        if let Some(eng_token_id) = self.eng_token_id.clone() {
+            self.internal_send_eng_mint(eng_token_id, receiver_id, amount);
+            return;
+        }
+        if self.eng.storage_balance_of(receiver_id.clone()).is_none() {
+            self.eng.internal_register_account(&receiver_id);
+        }
+        self.eng.internal_deposit(&receiver_id, amount.into());
+    }
+
+    fn internal_send_eng_mint(
+        &self,
+        eng_token_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> Promise {
+        ext_eng::mint(
+            receiver_id.clone(),
+            amount,
+            eng_token_id,
+            1,
+            GAS_FOR_ENG_MINT,
+        )
+        .then(ext_self::resolve_mint_eng(
+            receiver_id,
+            amount,
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_ENG_MINT,
+        ))
+    }
+
+    pub fn internal_burn_eng(&mut self, account_id: &AccountId, amount: U128) {
+        self.eng.internal_withdraw(account_id, amount.into());
+        self.eng_emission.record_burn(amount.0);
+    }
+
+    pub(crate) fn internal_assert_eng_total_cap(&self, total_cap: Option<U128>) {
+        if let Some(cap) = total_cap {
+            require!(
+                cap.0 >= self.eng_emission.total_minted(),
+                "ERR_CAP_BELOW_MINTED"
+            );
+        }
+    }
+
+    pub(crate) fn internal_set_eng_emission_cap(
+        &mut self,
+        total_cap: Option<U128>,
+        epoch_cap: Option<U128>,
+        epoch_blocks: u64,
+    ) {
+        // ENG may have been minted while the change was queued
+        self.internal_assert_eng_total_cap(total_cap);
+
+        let block_height = env::block_height();
+        let epoch_minted = self.eng_emission.epoch_minted_at(block_height);
+        self.eng_emission.total_cap = total_cap.map(|v| v.0);
+        self.eng_emission.epoch_cap = epoch_cap.map(|v| v.0);
+        // a new epoch length restarts the epoch counting from the current block
+        if epoch_blocks != self.eng_emission.epoch_blocks {
+            self.eng_emission.epoch_blocks = epoch_blocks;
+            self.eng_emission.current_epoch = self.eng_emission.epoch_at(block_height);
+            self.eng_emission.epoch_minted = epoch_minted;
+        }
+
+        Event::EngEmissionCapUpdated {
+            total_cap,
+            epoch_cap,
+            epoch_blocks,
+        }
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_epoch_cap() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.block_index(100).build());
+        let mut emission = EngEmission::new();
+        emission.epoch_blocks = 100;
+        emission.epoch_cap = Some(10);
+
+        emission.record_mint(EngSource::BurnReward, 6);
+        emission.record_mint(EngSource::OwnerMint, 4);
+        assert_eq!(emission.epoch_minted_at(199), 10);
+
+        // next epoch starts from zero
+        testing_env!(context.block_index(200).build());
+        assert_eq!(emission.epoch_minted_at(200), 0);
+        emission.record_mint(EngSource::UpgradeRefund, 10);
+        assert_eq!(emission.total_minted(), 20);
+        assert_eq!(emission.burn_reward, 6);
+        assert_eq!(emission.owner_mint, 4);
+        assert_eq!(emission.upgrade_refund, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ENG_EPOCH_CAP_EXCEEDED")]
+    fn test_epoch_cap_exceeded() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.block_index(100).build());
+        let mut emission = EngEmission::new();
+        emission.epoch_cap = Some(10);
+        emission.record_mint(EngSource::BurnReward, 6);
+        emission.record_mint(EngSource::BurnReward, 5);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ENG_EMISSION_CAP_EXCEEDED")]
+    fn test_total_cap_exceeded() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut emission = EngEmission::new();
+        emission.total_cap = Some(10);
+        emission.record_mint(EngSource::OwnerMint, 10);
+        emission.record_mint(EngSource::OwnerMint, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_ENG_EMISSION_CAP_EXCEEDED")]
+    fn test_total_cap_counts_legacy_supply() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.predecessor_account_id(accounts(0)).build());
+        let mut emission = EngEmission::with_legacy_supply(8);
+        emission.total_cap = Some(10);
+        emission.record_mint(EngSource::OwnerMint, 2);
+        assert_eq!(emission.total_minted(), 10);
+        emission.record_mint(EngSource::OwnerMint, 1);
+    }
+}
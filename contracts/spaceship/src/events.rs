@@ -1,4 +1,5 @@
-use near_sdk::{log, serde::Serialize, serde_json::json, AccountId};
+use crate::TimelockAction;
+use near_sdk::{json_types::U128, log, serde::Serialize, serde_json::json, AccountId};
 
 const EVENT_STANDARD: &str = "spaceship";
 const EVENT_STANDARD_VERSION: &str = "1.0.0";
@@ -13,6 +14,130 @@ pub enum Event<'a> {
         token_id_1: &'a String,
         token_id_2: &'a String,
         token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+    },
+    CapacityRoll {
+        owner_id: &'a AccountId,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+    },
+    EngReward {
+        owner_id: &'a AccountId,
+        receiver_id: &'a AccountId,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+        amount: U128,
+    },
+    BatchBurn {
+        owner_id: &'a AccountId,
+        token_ids: &'a Vec<String>,
+        amount: U128,
+    },
+    ShipTierUpdated {
+        ship_type: u8,
+        name: &'a String,
+        sub_type_count: u8,
+        upgrade_target: Option<u8>,
+    },
+    RegistrationQueued {
+        owner_id: &'a AccountId,
+        token_ids: &'a Vec<String>,
+    },
+    ShipPayout {
+        receiver_id: &'a AccountId,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+    },
+    EngEmissionCapUpdated {
+        total_cap: Option<U128>,
+        epoch_cap: Option<U128>,
+        epoch_blocks: u64,
+    },
+    EngMigrated {
+        account_ids: &'a Vec<AccountId>,
+        amounts: &'a Vec<U128>,
+    },
+    EngClaimable {
+        account_id: &'a AccountId,
+        amount: U128,
+    },
+    UpdateUser {
+        token_id: &'a String,
+        owner_id: &'a AccountId,
+        user_id: &'a AccountId,
+        expires_at: u64,
+    },
+    FuseEvent {
+        sender_id: &'a AccountId,
+        token_ids: &'a Vec<String>,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+    },
+    CollectionCompleted {
+        owner_id: &'a AccountId,
+        ship_type: u8,
+        sub_type_count: u8,
+    },
+    ShipLocked {
+        token_id: &'a String,
+        owner_id: &'a AccountId,
+        pool_id: &'a AccountId,
+    },
+    ShipUnlocked {
+        token_id: &'a String,
+        owner_id: &'a AccountId,
+        pool_id: &'a AccountId,
+    },
+    ActionQueued {
+        action_id: u64,
+        action: &'a TimelockAction,
+        eta: u64,
+    },
+    ActionCancelled {
+        action_id: u64,
+        action: &'a TimelockAction,
+    },
+    ActionExecuted {
+        action_id: u64,
+        action: &'a TimelockAction,
+    },
+    ShipLevelUp {
+        token_id: &'a String,
+        level: u8,
+        xp: u64,
+        effective_capacity: u32,
+    },
+    ShipMint {
+        owner_id: &'a AccountId,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+    },
+    ShipBurn {
+        owner_id: &'a AccountId,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
+    },
+    ShipTransfer {
+        old_owner_id: &'a AccountId,
+        new_owner_id: &'a AccountId,
+        token_id: &'a String,
+        ship_type: u8,
+        ship_sub_type: u8,
+        capacity: u32,
     },
 }
 
@@ -61,11 +186,260 @@ mod tests {
             token_id_1,
             token_id_2,
             token_id,
+            ship_type: 2,
+            ship_sub_type: 3,
+            capacity: 25,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"upgrade_event","data":[{"sender_id":"alice","token_id_1":"1","token_id_2":"2","token_id":"3","ship_type":2,"ship_sub_type":3,"capacity":25}]}"#
+        );
+    }
+
+    #[test]
+    fn event_registration_queued() {
+        let owner_id = &alice();
+        let token_ids = &vec!["1:1:3:12".to_string(), "2:2:1:25".to_string()];
+
+        Event::RegistrationQueued {
+            owner_id,
+            token_ids,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"registration_queued","data":[{"owner_id":"alice","token_ids":["1:1:3:12","2:2:1:25"]}]}"#
+        );
+    }
+
+    #[test]
+    fn event_batch_burn() {
+        let owner_id = &alice();
+        let token_ids = &vec!["1:1:3:12".to_string(), "2:1:5:11".to_string()];
+
+        Event::BatchBurn {
+            owner_id,
+            token_ids,
+            amount: U128(40),
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"batch_burn","data":[{"owner_id":"alice","token_ids":["1:1:3:12","2:1:5:11"],"amount":"40"}]}"#
+        );
+    }
+
+    #[test]
+    fn event_eng_reward() {
+        let owner_id = &alice();
+        let token_id = &"1:1:3:12".to_string();
+
+        Event::EngReward {
+            owner_id,
+            receiver_id: owner_id,
+            token_id,
+            ship_type: 1,
+            ship_sub_type: 3,
+            capacity: 12,
+            amount: U128(100),
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"eng_reward","data":[{"owner_id":"alice","receiver_id":"alice","token_id":"1:1:3:12","ship_type":1,"ship_sub_type":3,"capacity":12,"amount":"100"}]}"#
+        );
+    }
+
+    #[test]
+    fn event_eng_emission_cap_updated() {
+        Event::EngEmissionCapUpdated {
+            total_cap: Some(U128(1000)),
+            epoch_cap: None,
+            epoch_blocks: 100,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"eng_emission_cap_updated","data":[{"total_cap":"1000","epoch_cap":null,"epoch_blocks":100}]}"#
+        );
+    }
+
+    #[test]
+    fn event_eng_migrated() {
+        let account_ids = &vec![alice()];
+        let amounts = &vec![U128(100)];
+
+        Event::EngMigrated {
+            account_ids,
+            amounts,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"eng_migrated","data":[{"account_ids":["alice"],"amounts":["100"]}]}"#
+        );
+    }
+
+    #[test]
+    fn event_eng_claimable() {
+        let account_id = &alice();
+
+        Event::EngClaimable {
+            account_id,
+            amount: U128(100),
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"eng_claimable","data":[{"account_id":"alice","amount":"100"}]}"#
+        );
+    }
+
+    #[test]
+    fn event_update_user() {
+        let owner_id = &alice();
+        let user_id = &AccountId::new_unchecked("bob".to_string());
+        let token_id = &"1:1:3:12".to_string();
+
+        Event::UpdateUser {
+            token_id,
+            owner_id,
+            user_id,
+            expires_at: 1_700_000_000,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"update_user","data":[{"token_id":"1:1:3:12","owner_id":"alice","user_id":"bob","expires_at":1700000000}]}"#
+        );
+    }
+
+    #[test]
+    fn event_ship_level_up() {
+        let token_id = &"1:1:3:12".to_string();
+
+        Event::ShipLevelUp {
+            token_id,
+            level: 2,
+            xp: 200,
+            effective_capacity: 13,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"ship_level_up","data":[{"token_id":"1:1:3:12","level":2,"xp":200,"effective_capacity":13}]}"#
+        );
+    }
+
+    #[test]
+    fn event_fuse() {
+        let sender_id = &alice();
+        let token_ids = &vec!["1:1:3:12".to_string(), "2:1:5:11".to_string()];
+        let token_id = &"3:2:7:26".to_string();
+
+        Event::FuseEvent {
+            sender_id,
+            token_ids,
+            token_id,
+            ship_type: 2,
+            ship_sub_type: 7,
+            capacity: 26,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"fuse_event","data":[{"sender_id":"alice","token_ids":["1:1:3:12","2:1:5:11"],"token_id":"3:2:7:26","ship_type":2,"ship_sub_type":7,"capacity":26}]}"#
+        );
+    }
+
+    #[test]
+    fn event_collection_completed() {
+        let owner_id = &alice();
+
+        Event::CollectionCompleted {
+            owner_id,
+            ship_type: 4,
+            sub_type_count: 4,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"collection_completed","data":[{"owner_id":"alice","ship_type":4,"sub_type_count":4}]}"#
+        );
+    }
+
+    #[test]
+    fn event_ship_locked() {
+        let token_id = &"1:1:3:12".to_string();
+        let owner_id = &alice();
+        let pool_id = &AccountId::new_unchecked("corepool".to_string());
+
+        Event::ShipLocked {
+            token_id,
+            owner_id,
+            pool_id,
+        }
+        .emit();
+        Event::ShipUnlocked {
+            token_id,
+            owner_id,
+            pool_id,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"ship_locked","data":[{"token_id":"1:1:3:12","owner_id":"alice","pool_id":"corepool"}]}"#
+        );
+        assert_eq!(
+            test_utils::get_logs()[1],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"ship_unlocked","data":[{"token_id":"1:1:3:12","owner_id":"alice","pool_id":"corepool"}]}"#
+        );
+    }
+
+    #[test]
+    fn event_timelock_action() {
+        let action = &TimelockAction::MintEng { amount: U128(100) };
+
+        Event::ActionQueued {
+            action_id: 1,
+            action,
+            eta: 172800,
+        }
+        .emit();
+        Event::ActionExecuted {
+            action_id: 1,
+            action,
+        }
+        .emit();
+        assert_eq!(
+            test_utils::get_logs()[0],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"action_queued","data":[{"action_id":1,"action":{"mint_eng":{"amount":"100"}},"eta":172800}]}"#
+        );
+        assert_eq!(
+            test_utils::get_logs()[1],
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"action_executed","data":[{"action_id":1,"action":{"mint_eng":{"amount":"100"}}}]}"#
+        );
+    }
+
+    #[test]
+    fn event_ship_transfer() {
+        let old_owner_id = &alice();
+        let new_owner_id = &AccountId::new_unchecked("bob".to_string());
+        let token_id = &"1:1:3:12".to_string();
+
+        Event::ShipTransfer {
+            old_owner_id,
+            new_owner_id,
+            token_id,
+            ship_type: 1,
+            ship_sub_type: 3,
+            capacity: 12,
         }
         .emit();
         assert_eq!(
             test_utils::get_logs()[0],
-            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"upgrade_event","data":[{"sender_id":"alice","token_id_1":"1","token_id_2":"2","token_id":"3"}]}"#
+            r#"EVENT_JSON:{"standard":"spaceship","version":"1.0.0","event":"ship_transfer","data":[{"old_owner_id":"alice","new_owner_id":"bob","token_id":"1:1:3:12","ship_type":1,"ship_sub_type":3,"capacity":12}]}"#
         );
     }
 }
@@ -1,19 +1,39 @@
 use crate::*;
+use near_contract_standards::fungible_token::core::FungibleTokenCore;
 use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC,
 };
+use near_contract_standards::fungible_token::resolver::FungibleTokenResolver;
+use near_sdk::Promise;
+
+pub const GAS_FOR_ENG_MINT: Gas = Gas(10 * TGAS);
+pub const GAS_FOR_RESOLVE_ENG_MINT: Gas = Gas(5 * TGAS);
+pub const GAS_FOR_ENG_BURN: Gas = Gas(10 * TGAS);
+pub const GAS_FOR_RESOLVE_ENG_BURN: Gas = Gas(5 * TGAS);
+pub const GAS_FOR_MIGRATE_ENG: Gas = Gas(50 * TGAS);
+pub const GAS_FOR_RESOLVE_MIGRATE_ENG: Gas = Gas(10 * TGAS);
+
+/// The standalone ENG token, spaceship is one of its minters and burners
+#[ext_contract(ext_eng)]
+pub trait EngToken {
+    fn mint(&mut self, receiver_id: AccountId, amount: U128);
+    fn burn_from(&mut self, account_id: AccountId, amount: U128);
+    fn migrate_balances(&mut self, account_ids: Vec<AccountId>, amounts: Vec<U128>);
+}
 
 #[near_bindgen]
 impl Contract {
     #[payable]
     pub fn burn_eng(&mut self, amount: U128) {
         assert_one_yocto();
+        self.assert_eng_not_migrated();
         let sender_id = env::predecessor_account_id();
-        self.eng.internal_withdraw(&sender_id, amount.into());
+        self.internal_burn_eng(&sender_id, amount);
     }
 
+    /// returns whether the ENG was burned
     #[payable]
-    pub fn burn_eng_for_user(&mut self, user: AccountId, amount: U128) {
+    pub fn burn_eng_for_user(&mut self, user: AccountId, amount: U128) -> PromiseOrValue<bool> {
         assert_one_yocto();
         let predecessor_account_id = env::predecessor_account_id();
         require!(
@@ -21,7 +41,39 @@ impl Contract {
                 || predecessor_account_id == self.luckpool_id,
             "Invalid contract id"
         );
-        self.eng.internal_withdraw(&user, amount.into());
+        if amount.0 == 0 {
+            return PromiseOrValue::Value(true);
+        }
+        match self.eng_token_id.clone() {
+            // forward until callers burn on the ENG token themselves
+            Some(eng_token_id) => {
+                ext_eng::burn_from(user, amount, eng_token_id, 1, GAS_FOR_ENG_BURN)
+                    .then(ext_self::resolve_burn_eng_for_user(
+                        amount,
+                        env::current_account_id(),
+                        0,
+                        GAS_FOR_RESOLVE_ENG_BURN,
+                    ))
+                    .into()
+            }
+            None => {
+                self.internal_burn_eng(&user, amount);
+                PromiseOrValue::Value(true)
+            }
+        }
+    }
+
+    /// the burn is counted once the ENG token confirmed it
+    #[private]
+    pub fn resolve_burn_eng_for_user(&mut self, amount: U128) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => {
+                self.eng_emission.record_burn(amount.0);
+                true
+            }
+            PromiseResult::Failed => false,
+        }
     }
 
     /// Arguments:
@@ -36,6 +88,7 @@ impl Contract {
         memo: Option<String>,
     ) {
         assert_one_yocto();
+        self.assert_eng_not_migrated();
         let sender_id = env::predecessor_account_id();
         let receivers: HashMap<_, _> = receiver_ids.iter().zip(amounts.iter()).collect();
         for (receiver, amount) in receivers {
@@ -44,8 +97,7 @@ impl Contract {
             }
             if receiver.is_empty() {
                 // burn eng token
-                self.eng
-                    .internal_withdraw(&sender_id, amount.clone().into());
+                self.internal_burn_eng(&sender_id, *amount);
             } else {
                 let receiver_id: AccountId = receiver.parse().expect("ERR_INVALID_RECEIVER_ID");
                 self.eng.internal_transfer(
@@ -57,6 +109,117 @@ impl Contract {
             }
         }
     }
+
+    /// [SSP-00-28] move the ENG balances of `account_ids` out of spaceship into the standalone token,
+    /// balances are restored if the token rejects them.
+    #[payable]
+    pub fn migrate_eng_balances(&mut self, account_ids: Vec<AccountId>) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let eng_token_id = self.eng_token_id.clone().expect("ERR_ENG_TOKEN_NOT_SET");
+
+        let mut migrated: Vec<AccountId> = vec![];
+        let mut amounts: Vec<U128> = vec![];
+        for account_id in account_ids {
+            let balance = self.eng.accounts.get(&account_id).unwrap_or(0);
+            if balance == 0 {
+                continue;
+            }
+            self.eng.internal_withdraw(&account_id, balance);
+            migrated.push(account_id);
+            amounts.push(U128(balance));
+        }
+        require!(!migrated.is_empty(), "ERR_NOTHING_TO_MIGRATE");
+
+        ext_eng::migrate_balances(
+            migrated.clone(),
+            amounts.clone(),
+            eng_token_id,
+            1,
+            GAS_FOR_MIGRATE_ENG,
+        )
+        .then(ext_self::resolve_migrate_eng(
+            migrated,
+            amounts,
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_MIGRATE_ENG,
+        ))
+    }
+
+    #[private]
+    pub fn resolve_migrate_eng(&mut self, account_ids: Vec<AccountId>, amounts: Vec<U128>) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => {
+                Event::EngMigrated {
+                    account_ids: &account_ids,
+                    amounts: &amounts,
+                }
+                .emit();
+                true
+            }
+            PromiseResult::Failed => {
+                for (account_id, amount) in account_ids.iter().zip(amounts.iter()) {
+                    self.eng.internal_deposit(account_id, amount.0);
+                }
+                false
+            }
+        }
+    }
+}
+
+impl Contract {
+    /// the local ENG ledger is read-only once ENG moved to its own token
+    fn assert_eng_not_migrated(&self) {
+        require!(self.eng_token_id.is_none(), "ERR_ENG_MIGRATED");
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenCore for Contract {
+    /// the local ENG stops moving once ENG moved to its own token
+    #[payable]
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>) {
+        self.assert_eng_not_migrated();
+        self.eng.ft_transfer(receiver_id, amount, memo)
+    }
+
+    #[payable]
+    fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        self.assert_eng_not_migrated();
+        self.eng.ft_transfer_call(receiver_id, amount, memo, msg)
+    }
+
+    fn ft_total_supply(&self) -> U128 {
+        self.eng.ft_total_supply()
+    }
+
+    fn ft_balance_of(&self, account_id: AccountId) -> U128 {
+        self.eng.ft_balance_of(account_id)
+    }
+}
+
+#[near_bindgen]
+impl FungibleTokenResolver for Contract {
+    #[private]
+    fn ft_resolve_transfer(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> U128 {
+        let (used_amount, _burned_amount) =
+            self.eng
+                .internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
+        used_amount.into()
+    }
 }
 
 #[near_bindgen]
@@ -97,7 +260,11 @@ mod tests {
             .predecessor_account_id(accounts(0))
             .attached_deposit(1)
             .build());
-        contract.mint_eng(1_000_000.into());
+        let action_id = contract.mint_eng(1_000_000.into());
+        testing_env!(context
+            .block_timestamp(DEFAULT_TIMELOCK_DELAY * 10u64.pow(9))
+            .build());
+        contract.execute_action(action_id);
         assert_eq!(contract.ft_balance_of(accounts(0)), 1_000_000.into());
 
         testing_env!(context
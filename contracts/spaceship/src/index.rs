@@ -0,0 +1,128 @@
+use crate::*;
+
+/// Narrows `get_ships_for_owner`, every field left out matches all ships
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipFilter {
+    pub ship_type: Option<u8>,
+    /// only valid together with `ship_type`
+    pub ship_sub_type: Option<u8>,
+    /// the capacity bounds apply to the effective capacity, raised by the ship level
+    pub min_capacity: Option<u32>,
+    pub max_capacity: Option<u32>,
+}
+
+impl ShipFilter {
+    fn matches(&self, progress: &ShipProgress) -> bool {
+        self.min_capacity
+            .is_none_or(|min| progress.effective_capacity >= min)
+            && self
+                .max_capacity
+                .is_none_or(|max| progress.effective_capacity <= max)
+    }
+}
+
+/// index keys of a ship, one for its type and one for its subtype
+fn ship_index_keys(owner_id: &AccountId, ship: &ShipAttributes) -> [String; 2] {
+    [
+        format!("{}:{}", owner_id, ship.ship_type),
+        format!("{}:{}:{}", owner_id, ship.ship_type, ship.ship_sub_type),
+    ]
+}
+
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-32]
+    /// Return the active ships of an owner matching `filter`, paginated over the matches
+    pub fn get_ships_for_owner(
+        &self,
+        account_id: AccountId,
+        filter: Option<ShipFilter>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<ShipAttributes> {
+        let filter = filter.unwrap_or_default();
+        let key = match (filter.ship_type, filter.ship_sub_type) {
+            (Some(ship_type), Some(ship_sub_type)) => {
+                Some(format!("{}:{}:{}", account_id, ship_type, ship_sub_type))
+            }
+            (Some(ship_type), None) => Some(format!("{}:{}", account_id, ship_type)),
+            (None, Some(_)) => env::panic_str("ERR_ILLEGAL_FILTER"),
+            (None, None) => None,
+        };
+        let token_ids = match key {
+            Some(key) => self.ships_per_owner.get(&key),
+            None => self
+                .tokens
+                .tokens_per_owner
+                .as_ref()
+                .and_then(|tokens_per_owner| tokens_per_owner.get(&account_id)),
+        };
+
+        match token_ids {
+            Some(token_ids) => token_ids
+                .iter()
+                .filter(|token_id| filter.matches(&self.internal_ship_progress(token_id)))
+                .skip(from_index.unwrap_or(0) as usize)
+                .take(limit.unwrap_or(u64::MAX) as usize)
+                .map(|token_id| self.internal_ship_attributes(&token_id))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// index the ships `account_id` held before the per-owner index existed
+    #[payable]
+    pub fn index_ships_for_owner(&mut self, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let token_ids = self
+            .tokens
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(&account_id))
+            .map(|token_ids| token_ids.to_vec())
+            .unwrap_or_default();
+        for token_id in token_ids.iter() {
+            let ship = self.internal_ship_attributes(token_id);
+            self.internal_index_ship(token_id, &ship, None, Some(&account_id));
+        }
+    }
+}
+
+impl Contract {
+    /// move a ship between the per-owner type and subtype sets,
+    /// `from` is None on mint and `to` is None on burn
+    pub(crate) fn internal_index_ship(
+        &mut self,
+        token_id: &TokenId,
+        ship: &ShipAttributes,
+        from: Option<&AccountId>,
+        to: Option<&AccountId>,
+    ) {
+        if let Some(owner_id) = from {
+            for key in ship_index_keys(owner_id, ship).iter() {
+                if let Some(mut token_ids) = self.ships_per_owner.get(key) {
+                    token_ids.remove(token_id);
+                    if token_ids.is_empty() {
+                        self.ships_per_owner.remove(key);
+                    } else {
+                        self.ships_per_owner.insert(key, &token_ids);
+                    }
+                }
+            }
+        }
+        if let Some(owner_id) = to {
+            for key in ship_index_keys(owner_id, ship).iter() {
+                let mut token_ids = self.ships_per_owner.get(key).unwrap_or_else(|| {
+                    UnorderedSet::new(StorageKey::ShipsPerOwner {
+                        key_hash: env::sha256(key.as_bytes()),
+                    })
+                });
+                token_ids.insert(token_id);
+                self.ships_per_owner.insert(key, &token_ids);
+            }
+        }
+    }
+}
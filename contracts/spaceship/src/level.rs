@@ -0,0 +1,174 @@
+use crate::*;
+
+pub const SECONDS_PER_XP: u64 = 3600;
+pub const GAS_FOR_ON_SHIP_LEVEL_UP: Gas = Gas(5 * TGAS);
+// level and fuse bonuses add at most ten times the rolled capacity
+pub const MAX_CAPACITY_BONUS: u32 = 1_000;
+
+/// Staking pools count a ship with its effective capacity once it leveled up
+#[ext_contract(ext_level_receiver)]
+pub trait ShipLevelReceiver {
+    fn on_ship_level_up(&mut self, token_id: TokenId, effective_capacity: u32);
+}
+
+/// A level is reached once a ship gathered `xp`,
+/// it raises the capacity by `capacity_bonus` percent of the rolled capacity.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipLevel {
+    pub xp: u64,
+    pub capacity_bonus: u32,
+}
+
+/// Progress of a ship, the rolled capacity in its attributes and token id never changes
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipProgress {
+    /// one xp per hour staked in a pool
+    pub xp: u64,
+    pub level: u8,
+    /// rolled capacity raised by the level bonus
+    pub effective_capacity: u32,
+    /// all seconds reported staked, xp counts the full hours of it
+    pub staked_seconds: u64,
+}
+
+/// `TokenMetadata.extra` of a ship
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub(crate) struct ShipExtra<'a> {
+    #[serde(flatten)]
+    pub attributes: &'a ShipAttributes,
+    #[serde(flatten)]
+    pub progress: &'a ShipProgress,
+}
+
+/// `capacity` raised by `bonus` percent, saturating instead of overflowing
+pub(crate) fn apply_capacity_bonus(capacity: u32, bonus: u32) -> u32 {
+    let raised = capacity as u64 + capacity as u64 * bonus as u64 / 100;
+    std::cmp::min(raised, u32::MAX as u64) as u32
+}
+
+/// The levels a new spaceship contract starts with, xp counted in staked hours
+pub fn default_ship_levels() -> Vec<ShipLevel> {
+    vec![
+        ShipLevel {
+            xp: 24,
+            capacity_bonus: 5,
+        },
+        ShipLevel {
+            xp: 24 * 7,
+            capacity_bonus: 10,
+        },
+        ShipLevel {
+            xp: 24 * 30,
+            capacity_bonus: 20,
+        },
+        ShipLevel {
+            xp: 24 * 90,
+            capacity_bonus: 35,
+        },
+        ShipLevel {
+            xp: 24 * 180,
+            capacity_bonus: 50,
+        },
+    ]
+}
+
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-30] staking pools report how long a ship was staked when it is withdrawn,
+    /// a level up is pushed to every staking pool
+    pub fn add_ship_xp(&mut self, token_id: TokenId, staked_seconds: u64) {
+        require!(
+            self.staking_pools.contains(&env::predecessor_account_id()),
+            "ERR_NOT_STAKING_POOL"
+        );
+        // the ship may be burned before the report arrives
+        if self.tokens.owner_by_id.get(&token_id).is_none() {
+            return;
+        }
+
+        let mut progress = self.internal_ship_progress(&token_id);
+        progress.staked_seconds += staked_seconds;
+        progress.xp = progress.staked_seconds / SECONDS_PER_XP;
+        let level = self
+            .ship_levels
+            .iter()
+            .take_while(|level| progress.xp >= level.xp)
+            .count() as u8;
+        if level != progress.level {
+            let capacity = self.internal_ship_attributes(&token_id).capacity;
+            let bonus = match level {
+                0 => 0,
+                _ => self.ship_levels[level as usize - 1].capacity_bonus,
+            };
+            progress.level = level;
+            progress.effective_capacity = apply_capacity_bonus(capacity, bonus);
+
+            Event::ShipLevelUp {
+                token_id: &token_id,
+                level,
+                xp: progress.xp,
+                effective_capacity: progress.effective_capacity,
+            }
+            .emit();
+
+            // the pools stake the ship with its new capacity from now on
+            for pool_id in self.staking_pools.iter() {
+                ext_level_receiver::on_ship_level_up(
+                    token_id.clone(),
+                    progress.effective_capacity,
+                    pool_id,
+                    0,
+                    GAS_FOR_ON_SHIP_LEVEL_UP,
+                );
+            }
+        }
+        self.ship_progress.insert(&token_id, &progress);
+    }
+
+    /// set the xp thresholds, they must be ascending
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn set_ship_levels(&mut self, levels: Vec<ShipLevel>) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            levels.len() <= u8::MAX as usize
+                && levels.windows(2).all(|w| w[0].xp < w[1].xp)
+                && levels.first().is_none_or(|level| level.xp > 0)
+                && levels
+                    .iter()
+                    .all(|level| level.capacity_bonus <= MAX_CAPACITY_BONUS),
+            "ERR_ILLEGAL_LEVELS"
+        );
+        self.internal_queue_action(TimelockAction::SetShipLevels { levels })
+    }
+
+    pub fn get_ship_levels(&self) -> Vec<ShipLevel> {
+        self.ship_levels.clone()
+    }
+
+    pub fn get_ship_progress(&self, token_id: TokenId) -> Option<ShipProgress> {
+        self.tokens
+            .owner_by_id
+            .get(&token_id)
+            .map(|_| self.internal_ship_progress(&token_id))
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_ship_progress(&self, token_id: &TokenId) -> ShipProgress {
+        self.ship_progress
+            .get(token_id)
+            .unwrap_or_else(|| ShipProgress {
+                xp: 0,
+                level: 0,
+                effective_capacity: self.internal_ship_attributes(token_id).capacity,
+                staked_seconds: 0,
+            })
+    }
+}
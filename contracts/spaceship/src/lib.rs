@@ -7,50 +7,69 @@ use near_contract_standards::fungible_token::FungibleToken;
 use near_contract_standards::non_fungible_token::enumeration::NonFungibleTokenEnumeration;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap};
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    assert_one_yocto, env, ext_contract, near_bindgen, require, AccountId, BorshStorageKey, Gas,
-    PanicOnDefault, PromiseOrValue,
+    assert_one_yocto, env, ext_contract, log, near_bindgen, require, AccountId, Balance,
+    BorshStorageKey, Gas, PanicOnDefault, PromiseOrValue, PromiseResult,
 };
-use rand::rngs::StdRng;
-use rand::{Rng, SeedableRng};
 use std::collections::HashMap;
 
+mod collection;
+mod emission;
 mod events;
 mod ft;
+mod index;
+mod level;
+mod lock;
+mod metadata;
 mod mynft;
 mod nft;
 mod owner;
+mod payout;
+mod rental;
+mod storage;
+mod tier;
+mod timelock;
 mod view;
 
+pub use crate::collection::*;
+pub use crate::emission::*;
 pub use crate::events::*;
 pub use crate::ft::*;
+pub use crate::index::*;
+pub use crate::level::*;
+pub use crate::lock::*;
+pub use crate::metadata::*;
 pub use crate::nft::*;
 pub use crate::owner::*;
+pub use crate::payout::*;
+pub use crate::rental::*;
+pub use crate::storage::*;
+pub use crate::tier::*;
+pub use crate::timelock::*;
 pub use crate::view::*;
 use mynft::MyNonFungibleToken;
+pub use random::*;
 
 pub const TGAS: u64 = 1_000_000_000_000;
 pub const GAS_FOR_REGISTER_SHIP: Gas = Gas(70 * TGAS);
+pub const GAS_FOR_RESOLVE_REGISTER: Gas = Gas(10 * TGAS);
+pub const GAS_FOR_RESOLVE_UPGRADE: Gas = Gas(95 * TGAS);
 pub const GAS_FOR_TRANSFER: Gas = Gas(25 * TGAS);
 pub const YOCTO18: u128 = 1_000_000_000_000_000_000;
 pub const MAX_ICON_LENGTH: usize = 2048;
+pub const DEFAULT_MAX_BURN_BATCH: u32 = 50;
+// roughly one day with 1s blocks
+pub const DEFAULT_EMISSION_EPOCH_BLOCKS: u64 = 86_400;
 
-#[derive(BorshSerialize, BorshDeserialize, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
 pub struct CapacityRange {
-    min: u32,
-    max: u32,
-}
-
-#[derive(BorshSerialize, BorshDeserialize)]
-pub struct Capacity {
-    capacity_a: CapacityRange,
-    capacity_b: CapacityRange,
-    capacity_c: CapacityRange,
-    capacity_d: CapacityRange,
-    capacity_s: CapacityRange,
+    pub min: u32,
+    pub max: u32,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Clone)]
@@ -59,17 +78,19 @@ pub struct ShipSupply {
     burned: u32,
 }
 
-#[derive(Serialize, Deserialize)]
+/// Typed attributes of a spaceship, stored on-chain per token
+/// and exposed in `TokenMetadata.extra` as JSON.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
-#[cfg_attr(feature = "test", derive(Debug, C&lone))]
-pub struct ShipElements {
-    prefix_id: u64,
-    ship_type: u8,
-    ship_sub_type: u8,
-    capacity: u32,
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipAttributes {
+    pub prefix_id: u64,
+    pub ship_type: u8,
+    pub ship_sub_type: u8,
+    pub capacity: u32,
 }
 
-impl ShipElements {
+impl ShipAttributes {
     pub fn to_token_id(&self) -> TokenId {
         format!(
             "{}:{}:{}:{}",
@@ -78,12 +99,43 @@ impl ShipElements {
     }
 }
 
+/// kept for callers of `get_spaceship_list_for_owner`
+pub type ShipElements = ShipAttributes;
+
 #[ext_contract(ext_shippool)]
 pub trait ShipPool {
-    fn register_ship(&mut self, token_id: String, token_owner_id: AccountId);
     fn batch_register_ships(&mut self, token_ids: Vec<String>, token_owner_id: AccountId);
 }
 
+#[ext_contract(ext_self)]
+pub trait ShipResolver {
+    fn resolve_register_ships(&mut self, token_ids: Vec<TokenId>, owner_id: AccountId) -> bool;
+    fn resolve_migrate_eng(&mut self, account_ids: Vec<AccountId>, amounts: Vec<U128>) -> bool;
+    fn resolve_upgrade_spaceship(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_id_1: TokenId,
+        token_id_2: TokenId,
+        target_sub_type: u8,
+        eng_amount: U128,
+    ) -> bool;
+    fn resolve_fuse_spaceship(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+        eng_amount: U128,
+    ) -> bool;
+    fn resolve_burn_eng_for_user(&mut self, amount: U128) -> bool;
+    fn resolve_mint_eng(&mut self, receiver_id: AccountId, amount: U128) -> bool;
+    fn resolve_lock(&mut self, token_id: TokenId, pool_id: AccountId) -> bool;
+    fn resolve_unlock(&mut self, token_id: TokenId, pool_id: AccountId) -> bool;
+}
+
 #[derive(BorshStorageKey, BorshSerialize)]
 pub(crate) enum StorageKey {
     NonFungibleToken,
@@ -96,6 +148,25 @@ pub(crate) enum StorageKey {
     Icon,
 
     Eng,
+    Approval,
+    NextApprovalId,
+    Royalty,
+    ShipAttributes,
+    PendingRegistrations,
+    ShipTiers,
+    MetadataTemplate,
+    Rentals,
+    StakingPools,
+    ShipProgress,
+    ShipsPerOwnerIndex,
+    ShipsPerOwner { key_hash: Vec<u8> },
+    Collections,
+    NftStorage,
+    ShipStoragePayers,
+    ShipLocks,
+    PendingActions,
+    EngClaimable,
+    TierListeners,
 }
 
 #[near_bindgen]
@@ -113,18 +184,76 @@ pub struct Contract {
     supply_per_owner: LookupMap<AccountId, UnorderedMap<String, ShipSupply>>,
     // ship_type:ship_sub_type, to ShipSupply
     icons: UnorderedMap<String, String>,
+    // token_id to its attributes
+    ship_attributes: LookupMap<TokenId, ShipAttributes>,
 
     box_id: AccountId,
     shippool_id: AccountId,
     shipmarket_id: AccountId,
     auction_id: AccountId,
     luckpool_id: AccountId,
+    // None for contracts migrated before collectpool was a default tier listener
+    collectpool_id: Option<AccountId>,
+
+    // ship_type => (royalty receiver => basis points), used by NEP-199 payouts
+    royalties: UnorderedMap<u8, HashMap<AccountId, u32>>,
+
+    // bumped on every roll so rolls in the same block differ
+    random_nonce: u64,
+
+    // owner => ships whose shippool registration failed, waiting for a retry
+    pending_registrations: UnorderedMap<AccountId, Vec<TokenId>>,
+
+    // ship_type => tier, replaces the hard-coded capacity and reward tables
+    tiers: UnorderedMap<u8, ShipTier>,
+
+    // "type" or "type:subtype" => token metadata template
+    metadata_templates: UnorderedMap<String, ShipMetadataTemplate>,
+
+    // max number of ships in one user_batch_burn
+    max_burn_batch: u32,
+
+    // ENG minted per source, burned total and emission caps
+    eng_emission: EngEmission,
+
+    // the standalone ENG token, None while ENG still lives in this contract
+    eng_token_id: Option<AccountId>,
+
+    // token_id => usage rights granted by the owner
+    rentals: LookupMap<TokenId, ShipRental>,
+    // pools that accept rented ships staked by their users
+    staking_pools: UnorderedSet<AccountId>,
+
+    // token_id => xp and level gathered by staking
+    ship_progress: LookupMap<TokenId, ShipProgress>,
+    // xp thresholds of each level, ascending
+    ship_levels: Vec<ShipLevel>,
+
+    // "owner:type" and "owner:type:subtype" => active token ids
+    ships_per_owner: LookupMap<String, UnorderedSet<TokenId>>,
 
-    // type => array  arr[0] is min and arr[1] is max.
-    capacity: Capacity,
+    // owner => ship_type => distinct subtypes held, recounted when a subtype is gained or lost
+    collections: LookupMap<AccountId, HashMap<u8, u8>>,
 
-    // eng_type_reward
-    eng_type_reward: Vec<u128>, // TYPE_S, TYPE_A, TYPE_B, TYPE_C, TYPE_D
+    // NEAR deposited for the storage of minted ships, only charged when required
+    nft_storage: LookupMap<AccountId, NftStorage>,
+    nft_storage_required: bool,
+    // token_id => account that paid its storage and the bytes it paid for
+    ship_storage_payers: LookupMap<TokenId, (AccountId, u64)>,
+
+    // ships staked in a pool while staying with their owner
+    ship_locks: LookupMap<TokenId, ShipLock>,
+
+    // owner actions waiting for their eta, `timelock_delay` in seconds
+    next_action_id: u64,
+    pending_actions: UnorderedMap<u64, PendingAction>,
+    timelock_delay: u64,
+
+    // ENG the standalone token failed to mint, the receiver claims it again
+    eng_claimable: LookupMap<AccountId, Balance>,
+
+    // contracts besides magicbox, shipmarket and shippool that keep a copy of the tier registry
+    tier_listeners: UnorderedSet<AccountId>,
 }
 
 #[near_bindgen]
@@ -137,6 +266,7 @@ impl Contract {
         shipmarket_id: AccountId,
         auction_id: AccountId,
         luckpool_id: AccountId,
+        collectpool_id: AccountId,
     ) -> Self {
         assert!(!env::state_exists(), "Already initialized");
         let mut contract = Contract {
@@ -160,35 +290,71 @@ impl Contract {
             total_supplies: UnorderedMap::new(StorageKey::TotalSupply),
             supply_per_owner: LookupMap::new(StorageKey::Supply),
             icons: UnorderedMap::new(StorageKey::Icon),
+            ship_attributes: LookupMap::new(StorageKey::ShipAttributes),
 
             box_id,
             shippool_id,
             shipmarket_id,
             auction_id,
             luckpool_id,
+            collectpool_id: Some(collectpool_id),
+
+            royalties: UnorderedMap::new(StorageKey::Royalty),
+
+            random_nonce: 0,
+
+            pending_registrations: UnorderedMap::new(StorageKey::PendingRegistrations),
+
+            tiers: UnorderedMap::new(StorageKey::ShipTiers),
+
+            metadata_templates: UnorderedMap::new(StorageKey::MetadataTemplate),
+
+            max_burn_batch: DEFAULT_MAX_BURN_BATCH,
 
-            capacity: Capacity {
-                capacity_a: CapacityRange { min: 80, max: 120 },
-                capacity_b: CapacityRange { min: 40, max: 60 },
-                capacity_c: CapacityRange { min: 20, max: 30 },
-                capacity_d: CapacityRange { min: 10, max: 15 },
-                capacity_s: CapacityRange { min: 240, max: 240 },
-            },
+            eng_emission: EngEmission::new(),
 
-            // TYPE_S, TYPE_A, TYPE_B, TYPE_C, TYPE_D. Notice: the index is reversed
-            eng_type_reward: vec![YOCTO18, 2 * YOCTO18, 4 * YOCTO18, 8 * YOCTO18, 20 * YOCTO18],
+            eng_token_id: None,
+
+            rentals: LookupMap::new(StorageKey::Rentals),
+            staking_pools: UnorderedSet::new(StorageKey::StakingPools),
+
+            ship_progress: LookupMap::new(StorageKey::ShipProgress),
+            ship_levels: default_ship_levels(),
+
+            ships_per_owner: LookupMap::new(StorageKey::ShipsPerOwnerIndex),
+
+            collections: LookupMap::new(StorageKey::Collections),
+
+            nft_storage: LookupMap::new(StorageKey::NftStorage),
+            nft_storage_required: false,
+            ship_storage_payers: LookupMap::new(StorageKey::ShipStoragePayers),
+
+            ship_locks: LookupMap::new(StorageKey::ShipLocks),
+
+            next_action_id: 0,
+            pending_actions: UnorderedMap::new(StorageKey::PendingActions),
+            timelock_delay: DEFAULT_TIMELOCK_DELAY,
+
+            eng_claimable: LookupMap::new(StorageKey::EngClaimable),
+
+            tier_listeners: UnorderedSet::new(StorageKey::TierListeners),
         };
-        // mint 4 S-class spaceship
-        for i in 1..(1 + TYPE_S_COUNT) {
+        for (ship_type, tier) in default_ship_tiers() {
+            contract.tiers.insert(&ship_type, &tier);
+        }
+        // mint one S-class spaceship per subtype
+        let tier_s = contract.internal_get_tier(TYPE_S);
+        for i in 1..(1 + tier_s.sub_type_count) {
+            let ship = ShipAttributes {
+                prefix_id: contract.next_id,
+                ship_type: TYPE_S,
+                ship_sub_type: i,
+                capacity: tier_s.capacity.min,
+            };
             contract.mint_ship_with_supply_updated(
                 env::current_account_id(),
-                &ShipElements {
-                    prefix_id: contract.next_id,
-                    ship_type: TYPE_S,
-                    ship_sub_type: i as u8,
-                    capacity: 240,
-                }
-                .to_token_id(),
+                &ship.to_token_id(),
+                &ship,
             );
             contract.next_id += 1;
         }
@@ -197,5 +363,4 @@ impl Contract {
     }
 }
 
-near_contract_standards::impl_fungible_token_core!(Contract, eng);
 near_contract_standards::impl_fungible_token_storage!(Contract, eng);
@@ -0,0 +1,189 @@
+use crate::*;
+use near_sdk::Promise;
+
+pub const GAS_FOR_NFT_ON_LOCK: Gas = Gas(50 * TGAS);
+pub const GAS_FOR_RESOLVE_LOCK: Gas = Gas(10 * TGAS);
+pub const GAS_FOR_IS_LOCKED_SHIP: Gas = Gas(5 * TGAS);
+pub const GAS_FOR_RESOLVE_UNLOCK: Gas = Gas(10 * TGAS);
+
+/// A ship staked in a pool without leaving its owner,
+/// it can not be transferred or burned until `pool_id` unlocks it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipLock {
+    pub pool_id: AccountId,
+    /// the owner, or the user of a rented ship
+    pub locked_by: AccountId,
+}
+
+/// Staking pools accept locked ships the same way they accept `nft_transfer_call`
+#[ext_contract(ext_lock_receiver)]
+pub trait ShipLockReceiver {
+    /// returns true if the ship is staked, false unlocks it again
+    fn nft_on_lock(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool>;
+
+    /// whether the pool still keeps the ship staked
+    fn is_locked_ship(&self, token_id: TokenId) -> bool;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-36] lock a ship into a staking pool, the owner or the user of a rented ship
+    /// stakes it in place and keeps it on its account.
+    #[payable]
+    pub fn lock(&mut self, token_id: TokenId, pool: AccountId, msg: String) -> Promise {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("ERR: Token not found");
+        require!(self.staking_pools.contains(&pool), "ERR_NOT_STAKING_POOL");
+        self.internal_assert_not_locked(&token_id);
+        match self.internal_active_rental(&token_id) {
+            Some(rental) => require!(sender_id == rental.user_id, "ERR_SHIP_RENTED"),
+            None => require!(sender_id == owner_id, "ERR_NOT_NFT_OWNER"),
+        }
+
+        self.ship_locks.insert(
+            &token_id,
+            &ShipLock {
+                pool_id: pool.clone(),
+                locked_by: sender_id.clone(),
+            },
+        );
+        Event::ShipLocked {
+            token_id: &token_id,
+            owner_id: &owner_id,
+            pool_id: &pool,
+        }
+        .emit();
+
+        ext_lock_receiver::nft_on_lock(
+            sender_id,
+            owner_id,
+            token_id.clone(),
+            msg,
+            pool.clone(),
+            0,
+            GAS_FOR_NFT_ON_LOCK,
+        )
+        .then(ext_self::resolve_lock(
+            token_id,
+            pool,
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_LOCK,
+        ))
+    }
+
+    /// [SSP-00-37] the pool holding the lock releases it,
+    /// the ship owner or the owner can once that pool is removed from the staking pools
+    /// or no longer keeps the ship staked, e.g. after its own unlock call failed.
+    #[payable]
+    pub fn unlock(&mut self, token_id: TokenId) -> PromiseOrValue<bool> {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let lock = self.ship_locks.get(&token_id).expect("ERR_SHIP_NOT_LOCKED");
+        if sender_id != lock.pool_id {
+            require!(
+                sender_id == self.owner_id
+                    || Some(sender_id) == self.tokens.owner_by_id.get(&token_id),
+                "ERR_NOT_ALLOWED"
+            );
+            if self.staking_pools.contains(&lock.pool_id) {
+                return ext_lock_receiver::is_locked_ship(
+                    token_id.clone(),
+                    lock.pool_id.clone(),
+                    0,
+                    GAS_FOR_IS_LOCKED_SHIP,
+                )
+                .then(ext_self::resolve_unlock(
+                    token_id,
+                    lock.pool_id,
+                    env::current_account_id(),
+                    0,
+                    GAS_FOR_RESOLVE_UNLOCK,
+                ))
+                .into();
+            }
+        }
+        self.internal_unlock(&token_id, &lock.pool_id);
+        PromiseOrValue::Value(true)
+    }
+
+    /// keep the lock if the pool staked the ship
+    #[private]
+    pub fn resolve_lock(&mut self, token_id: TokenId, pool_id: AccountId) -> bool {
+        let staked = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                near_sdk::serde_json::from_slice::<bool>(&value).unwrap_or(false)
+            }
+            PromiseResult::Failed => false,
+        };
+        if !staked
+            && self
+                .ship_locks
+                .get(&token_id)
+                .is_some_and(|lock| lock.pool_id == pool_id)
+        {
+            self.internal_unlock(&token_id, &pool_id);
+        }
+        staked
+    }
+
+    /// release the lock only if the pool no longer keeps the ship staked
+    #[private]
+    pub fn resolve_unlock(&mut self, token_id: TokenId, pool_id: AccountId) -> bool {
+        let staked = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(value) => {
+                near_sdk::serde_json::from_slice::<bool>(&value).unwrap_or(true)
+            }
+            PromiseResult::Failed => true,
+        };
+        if staked {
+            log!("ERR_NOT_LOCKING_POOL");
+            return false;
+        }
+        if self
+            .ship_locks
+            .get(&token_id)
+            .is_some_and(|lock| lock.pool_id == pool_id)
+        {
+            self.internal_unlock(&token_id, &pool_id);
+        }
+        true
+    }
+
+    pub fn get_ship_lock(&self, token_id: TokenId) -> Option<ShipLock> {
+        self.ship_locks.get(&token_id)
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_assert_not_locked(&self, token_id: &TokenId) {
+        require!(self.ship_locks.get(token_id).is_none(), "ERR_SHIP_LOCKED");
+    }
+
+    pub(crate) fn internal_unlock(&mut self, token_id: &TokenId, pool_id: &AccountId) {
+        self.ship_locks.remove(token_id);
+        if let Some(owner_id) = self.tokens.owner_by_id.get(token_id) {
+            Event::ShipUnlocked {
+                token_id,
+                owner_id: &owner_id,
+                pool_id,
+            }
+            .emit();
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// Token metadata shared by every ship of a type (key "type") or a subtype (key "type:subtype")
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipMetadataTemplate {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// bulk upload templates, keyed by "type" or "type:subtype"; a subtype template wins over its type one
+    #[payable]
+    pub fn set_ship_metadata_templates(
+        &mut self,
+        templates: HashMap<String, ShipMetadataTemplate>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        for (key, template) in templates.iter() {
+            self.assert_template_key(key);
+            if let Some(media) = &template.media {
+                require!(media.len() <= MAX_ICON_LENGTH, "ERR_ICON_TOO_LARGE");
+            }
+            require!(
+                template.media_hash.as_ref().is_none_or(|h| h.0.len() == 32),
+                "ERR_ILLEGAL_HASH"
+            );
+            require!(
+                template
+                    .reference_hash
+                    .as_ref()
+                    .is_none_or(|h| h.0.len() == 32),
+                "ERR_ILLEGAL_HASH"
+            );
+            self.metadata_templates.insert(key, template);
+        }
+    }
+
+    #[payable]
+    pub fn remove_ship_metadata_template(&mut self, key: String) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.metadata_templates.remove(&key);
+    }
+
+    /// update `base_uri` and `icon` of the NFT contract metadata
+    #[payable]
+    pub fn set_nft_metadata(&mut self, base_uri: Option<String>, icon: Option<String>) {
+        assert_one_yocto();
+        self.assert_owner();
+        if let Some(icon) = &icon {
+            require!(icon.len() <= MAX_ICON_LENGTH, "ERR_ICON_TOO_LARGE");
+        }
+        let mut metadata = self.metadata.get().unwrap();
+        metadata.base_uri = base_uri;
+        metadata.icon = icon;
+        self.metadata.set(&metadata);
+    }
+
+    pub fn get_ship_metadata_template(&self, key: String) -> Option<ShipMetadataTemplate> {
+        self.metadata_templates.get(&key)
+    }
+
+    pub fn get_ship_metadata_templates(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(String, ShipMetadataTemplate)> {
+        self.metadata_templates
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    fn assert_template_key(&self, key: &str) {
+        let items: Vec<&str> = key.split(":").collect();
+        require!(items.len() <= 2, "ERR_ILLEGAL_TEMPLATE_KEY");
+        let ship_type = items[0].parse::<u8>().expect("ERR_ILLEGAL_TEMPLATE_KEY");
+        let tier = self.internal_get_tier(ship_type);
+        if let Some(sub_type) = items.get(1) {
+            let sub_type = sub_type.parse::<u8>().expect("ERR_ILLEGAL_TEMPLATE_KEY");
+            require!(
+                sub_type >= 1 && sub_type <= tier.sub_type_count,
+                "ERR_ILLEGAL_SHIP_SUB_TYPE"
+            );
+        }
+    }
+
+    /// the subtype template of a ship, or its type template
+    pub(crate) fn internal_metadata_template(
+        &self,
+        ship: &ShipAttributes,
+    ) -> Option<ShipMetadataTemplate> {
+        self.metadata_templates
+            .get(&format!("{}:{}", ship.ship_type, ship.ship_sub_type))
+            .or_else(|| self.metadata_templates.get(&format!("{}", ship.ship_type)))
+    }
+}
@@ -1,20 +1,25 @@
 use crate::*;
+use near_contract_standards::non_fungible_token::approval::NonFungibleTokenApproval;
 use near_contract_standards::non_fungible_token::core::NonFungibleTokenCore;
 use near_contract_standards::non_fungible_token::core::NonFungibleTokenResolver;
 use near_contract_standards::non_fungible_token::enumeration::NonFungibleTokenEnumeration;
 use near_contract_standards::non_fungible_token::events::{NftBurn, NftMint, NftTransfer};
-use near_contract_standards::non_fungible_token::{Token, TokenId};
+use near_contract_standards::non_fungible_token::{
+    refund_approved_account_ids, refund_approved_account_ids_iter, refund_deposit, Token, TokenId,
+};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::{LookupMap, UnorderedSet};
 use near_sdk::json_types::U128;
 use near_sdk::{
-    assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, PromiseOrValue,
+    assert_one_yocto, env, ext_contract, require, AccountId, Balance, Gas, Promise, PromiseOrValue,
     PromiseResult,
 };
 use std::collections::HashMap;
+use std::mem::size_of;
 
 const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(20_000_000_000_000);
 const GAS_FOR_NFT_TRANSFER_CALL: Gas = Gas(35_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+const GAS_FOR_NFT_APPROVE: Gas = Gas(10_000_000_000_000);
 
 const NO_DEPOSIT: Balance = 0;
 
@@ -41,6 +46,31 @@ pub trait NonFungibleTokenReceiver {
     ) -> PromiseOrValue<bool>;
 }
 
+#[ext_contract(ext_approval_receiver)]
+pub trait NonFungibleTokenApprovalReceiver {
+    fn nft_on_approve(
+        &mut self,
+        token_id: TokenId,
+        owner_id: AccountId,
+        approval_id: u64,
+        msg: String,
+    );
+}
+
+/// Storage bytes taken by one approved account in `approvals_by_id`
+fn bytes_for_approved_account_id(account_id: &AccountId) -> u64 {
+    // The extra 4 bytes are coming from Borsh serialization to store the length of the string.
+    account_id.as_str().len() as u64 + 4 + size_of::<u64>() as u64
+}
+
+/// Attributes stored at mint, read through the prefix of `Contract::ship_attributes`
+/// as the token is not handed the contract, legacy ships decode their token id
+fn stored_ship_attributes(token_id: &TokenId) -> ShipAttributes {
+    LookupMap::<TokenId, ShipAttributes>::new(StorageKey::ShipAttributes)
+        .get(token_id)
+        .unwrap_or_else(|| parse_token_id(token_id))
+}
+
 /// Implementation of the non-fungible token standard.
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct MyNonFungibleToken {
@@ -55,6 +85,10 @@ pub struct MyNonFungibleToken {
     // required by enumeration extension
     pub tokens_per_owner: Option<LookupMap<AccountId, UnorderedSet<TokenId>>>,
     pub user_count: Balance,
+
+    // required by approval extension
+    pub approvals_by_id: LookupMap<TokenId, HashMap<AccountId, u64>>,
+    pub next_approval_id_by_id: LookupMap<TokenId, u64>,
 }
 
 impl MyNonFungibleToken {
@@ -66,6 +100,8 @@ impl MyNonFungibleToken {
             burned: 0,
             tokens_per_owner: Some(LookupMap::new(StorageKey::Enumeration)),
             user_count: 0,
+            approvals_by_id: LookupMap::new(StorageKey::Approval),
+            next_approval_id_by_id: LookupMap::new(StorageKey::NextApprovalId),
         }
     }
 
@@ -118,18 +154,42 @@ impl MyNonFungibleToken {
         sender_id: &AccountId,
         receiver_id: &AccountId,
         #[allow(clippy::ptr_arg)] token_id: &TokenId,
-        #[allow(unused_variables)] approval_id: Option<u64>,
+        approval_id: Option<u64>,
         memo: Option<String>,
-    ) -> AccountId {
+    ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
         let owner_id = self
             .owner_by_id
             .get(token_id)
             .unwrap_or_else(|| env::panic_str("Token not found"));
 
+        // clear approvals, this will be rolled back by a panic if sending fails
+        let approved_account_ids = self.approvals_by_id.remove(token_id);
+
         // check if authorized
-        if sender_id != &owner_id {
-            env::panic_str("Sender not approved");
-        }
+        let sender_id = if sender_id != &owner_id {
+            let app_acc_ids = approved_account_ids
+                .as_ref()
+                .unwrap_or_else(|| env::panic_str("Sender not approved"));
+
+            let actual_approval_id = app_acc_ids.get(sender_id);
+
+            // Panic if sender not approved at all
+            if actual_approval_id.is_none() {
+                env::panic_str("Sender not approved");
+            }
+
+            // If approval_id included, check that it matches
+            require!(
+                approval_id.is_none() || actual_approval_id == approval_id.as_ref(),
+                format!(
+                    "The actual approval_id {:?} is different from the given approval_id {:?}",
+                    actual_approval_id, approval_id
+                )
+            );
+            Some(sender_id)
+        } else {
+            None
+        };
 
         require!(
             &owner_id != receiver_id,
@@ -138,10 +198,10 @@ impl MyNonFungibleToken {
 
         self.internal_transfer_unguarded(token_id, &owner_id, receiver_id);
 
-        MyNonFungibleToken::emit_transfer(&owner_id, receiver_id, token_id, Some(sender_id), memo);
+        MyNonFungibleToken::emit_transfer(&owner_id, receiver_id, token_id, sender_id, memo);
 
-        // return previous owner
-        owner_id
+        // return previous owner & approvals
+        (owner_id, approved_account_ids)
     }
 
     fn emit_transfer(
@@ -155,10 +215,21 @@ impl MyNonFungibleToken {
             old_owner_id: owner_id,
             new_owner_id: receiver_id,
             token_ids: &[token_id],
-            authorized_id: sender_id.filter(|sender_id| *sender_id == owner_id),
+            authorized_id: sender_id,
             memo: memo.as_deref(),
         }
         .emit();
+
+        let ship = stored_ship_attributes(&token_id.to_string());
+        Event::ShipTransfer {
+            old_owner_id: owner_id,
+            new_owner_id: receiver_id,
+            token_id: &token_id.to_string(),
+            ship_type: ship.ship_type,
+            ship_sub_type: ship.ship_sub_type,
+            capacity: ship.capacity,
+        }
+        .emit();
     }
 
     /// Mint a new token without checking:
@@ -202,7 +273,7 @@ impl MyNonFungibleToken {
 
     /// Burn a token without checking:
     /// * Whether the caller id is equal to the `owner_id`
-    /// * emits the mint event
+    /// * emits the burn event
     pub fn internal_burn(&mut self, token_id: &TokenId) {
         // Core behavior: every token must have an owner
         let owner_id = self.owner_by_id.remove(&token_id).unwrap();
@@ -222,6 +293,12 @@ impl MyNonFungibleToken {
             }
         }
 
+        // clear approvals and refund their storage to the last owner
+        if let Some(approved_account_ids) = self.approvals_by_id.remove(token_id) {
+            refund_approved_account_ids(owner_id.clone(), &approved_account_ids);
+        }
+        self.next_approval_id_by_id.remove(token_id);
+
         self.burned += 1;
         self.supply -= 1;
 
@@ -234,6 +311,48 @@ impl MyNonFungibleToken {
         .emit();
     }
 
+    /// `nft_transfer_call` checked against `authorized_id`, while the receiver sees `sender_id`,
+    /// lets the renter of a ship stake it on behalf of the owner.
+    #[allow(clippy::too_many_arguments)]
+    pub fn internal_transfer_call(
+        &mut self,
+        sender_id: &AccountId,
+        authorized_id: &AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        require!(
+            env::prepaid_gas() > GAS_FOR_NFT_TRANSFER_CALL,
+            "More gas is required"
+        );
+        let (old_owner, old_approvals) =
+            self.internal_transfer(authorized_id, &receiver_id, &token_id, approval_id, memo);
+
+        // Initiating receiver's call and the callback
+        ext_receiver::nft_on_transfer(
+            sender_id.clone(),
+            old_owner.clone(),
+            token_id.clone(),
+            msg.clone(),
+            receiver_id.clone(),
+            NO_DEPOSIT,
+            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
+        )
+        .then(ext_self::nft_resolve_transfer(
+            old_owner,
+            receiver_id.clone(),
+            token_id.clone(),
+            old_approvals,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_RESOLVE_TRANSFER,
+        ))
+        .into()
+    }
+
     pub fn internal_burned_count(&self) -> Balance {
         self.burned
     }
@@ -253,7 +372,11 @@ impl NonFungibleTokenCore for MyNonFungibleToken {
     ) {
         assert_one_yocto();
         let sender_id = env::predecessor_account_id();
-        self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        let (old_owner, old_approvals) =
+            self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        if let Some(approved_account_ids) = old_approvals {
+            refund_approved_account_ids(old_owner, &approved_account_ids);
+        }
     }
 
     fn nft_transfer_call(
@@ -265,50 +388,32 @@ impl NonFungibleTokenCore for MyNonFungibleToken {
         msg: String,
     ) -> PromiseOrValue<bool> {
         assert_one_yocto();
-        require!(
-            env::prepaid_gas() > GAS_FOR_NFT_TRANSFER_CALL,
-            "More gas is required"
-        );
         let sender_id = env::predecessor_account_id();
-        let old_owner =
-            self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
-
-        // Initiating receiver's call and the callback
-        ext_receiver::nft_on_transfer(
-            sender_id.clone(),
-            old_owner.clone(),
-            token_id.clone(),
-            msg.clone(),
-            receiver_id.clone(),
-            NO_DEPOSIT,
-            env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL,
+        self.internal_transfer_call(
+            &sender_id,
+            &sender_id,
+            receiver_id,
+            token_id,
+            approval_id,
+            memo,
+            msg,
         )
-        .then(ext_self::nft_resolve_transfer(
-            old_owner,
-            receiver_id.clone(),
-            token_id.clone(),
-            None,
-            env::current_account_id(),
-            NO_DEPOSIT,
-            GAS_FOR_RESOLVE_TRANSFER,
-        ))
-        .into()
     }
 
     fn nft_token(&self, token_id: TokenId) -> Option<Token> {
         let owner_id = self.owner_by_id.get(&token_id)?;
+        let approved_account_ids = Some(self.approvals_by_id.get(&token_id).unwrap_or_default());
         Some(Token {
             token_id,
             owner_id,
             metadata: None,
-            approved_account_ids: None,
+            approved_account_ids,
         })
     }
 }
 
 impl NonFungibleTokenResolver for MyNonFungibleToken {
     /// Returns true if token was successfully transferred to `receiver_id`.
-    #[allow(unused_variables)]
     fn nft_resolve_transfer(
         &mut self,
         previous_owner_id: AccountId,
@@ -345,11 +450,25 @@ impl NonFungibleTokenResolver for MyNonFungibleToken {
             }
         } else {
             // The token was burned and doesn't exist anymore.
+            // Refund storage cost for storing approvals to original owner and return early.
+            if let Some(approved_account_ids) = approved_account_ids {
+                refund_approved_account_ids(previous_owner_id, &approved_account_ids);
+            }
             return true;
         };
 
         self.internal_transfer_unguarded(&token_id, &receiver_id, &previous_owner_id);
 
+        // 1. revert any approvals receiver already set, refunding storage costs
+        // 2. reset approvals to what previous owner had set before call to nft_transfer_call
+        if let Some(receiver_approvals) = self.approvals_by_id.remove(&token_id) {
+            refund_approved_account_ids(receiver_id.clone(), &receiver_approvals);
+        }
+        if let Some(previous_owner_approvals) = approved_account_ids {
+            self.approvals_by_id
+                .insert(&token_id, &previous_owner_approvals);
+        }
+
         MyNonFungibleToken::emit_transfer(&receiver_id, &previous_owner_id, &token_id, None, None);
         false
         //
@@ -360,11 +479,137 @@ impl MyNonFungibleToken {
     /// Helper function used by a enumerations methods
     /// Note: this method is not exposed publicly to end users
     fn enum_get_token(&self, owner_id: AccountId, token_id: TokenId) -> Token {
+        let approved_account_ids = Some(self.approvals_by_id.get(&token_id).unwrap_or_default());
         Token {
             token_id,
             owner_id,
             metadata: None,
-            approved_account_ids: None,
+            approved_account_ids,
+        }
+    }
+}
+
+impl NonFungibleTokenApproval for MyNonFungibleToken {
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        require!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let owner_id = self
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        require!(
+            env::predecessor_account_id() == owner_id,
+            "Predecessor must be token owner."
+        );
+
+        // update HashMap of approvals for this token
+        let approved_account_ids = &mut self.approvals_by_id.get(&token_id).unwrap_or_default();
+        let approval_id: u64 = self.next_approval_id_by_id.get(&token_id).unwrap_or(1u64);
+        let old_approval_id = approved_account_ids.insert(account_id.clone(), approval_id);
+        self.approvals_by_id.insert(&token_id, approved_account_ids);
+        self.next_approval_id_by_id
+            .insert(&token_id, &(approval_id + 1));
+
+        // If this approval replaced existing for same account, no storage was used.
+        // Otherwise, require that enough deposit was attached to pay for storage, and refund
+        // excess.
+        let storage_used = if old_approval_id.is_none() {
+            bytes_for_approved_account_id(&account_id)
+        } else {
+            0
+        };
+        refund_deposit(storage_used);
+
+        // if given `msg`, schedule call to `nft_on_approve` and return it. Else, return None.
+        msg.map(|msg| {
+            ext_approval_receiver::nft_on_approve(
+                token_id,
+                owner_id,
+                approval_id,
+                msg,
+                account_id,
+                NO_DEPOSIT,
+                env::prepaid_gas() - GAS_FOR_NFT_APPROVE,
+            )
+        })
+    }
+
+    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+        assert_one_yocto();
+        let owner_id = self
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        let predecessor_account_id = env::predecessor_account_id();
+        require!(
+            predecessor_account_id == owner_id,
+            "Predecessor must be token owner."
+        );
+
+        // if token has no approvals, do nothing
+        if let Some(approved_account_ids) = &mut self.approvals_by_id.get(&token_id) {
+            // if account_id was already not approved, do nothing
+            if approved_account_ids.remove(&account_id).is_some() {
+                refund_approved_account_ids_iter(
+                    predecessor_account_id,
+                    core::iter::once(&account_id),
+                );
+                // if this was the last approval, remove the whole HashMap to save space.
+                if approved_account_ids.is_empty() {
+                    self.approvals_by_id.remove(&token_id);
+                } else {
+                    self.approvals_by_id.insert(&token_id, approved_account_ids);
+                }
+            }
+        }
+    }
+
+    fn nft_revoke_all(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        let owner_id = self
+            .owner_by_id
+            .get(&token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        let predecessor_account_id = env::predecessor_account_id();
+        require!(
+            predecessor_account_id == owner_id,
+            "Predecessor must be token owner."
+        );
+
+        // refund owner for storage costs of all approvals and remove whole HashMap of approvals
+        if let Some(approved_account_ids) = self.approvals_by_id.remove(&token_id) {
+            refund_approved_account_ids(predecessor_account_id, &approved_account_ids);
+        }
+    }
+
+    fn nft_is_approved(
+        &self,
+        token_id: TokenId,
+        approved_account_id: AccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        require!(self.owner_by_id.get(&token_id).is_some(), "Token not found");
+
+        let approved_account_ids = if let Some(ids) = self.approvals_by_id.get(&token_id) {
+            ids
+        } else {
+            // token has no approvals
+            return false;
+        };
+
+        match (approved_account_ids.get(&approved_account_id), approval_id) {
+            (None, _) => false,
+            (Some(actual_approval_id), Some(given_approval_id)) => {
+                *actual_approval_id == given_approval_id
+            }
+            (Some(_), None) => true,
         }
     }
 }
@@ -436,17 +681,22 @@ impl NonFungibleTokenCore for Contract {
         approval_id: Option<u64>,
         memo: Option<String>,
     ) {
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("ERR: Token not found");
+        self.internal_assert_not_locked(&token_id);
         require!(
-            self.tokens.owner_by_id.get(&token_id) == Some(env::predecessor_account_id()),
-            "ERR: Token not found"
-        );
-        self.update_owner_supply(
-            &token_id,
-            Some(env::predecessor_account_id()),
-            Some(receiver_id.clone()),
+            self.internal_check_rental(&token_id, &receiver_id)
+                .is_none(),
+            "ERR_SHIP_RENTED"
         );
+        let initial_usage = env::storage_usage();
+        self.update_owner_supply(&token_id, Some(owner_id), Some(receiver_id.clone()));
         self.tokens
-            .nft_transfer(receiver_id, token_id, approval_id, memo)
+            .nft_transfer(receiver_id.clone(), token_id.clone(), approval_id, memo);
+        self.internal_move_nft_storage(&token_id, &receiver_id, initial_usage, true);
     }
 
     #[payable]
@@ -458,17 +708,39 @@ impl NonFungibleTokenCore for Contract {
         memo: Option<String>,
         msg: String,
     ) -> PromiseOrValue<bool> {
-        require!(
-            self.tokens.owner_by_id.get(&token_id) == Some(env::predecessor_account_id()),
-            "ERR: Token not found"
-        );
-        self.update_owner_supply(
-            &token_id,
-            Some(env::predecessor_account_id()),
-            Some(receiver_id.clone()),
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("ERR: Token not found");
+        self.internal_assert_not_locked(&token_id);
+        let rented_from = self.internal_check_rental(&token_id, &receiver_id);
+        let initial_usage = env::storage_usage();
+        self.update_owner_supply(&token_id, Some(owner_id), Some(receiver_id.clone()));
+        if let Some(owner_id) = rented_from {
+            // the user stakes a rented ship on behalf of its owner
+            assert_one_yocto();
+            let ret = self.tokens.internal_transfer_call(
+                &env::predecessor_account_id(),
+                &owner_id,
+                receiver_id.clone(),
+                token_id.clone(),
+                approval_id,
+                memo,
+                msg,
+            );
+            self.internal_move_nft_storage(&token_id, &receiver_id, initial_usage, true);
+            return ret;
+        }
+        let ret = self.tokens.nft_transfer_call(
+            receiver_id.clone(),
+            token_id.clone(),
+            approval_id,
+            memo,
+            msg,
         );
-        self.tokens
-            .nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
+        self.internal_move_nft_storage(&token_id, &receiver_id, initial_usage, true);
+        ret
     }
 
     fn nft_token(&self, token_id: TokenId) -> Option<Token> {
@@ -477,12 +749,45 @@ impl NonFungibleTokenCore for Contract {
                 token_id: token.token_id.clone(),
                 owner_id: token.owner_id.clone(),
                 metadata: Some(self.gen_metadata(&token.token_id)),
-                approved_account_ids: None,
+                approved_account_ids: token.approved_account_ids,
             })
         })
     }
 }
 
+#[near_bindgen]
+impl NonFungibleTokenApproval for Contract {
+    #[payable]
+    fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        self.tokens.nft_approve(token_id, account_id, msg)
+    }
+
+    #[payable]
+    fn nft_revoke(&mut self, token_id: TokenId, account_id: AccountId) {
+        self.tokens.nft_revoke(token_id, account_id)
+    }
+
+    #[payable]
+    fn nft_revoke_all(&mut self, token_id: TokenId) {
+        self.tokens.nft_revoke_all(token_id)
+    }
+
+    fn nft_is_approved(
+        &self,
+        token_id: TokenId,
+        approved_account_id: AccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        self.tokens
+            .nft_is_approved(token_id, approved_account_id, approval_id)
+    }
+}
+
 #[near_bindgen]
 impl NonFungibleTokenResolver for Contract {
     #[private]
@@ -493,6 +798,7 @@ impl NonFungibleTokenResolver for Contract {
         token_id: TokenId,
         approved_account_ids: Option<std::collections::HashMap<AccountId, u64>>,
     ) -> bool {
+        let initial_usage = env::storage_usage();
         let ret = self.tokens.nft_resolve_transfer(
             previous_owner_id.clone(),
             receiver_id.clone(),
@@ -505,6 +811,8 @@ impl NonFungibleTokenResolver for Contract {
                 Some(receiver_id.clone()),
                 Some(previous_owner_id.clone()),
             );
+            // the ship is back, its storage must not fail the revert
+            self.internal_move_nft_storage(&token_id, &previous_owner_id, initial_usage, false);
         }
         ret
     }
@@ -541,7 +849,7 @@ impl NonFungibleTokenEnumeration for Contract {
                 token_id: token.token_id.clone(),
                 owner_id: token.owner_id.clone(),
                 metadata: Some(self.gen_metadata(&token.token_id)),
-                approved_account_ids: None,
+                approved_account_ids: token.approved_account_ids,
             })
             .collect()
     }
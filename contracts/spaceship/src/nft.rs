@@ -10,14 +10,17 @@ pub const TYPE_B: u8 = 3;
 pub const TYPE_C: u8 = 2;
 pub const TYPE_D: u8 = 1;
 
+// default subtype counts, the tier registry holds the live values
 pub const TYPE_A_MAX: u8 = 4;
 pub const TYPE_B_MAX: u8 = 8;
 pub const TYPE_C_MAX: u8 = 16;
 pub const TYPE_D_MAX: u8 = 32;
 
-pub fn parse_token_id(token_id: &TokenId) -> ShipElements {
+/// Decode attributes from a `prefix:type:subtype:capacity` token id,
+/// only used for ships minted before attributes were stored on-chain.
+pub fn parse_token_id(token_id: &TokenId) -> ShipAttributes {
     let items: Vec<&str> = token_id.split(":").collect();
-    ShipElements {
+    ShipAttributes {
         prefix_id: items
             .get(0)
             .expect("ILLIGAL_TOKEN_ID")
@@ -41,7 +44,7 @@ pub fn parse_token_id(token_id: &TokenId) -> ShipElements {
     }
 }
 
-pub fn gen_token_id(element: &ShipElements) -> TokenId {
+pub fn gen_token_id(element: &ShipAttributes) -> TokenId {
     format!(
         "{}:{}:{}:{}",
         element.prefix_id, element.ship_type, element.ship_sub_type, element.capacity
@@ -61,13 +64,13 @@ impl Contract {
     }
 
     /// [SSP-00-20]
-    /// Return avtive ship's ShipElements in list for given owner
+    /// Return avtive ship's ShipAttributes in list for given owner
     pub fn get_spaceship_list_for_owner(
         &self,
         account_id: AccountId,
         from_index: Option<u64>,
         limit: Option<u64>,
-    ) -> Vec<ShipElements> {
+    ) -> Vec<ShipAttributes> {
         self.tokens
             .nft_tokens_for_owner(
                 account_id,
@@ -75,10 +78,19 @@ impl Contract {
                 limit,
             )
             .into_iter()
-            .map(|token| parse_token_id(&token.token_id))
+            .map(|token| self.internal_ship_attributes(&token.token_id))
             .collect()
     }
 
+    /// [SSP-00-24]
+    /// Return the stored attributes of an active ship
+    pub fn get_ship_attributes(&self, token_id: TokenId) -> Option<ShipAttributes> {
+        self.tokens
+            .owner_by_id
+            .get(&token_id)
+            .map(|_| self.internal_ship_attributes(&token_id))
+    }
+
     // SSP-00-07 total burned token count by type_detail
     pub fn total_burnt_subtype_of(&self, ship_type: u8, ship_sub_type: u8) -> u32 {
         let ship_type_detail: String = format!("{}:{}", ship_type, ship_sub_type);
@@ -159,6 +171,8 @@ impl Contract {
 #[near_bindgen]
 impl Contract {
     // [SSP-00-01]
+    /// the attached deposit goes to the nft storage of `owner_id`
+    #[payable]
     pub fn batch_mint(
         &mut self,
         owner_id: AccountId,
@@ -169,6 +183,9 @@ impl Contract {
             env::predecessor_account_id() == self.box_id,
             "ERR_NOT_ALLOWED"
         );
+        if env::attached_deposit() > 0 {
+            self.internal_deposit_nft_storage(&owner_id, env::attached_deposit());
+        }
 
         let mut token_ids: Vec<TokenId> = vec![];
 
@@ -176,48 +193,86 @@ impl Contract {
             let (ship_type, ship_sub_type) = tup;
             let ship_type = ship_type.parse::<u8>().unwrap();
             let ship_sub_type = ship_sub_type.parse::<u8>().unwrap();
+            require!(ship_type != TYPE_S, "ERR_ILLEGAL_SHIP_TYPE");
+            let tier = self.internal_get_tier(ship_type);
             require!(
-                ship_type >= TYPE_D && ship_type <= TYPE_A,
-                "ERR_ILLEGAL_SHIP_TYPE"
+                ship_sub_type >= 1 && ship_sub_type <= tier.sub_type_count,
+                "ERR_ILLEGAL_SHIP_SUB_TYPE"
             );
             let capacity: u32 = self.internal_random_spaceship_capacity(&ship_type);
-            let token_id = gen_token_id(&ShipElements {
+            let ship = ShipAttributes {
                 prefix_id: self.next_id,
                 ship_type,
                 ship_sub_type,
                 capacity,
-            });
+            };
+            let token_id = gen_token_id(&ship);
             require!(
                 self.tokens.owner_by_id.get(&token_id).is_none(),
                 "ERR_TOKEN_ID_CONFLICT"
             );
             self.next_id += 1;
 
-            self.mint_ship_with_supply_updated(owner_id.clone(), &token_id);
+            let initial_usage = env::storage_usage();
+            self.mint_ship_with_supply_updated(owner_id.clone(), &token_id, &ship);
+            self.internal_charge_nft_storage(&owner_id, &token_id, initial_usage);
 
-            token_ids.push(token_id);
+            Event::CapacityRoll {
+                owner_id: &owner_id,
+                token_id: &token_id,
+                ship_type,
+                ship_sub_type,
+                capacity,
+            }
+            .emit();
 
-            // todo: emit EVENT
+            token_ids.push(token_id);
         }
 
         if token_ids.len() > 0 {
-            // register ship, no need to attach callback
-            ext_shippool::batch_register_ships(
-                token_ids.clone(),
-                owner_id.clone(),
-                self.shippool_id.clone(),
-                0,
-                GAS_FOR_REGISTER_SHIP,
-            );
+            self.internal_register_ships(token_ids, owner_id);
+        }
+    }
+
+    /// [SSP-00-25] re-send the queued shippool registrations of `owner_id`
+    pub fn retry_register_ships(&mut self, owner_id: AccountId) {
+        let token_ids = self
+            .pending_registrations
+            .remove(&owner_id)
+            .expect("ERR_NO_PENDING_REGISTRATION");
+        self.internal_register_ships(token_ids, owner_id);
+    }
+
+    /// queue the ships for a retry if shippool failed to register them
+    #[private]
+    pub fn resolve_register_ships(&mut self, token_ids: Vec<TokenId>, owner_id: AccountId) -> bool {
+        match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => true,
+            PromiseResult::Failed => {
+                let mut pending = self
+                    .pending_registrations
+                    .get(&owner_id)
+                    .unwrap_or_default();
+                pending.extend(token_ids.iter().cloned());
+                self.pending_registrations.insert(&owner_id, &pending);
+
+                Event::RegistrationQueued {
+                    owner_id: &owner_id,
+                    token_ids: &token_ids,
+                }
+                .emit();
+                false
+            }
         }
     }
 
     /// [SSP-00-15] only for distribute S-class spaceship by auction contract
-    pub fn nft_payout(&mut self, receiver_id: AccountId, token_id: TokenId) {
+    pub fn auction_distribute(&mut self, receiver_id: AccountId, token_id: TokenId) {
         let predecessor_id = env::predecessor_account_id();
         require!(predecessor_id == self.auction_id, "Invalid contract id");
         require!(
-            parse_token_id(&token_id).ship_type == TYPE_S,
+            self.internal_ship_attributes(&token_id).ship_type == TYPE_S,
             "ERR_ILLIGAL_SHIP_TYPE"
         );
 
@@ -233,6 +288,16 @@ impl Contract {
             Some(env::current_account_id()),
             Some(receiver_id.clone()),
         );
+
+        let ship = self.internal_ship_attributes(&token_id);
+        Event::ShipPayout {
+            receiver_id: &receiver_id,
+            token_id: &token_id,
+            ship_type: ship.ship_type,
+            ship_sub_type: ship.ship_sub_type,
+            capacity: ship.capacity,
+        }
+        .emit();
     }
 
     // [SSP-00-17] user initiative to burn;
@@ -251,7 +316,43 @@ impl Contract {
         );
     }
 
+    /// [SSP-00-26] burn several ships of the caller at once, ENG rewards are minted in one go.
+    /// The whole batch fails if any ship is S-class or not owned by the caller.
+    #[payable]
+    pub fn user_batch_burn(&mut self, token_ids: Vec<TokenId>) -> U128 {
+        assert_one_yocto();
+        require!(
+            !token_ids.is_empty() && token_ids.len() <= self.max_burn_batch as usize,
+            "ERR_INVALID_BATCH_SIZE"
+        );
+        let predecessor_id = env::predecessor_account_id();
+
+        let mut amount: u128 = 0;
+        for token_id in token_ids.iter() {
+            require!(
+                Some(predecessor_id.clone()) == self.tokens.owner_by_id.get(token_id),
+                "ERR_NOT_NFT_OWNER"
+            );
+            let ship_type = self.internal_ship_attributes(token_id).ship_type;
+            amount += self.internal_get_tier(ship_type).eng_reward.0;
+            self.burn_ship_with_supply_updated(&predecessor_id, token_id, None);
+        }
+        if amount > 0 {
+            self.internal_mint_eng(predecessor_id.clone(), U128(amount), EngSource::BurnReward);
+        }
+
+        Event::BatchBurn {
+            owner_id: &predecessor_id,
+            token_ids: &token_ids,
+            amount: U128(amount),
+        }
+        .emit();
+        U128(amount)
+    }
+
     // [SSP-00-14]
+    /// returns false if nothing was upgraded, so shipmarket refunds the TIA fee.
+    /// NEAR attached beyond 1 yocto goes to the owner's NFT storage deposit.
     #[payable]
     pub fn upgrade_spaceship(
         &mut self,
@@ -260,89 +361,452 @@ impl Contract {
         token_id_2: TokenId,
         target_sub_type: u8,
         eng_amount: U128,
-    ) {
-        assert_one_yocto();
+    ) -> PromiseOrValue<bool> {
+        let predecessor_id = env::predecessor_account_id();
+        require!(predecessor_id == self.shipmarket_id, "ERR_NOT_SHIPMARKET");
+        self.internal_upgrade_target(&token_id_1, &token_id_2, target_sub_type);
+        self.internal_assert_materials(&owner_id, &[token_id_1.clone(), token_id_2.clone()]);
+        self.internal_deposit_attached_storage(&owner_id);
+
+        match self.eng_token_id.clone() {
+            // the ENG fee lives in the standalone token, upgrade once it is burned
+            Some(eng_token_id) if eng_amount.0 > 0 => ext_eng::burn_from(
+                owner_id.clone(),
+                eng_amount,
+                eng_token_id,
+                1,
+                GAS_FOR_ENG_BURN,
+            )
+            .then(ext_self::resolve_upgrade_spaceship(
+                predecessor_id,
+                owner_id,
+                token_id_1,
+                token_id_2,
+                target_sub_type,
+                eng_amount,
+                env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_UPGRADE,
+            ))
+            .into(),
+            _ => {
+                // the local ledger, or nothing to burn
+                if eng_amount.0 > 0 {
+                    self.internal_burn_eng(&owner_id, eng_amount);
+                }
+                self.internal_upgrade_spaceship(
+                    &predecessor_id,
+                    owner_id,
+                    token_id_1,
+                    token_id_2,
+                    target_sub_type,
+                );
+                PromiseOrValue::Value(true)
+            }
+        }
+    }
+
+    /// upgrade after the ENG fee is burned in the standalone token,
+    /// the materials may have moved meanwhile, the fee is then minted back
+    #[private]
+    pub fn resolve_upgrade_spaceship(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_id_1: TokenId,
+        token_id_2: TokenId,
+        target_sub_type: u8,
+        eng_amount: U128,
+    ) -> bool {
+        if !self.internal_resolve_material_burn(
+            &owner_id,
+            &[token_id_1.clone(), token_id_2.clone()],
+            eng_amount,
+        ) {
+            return false;
+        }
+        self.internal_upgrade_spaceship(
+            &sender_id,
+            owner_id,
+            token_id_1,
+            token_id_2,
+            target_sub_type,
+        );
+        true
+    }
+
+    /// [SSP-00-31] fuse the materials of an upgrade recipe into one ship,
+    /// shipmarket checks the recipe and rolls the target subtype.
+    /// returns false if nothing was fused, so shipmarket refunds the TIA cost.
+    /// NEAR attached beyond 1 yocto goes to the owner's NFT storage deposit.
+    #[payable]
+    pub fn fuse_spaceship(
+        &mut self,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+        eng_amount: U128,
+    ) -> PromiseOrValue<bool> {
         let predecessor_id = env::predecessor_account_id();
         require!(predecessor_id == self.shipmarket_id, "ERR_NOT_SHIPMARKET");
+        require!(
+            capacity_bonus <= MAX_CAPACITY_BONUS,
+            "ERR_ILLEGAL_CAPACITY_BONUS"
+        );
+        self.internal_fuse_check(&owner_id, &token_ids, target_type, target_sub_type);
+        self.internal_deposit_attached_storage(&owner_id);
+
+        match self.eng_token_id.clone() {
+            Some(eng_token_id) if eng_amount.0 > 0 => ext_eng::burn_from(
+                owner_id.clone(),
+                eng_amount,
+                eng_token_id,
+                1,
+                GAS_FOR_ENG_BURN,
+            )
+            .then(ext_self::resolve_fuse_spaceship(
+                predecessor_id,
+                owner_id,
+                token_ids,
+                target_type,
+                target_sub_type,
+                capacity_bonus,
+                eng_amount,
+                env::current_account_id(),
+                0,
+                GAS_FOR_RESOLVE_UPGRADE,
+            ))
+            .into(),
+            _ => {
+                if eng_amount.0 > 0 {
+                    self.internal_burn_eng(&owner_id, eng_amount);
+                }
+                self.internal_fuse_spaceship(
+                    &predecessor_id,
+                    owner_id,
+                    token_ids,
+                    target_type,
+                    target_sub_type,
+                    capacity_bonus,
+                );
+                PromiseOrValue::Value(true)
+            }
+        }
+    }
+
+    /// fuse after the ENG cost is burned in the standalone token
+    #[private]
+    #[allow(clippy::too_many_arguments)]
+    pub fn resolve_fuse_spaceship(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+        eng_amount: U128,
+    ) -> bool {
+        if !self.internal_resolve_material_burn(&owner_id, &token_ids, eng_amount) {
+            return false;
+        }
+        self.internal_fuse_spaceship(
+            &sender_id,
+            owner_id,
+            token_ids,
+            target_type,
+            target_sub_type,
+            capacity_bonus,
+        );
+        true
+    }
+
+    // only time lock to set, returns the id of the queued action
+    // SSP-00-11
+    #[payable]
+    pub fn set_spaceship_type_capacity(&mut self, ship_type: u8, min: u32, max: u32) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(max >= min, "SpaceShip: invalid min and max");
+        self.internal_get_tier(ship_type);
+
+        self.internal_queue_action(TimelockAction::SetSpaceshipTypeCapacity {
+            ship_type,
+            min,
+            max,
+        })
+    }
+}
 
-        let ship1_type = parse_token_id(&token_id_1).ship_type;
-        let ship2_type = parse_token_id(&token_id_2).ship_type;
+#[near_bindgen]
+impl NonFungibleTokenMetadataProvider for Contract {
+    fn nft_metadata(&self) -> NFTContractMetadata {
+        self.metadata.get().unwrap()
+    }
+}
+
+impl Contract {
+    /// ship_type two materials upgrade into, checks the target subtype as well
+    pub(crate) fn internal_upgrade_target(
+        &self,
+        token_id_1: &TokenId,
+        token_id_2: &TokenId,
+        target_sub_type: u8,
+    ) -> u8 {
+        let ship1_type = self.internal_ship_attributes(token_id_1).ship_type;
+        let ship2_type = self.internal_ship_attributes(token_id_2).ship_type;
 
         require!(
             ship1_type == ship2_type,
             "ShipFactory: material is must same type"
         );
+        let target_type = self
+            .internal_get_tier(ship1_type)
+            .upgrade_target
+            .expect("ShipFactory: material is not allow type");
         require!(
-            ship1_type == TYPE_B || ship1_type == TYPE_C || ship1_type == TYPE_D,
-            "ShipFactory: material is not allow type"
+            target_sub_type >= 1
+                && target_sub_type <= self.internal_get_tier(target_type).sub_type_count,
+            "ERR_ILLEGAL_SHIP_SUB_TYPE"
         );
+        target_type
+    }
+
+    pub(crate) fn internal_upgrade_spaceship(
+        &mut self,
+        sender_id: &AccountId,
+        owner_id: AccountId,
+        token_id_1: TokenId,
+        token_id_2: TokenId,
+        target_sub_type: u8,
+    ) {
+        let target_type = self.internal_upgrade_target(&token_id_1, &token_id_2, target_sub_type);
+
+        // materials give their storage back to their payers, the new ship is charged on its own
+        self.burn_ship_with_supply_updated(&owner_id, &token_id_1, None);
+        self.burn_ship_with_supply_updated(&owner_id, &token_id_2, None);
+        let initial_usage = env::storage_usage();
+        let (token_id, ship) =
+            self.internal_mint_upgraded_ship(&owner_id, target_type, target_sub_type, 0);
+        self.internal_charge_nft_storage(&owner_id, &token_id, initial_usage);
 
-        let token_id = gen_token_id(&ShipElements {
+        Event::UpgradeEvent {
+            sender_id,
+            token_id_1: &token_id_1,
+            token_id_2: &token_id_2,
+            token_id: &token_id,
+            ship_type: ship.ship_type,
+            ship_sub_type: ship.ship_sub_type,
+            capacity: ship.capacity,
+        }
+        .emit();
+    }
+
+    /// why `token_id` can not be burned as a material of `owner_id`
+    pub(crate) fn internal_material_error(
+        &self,
+        owner_id: &AccountId,
+        token_id: &TokenId,
+    ) -> Option<&'static str> {
+        if self.tokens.owner_by_id.get(token_id).as_ref() != Some(owner_id) {
+            Some("ERR_NOT_NFT_OWNER")
+        } else if self.internal_ship_attributes(token_id).ship_type == TYPE_S {
+            Some("SpaceShip: not support type")
+        } else if self.internal_active_rental(token_id).is_some() {
+            Some("ERR_SHIP_RENTED")
+        } else if self.ship_locks.get(token_id).is_some() {
+            Some("ERR_SHIP_LOCKED")
+        } else {
+            None
+        }
+    }
+
+    pub(crate) fn internal_assert_materials(&self, owner_id: &AccountId, token_ids: &[TokenId]) {
+        for (index, token_id) in token_ids.iter().enumerate() {
+            if let Some(err) = self.internal_material_error(owner_id, token_id) {
+                env::panic_str(err);
+            }
+            require!(
+                !token_ids[..index].contains(token_id),
+                "ERR_DUPLICATE_MATERIAL"
+            );
+        }
+    }
+
+    /// settle the ENG burned for an upgrade or fusion, true if the materials can be burned now.
+    /// Materials moved while the burn was pending get the ENG minted back.
+    fn internal_resolve_material_burn(
+        &mut self,
+        owner_id: &AccountId,
+        token_ids: &[TokenId],
+        eng_amount: U128,
+    ) -> bool {
+        let burned = match env::promise_result(0) {
+            PromiseResult::NotReady => env::abort(),
+            PromiseResult::Successful(_) => true,
+            PromiseResult::Failed => false,
+        };
+        if !burned {
+            log!("ERR_ENG_BURN_FAILED");
+            return false;
+        }
+        self.eng_emission.record_burn(eng_amount.0);
+        if token_ids
+            .iter()
+            .any(|token_id| self.internal_material_error(owner_id, token_id).is_some())
+        {
+            log!("ERR_MATERIAL_MOVED");
+            self.internal_mint_eng(owner_id.clone(), eng_amount, EngSource::UpgradeRefund);
+            return false;
+        }
+        true
+    }
+
+    /// materials must be distinct ships of `owner_id`, the target a known subtype
+    pub(crate) fn internal_fuse_check(
+        &self,
+        owner_id: &AccountId,
+        token_ids: &[TokenId],
+        target_type: u8,
+        target_sub_type: u8,
+    ) {
+        require!(!token_ids.is_empty(), "ERR_NO_MATERIAL");
+        self.internal_assert_materials(owner_id, token_ids);
+        let material_type = self.internal_ship_attributes(&token_ids[0]).ship_type;
+        require!(
+            token_ids
+                .iter()
+                .all(|token_id| self.internal_ship_attributes(token_id).ship_type == material_type),
+            "ERR_ILLEGAL_MATERIAL_TYPE"
+        );
+        require!(
+            target_type != TYPE_S && self.internal_is_upgrade_of(material_type, target_type),
+            "ERR_ILLEGAL_TARGET_TYPE"
+        );
+        require!(
+            target_sub_type >= 1
+                && target_sub_type <= self.internal_get_tier(target_type).sub_type_count,
+            "ERR_ILLEGAL_SHIP_SUB_TYPE"
+        );
+    }
+
+    pub(crate) fn internal_fuse_spaceship(
+        &mut self,
+        sender_id: &AccountId,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+    ) {
+        self.internal_fuse_check(&owner_id, &token_ids, target_type, target_sub_type);
+        for material in token_ids.iter() {
+            self.burn_ship_with_supply_updated(&owner_id, material, None);
+        }
+        let initial_usage = env::storage_usage();
+        let (token_id, ship) = self.internal_mint_upgraded_ship(
+            &owner_id,
+            target_type,
+            target_sub_type,
+            capacity_bonus,
+        );
+        self.internal_charge_nft_storage(&owner_id, &token_id, initial_usage);
+
+        Event::FuseEvent {
+            sender_id,
+            token_ids: &token_ids,
+            token_id: &token_id,
+            ship_type: ship.ship_type,
+            ship_sub_type: ship.ship_sub_type,
+            capacity: ship.capacity,
+        }
+        .emit();
+    }
+
+    /// mint the result of an upgrade, `capacity_bonus` percent is added to the rolled capacity
+    fn internal_mint_upgraded_ship(
+        &mut self,
+        owner_id: &AccountId,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+    ) -> (TokenId, ShipAttributes) {
+        let capacity = self.internal_random_spaceship_capacity(&target_type);
+        let ship = ShipAttributes {
             prefix_id: self.next_id,
-            ship_type: ship1_type + 1,
+            ship_type: target_type,
             ship_sub_type: target_sub_type,
-            capacity: self.internal_random_spaceship_capacity(&(ship1_type + 1)),
-        });
+            capacity: apply_capacity_bonus(capacity, capacity_bonus),
+        };
+        let token_id = gen_token_id(&ship);
         require!(
             self.tokens.owner_by_id.get(&token_id).is_none(),
             "ERR_TOKEN_ID_CONFLICT"
         );
         self.next_id += 1;
 
-        self.mint_ship_with_supply_updated(owner_id.clone(), &token_id);
-
-        ext_shippool::register_ship(
-            token_id.clone(),
-            owner_id.clone(),
-            self.shippool_id.clone(),
-            0,
-            GAS_FOR_REGISTER_SHIP,
-        );
+        self.mint_ship_with_supply_updated(owner_id.clone(), &token_id, &ship);
 
-        self.burn_eng_for_user(owner_id.clone(), eng_amount);
-        self.burn_ship_with_supply_updated(&owner_id, &token_id_1, None);
-        self.burn_ship_with_supply_updated(&owner_id, &token_id_2, None);
-
-        Event::UpgradeEvent {
-            sender_id: &predecessor_id,
-            token_id_1: &token_id_1,
-            token_id_2: &token_id_2,
+        Event::CapacityRoll {
+            owner_id,
             token_id: &token_id,
+            ship_type: ship.ship_type,
+            ship_sub_type: ship.ship_sub_type,
+            capacity: ship.capacity,
         }
         .emit();
+
+        // queued for retry_register_ships if shippool refuses it, as for batch_mint
+        self.internal_register_ships(vec![token_id.clone()], owner_id.clone());
+        (token_id, ship)
     }
-}
 
-#[near_bindgen]
-impl NonFungibleTokenMetadataProvider for Contract {
-    fn nft_metadata(&self) -> NFTContractMetadata {
-        self.metadata.get().unwrap()
+    /// attributes stored at mint, fall back to decode token id for legacy ships
+    pub(crate) fn internal_ship_attributes(&self, token_id: &TokenId) -> ShipAttributes {
+        self.ship_attributes
+            .get(token_id)
+            .unwrap_or_else(|| parse_token_id(token_id))
     }
-}
 
-impl Contract {
     pub(crate) fn gen_metadata(&self, token_id: &String) -> TokenMetadata {
-        let ship_element = parse_token_id(token_id);
+        let ship_element = self.internal_ship_attributes(token_id);
         let ship_sub_type = format!("{}:{}", ship_element.ship_type, ship_element.ship_sub_type);
+        let template =
+            self.internal_metadata_template(&ship_element)
+                .unwrap_or(ShipMetadataTemplate {
+                    title: None,
+                    description: None,
+                    media: None,
+                    media_hash: None,
+                    reference: None,
+                    reference_hash: None,
+                });
         TokenMetadata {
-            title: Some("Spaceship".to_string()),
-            description: Some(token_id.clone()),
-            media: self.icons.get(&ship_sub_type).and_then(|x| Some(x)),
-            //Some(Base64VecU8::from("a".repeat(64).as_bytes().to_vec()))
-            media_hash: None,
+            title: template.title.or(Some("Spaceship".to_string())),
+            description: template.description.or(Some(token_id.clone())),
+            media: template.media.or_else(|| self.icons.get(&ship_sub_type)),
+            media_hash: template.media_hash,
             copies: Some(1),
             issued_at: None,
             expires_at: None,
             starts_at: None,
             updated_at: None,
-            extra: None,
-            reference: None,
-            reference_hash: None,
+            extra: Some(
+                near_sdk::serde_json::to_string(&ShipExtra {
+                    attributes: &ship_element,
+                    progress: &self.internal_ship_progress(token_id),
+                })
+                .unwrap(),
+            ),
+            reference: template.reference,
+            reference_hash: template.reference_hash,
         }
     }
 
     pub(crate) fn upate_total_supply(&mut self, token_id: &String, delta: i8) {
-        let ship_element = parse_token_id(&token_id);
+        let ship_element = self.internal_ship_attributes(token_id);
         let ship_type = format!("{}", ship_element.ship_type);
         let ship_type_detail = format!("{}:{}", ship_element.ship_type, ship_element.ship_sub_type);
 
@@ -372,9 +836,10 @@ impl Contract {
         from: Option<AccountId>,
         to: Option<AccountId>,
     ) {
-        let ship_element = parse_token_id(&token_id);
+        let ship_element = self.internal_ship_attributes(token_id);
         let ship_type = format!("{}", ship_element.ship_type);
         let ship_type_detail = format!("{}:{}", ship_element.ship_type, ship_element.ship_sub_type);
+        self.internal_index_ship(token_id, &ship_element, from.as_ref(), to.as_ref());
 
         // transfer or burn
         if let Some(owner_id) = from {
@@ -406,6 +871,10 @@ impl Contract {
             owner_supplies.insert(&ship_type_detail, &new_supply);
 
             self.supply_per_owner.insert(&owner_id, &owner_supplies);
+            // the last ship of this subtype left
+            if new_supply.active == 0 {
+                self.internal_update_collection(&owner_id, ship_element.ship_type, &owner_supplies);
+            }
         }
 
         // mint or transfer
@@ -433,74 +902,66 @@ impl Contract {
             owner_supplies.insert(&ship_type_detail, &new_supply);
 
             self.supply_per_owner.insert(&owner_id, &owner_supplies);
+            // the first ship of this subtype arrived
+            if new_supply.active == 1 {
+                self.internal_update_collection(&owner_id, ship_element.ship_type, &owner_supplies);
+            }
         }
     }
 
-    pub fn mint_ship_with_supply_updated(&mut self, owner_id: AccountId, token_id: &String) {
+    pub fn mint_ship_with_supply_updated(
+        &mut self,
+        owner_id: AccountId,
+        token_id: &TokenId,
+        ship: &ShipAttributes,
+    ) {
+        self.ship_attributes.insert(token_id, ship);
         self.tokens
             .internal_mint(token_id.clone(), owner_id.clone());
+        Event::ShipMint {
+            owner_id: &owner_id,
+            token_id,
+            ship_type: ship.ship_type,
+            ship_sub_type: ship.ship_sub_type,
+            capacity: ship.capacity,
+        }
+        .emit();
         self.upate_total_supply(token_id, 1);
         self.update_owner_supply(token_id, None, Some(owner_id.clone()));
     }
 
-    // SSP-00-10
-    pub fn internal_random_spaceship_capacity(&self, ship_type: &u8) -> u32 {
-        let mut type_capacity: CapacityRange = CapacityRange { min: 0, max: 0 };
+    pub fn internal_register_ships(&mut self, token_ids: Vec<TokenId>, owner_id: AccountId) {
+        ext_shippool::batch_register_ships(
+            token_ids.clone(),
+            owner_id.clone(),
+            self.shippool_id.clone(),
+            0,
+            GAS_FOR_REGISTER_SHIP,
+        )
+        .then(ext_self::resolve_register_ships(
+            token_ids,
+            owner_id,
+            env::current_account_id(),
+            0,
+            GAS_FOR_RESOLVE_REGISTER,
+        ));
+    }
 
-        if *ship_type == TYPE_A {
-            type_capacity = self.capacity.capacity_a.clone();
-        }
-        if *ship_type == TYPE_B {
-            type_capacity = self.capacity.capacity_b.clone();
-        }
-        if *ship_type == TYPE_C {
-            type_capacity = self.capacity.capacity_c.clone();
-        }
-        if *ship_type == TYPE_D {
-            type_capacity = self.capacity.capacity_d.clone();
-        }
-        if *ship_type == TYPE_S {
-            type_capacity = self.capacity.capacity_s.clone();
-        }
+    // SSP-00-10
+    pub fn internal_random_spaceship_capacity(&mut self, ship_type: &u8) -> u32 {
+        let type_capacity = self.internal_get_tier(*ship_type).capacity;
 
         let mut min: u32 = type_capacity.min;
         let max: u32 = type_capacity.max;
         let base: u32 = max - min;
 
         if base > 0 {
-            let rnd = self.internal_random();
+            let rnd = self.internal_random(DOMAIN_CAPACITY);
             min += (rnd % base as u64) as u32;
         }
         min
     }
 
-    // only time lock to set
-    // SSP-00-11
-    pub fn set_spaceship_type_capacity(&mut self, ship_type: u8, min: u32, max: u32) {
-        require!(max >= min, "SpaceShip: invalid min and max");
-
-        if ship_type == TYPE_A {
-            self.capacity.capacity_a.min = min;
-            self.capacity.capacity_a.max = max;
-        }
-        if ship_type == TYPE_B {
-            self.capacity.capacity_b.min = min;
-            self.capacity.capacity_b.max = max;
-        }
-        if ship_type == TYPE_C {
-            self.capacity.capacity_c.min = min;
-            self.capacity.capacity_c.max = max;
-        }
-        if ship_type == TYPE_D {
-            self.capacity.capacity_d.min = min;
-            self.capacity.capacity_d.max = max;
-        }
-        if ship_type == TYPE_S {
-            self.capacity.capacity_s.min = min;
-            self.capacity.capacity_s.max = max;
-        }
-    }
-
     // SSP-00-01
     pub fn burn_ship_with_supply_updated(
         &mut self,
@@ -509,41 +970,54 @@ impl Contract {
         eng_receiver: Option<AccountId>,
     ) {
         // burn token
-        let ship_element = parse_token_id(token_id);
+        let ship_element = self.internal_ship_attributes(token_id);
         require!(
             ship_element.ship_type != TYPE_S,
             "SpaceShip: not support type"
         );
+        require!(
+            self.internal_active_rental(token_id).is_none(),
+            "ERR_SHIP_RENTED"
+        );
+        self.internal_assert_not_locked(token_id);
+        self.rentals.remove(token_id);
+        self.ship_progress.remove(token_id);
         self.tokens.internal_burn(token_id);
+        Event::ShipBurn {
+            owner_id,
+            token_id,
+            ship_type: ship_element.ship_type,
+            ship_sub_type: ship_element.ship_sub_type,
+            capacity: ship_element.capacity,
+        }
+        .emit();
 
         self.upate_total_supply(&token_id, -1);
         self.update_owner_supply(&token_id, Some(owner_id.clone()), None);
+        self.ship_attributes.remove(token_id);
+        self.internal_release_nft_storage(token_id);
 
         // send eng
         if let Some(to) = eng_receiver {
-            self.internal_mint_eng_for_user(
-                to,
-                U128(self.eng_type_reward[(ship_element.ship_type - 1) as usize]),
-            );
-        }
-    }
-
-    pub fn internal_random(&self) -> u64 {
-        // get random data
-        let seeds: Vec<u8> = env::random_seed();
-        let mut seed: u128 = 0;
-
-        for i in 0..4 {
-            let mut temp_seed: u128 = 0;
-            for j in 0..8 {
-                temp_seed = temp_seed * 10 + seeds[i * 4 + j] as u128;
+            let amount = self.internal_get_tier(ship_element.ship_type).eng_reward;
+            self.internal_mint_eng(to.clone(), amount, EngSource::BurnReward);
+
+            Event::EngReward {
+                owner_id,
+                receiver_id: &to,
+                token_id,
+                ship_type: ship_element.ship_type,
+                ship_sub_type: ship_element.ship_sub_type,
+                capacity: ship_element.capacity,
+                amount,
             }
-            seed += temp_seed;
+            .emit();
         }
+    }
 
-        let mut r = StdRng::seed_from_u64(seed as u64); // <- Here we set the seed
-        let rnd: u64 = r.gen();
-
-        rnd // random number
+    /// Next random number for `domain`, every call consumes a fresh nonce
+    pub fn internal_random(&mut self, domain: &str) -> u64 {
+        self.random_nonce += 1;
+        random_u64(domain, self.random_nonce)
     }
 }
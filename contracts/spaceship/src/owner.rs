@@ -1,5 +1,61 @@
 use crate::*;
 
+// the capacity table before the tier registry replaced it
+#[derive(BorshDeserialize)]
+pub struct CapacityV0 {
+    capacity_a: CapacityRange,
+    capacity_b: CapacityRange,
+    capacity_c: CapacityRange,
+    capacity_d: CapacityRange,
+    capacity_s: CapacityRange,
+}
+
+impl CapacityV0 {
+    fn range(&self, ship_type: u8) -> Option<CapacityRange> {
+        match ship_type {
+            TYPE_S => Some(self.capacity_s.clone()),
+            TYPE_A => Some(self.capacity_a.clone()),
+            TYPE_B => Some(self.capacity_b.clone()),
+            TYPE_C => Some(self.capacity_c.clone()),
+            TYPE_D => Some(self.capacity_d.clone()),
+            _ => None,
+        }
+    }
+}
+
+// the NFT state before approvals were added
+#[derive(BorshDeserialize)]
+pub struct MyNonFungibleTokenV0 {
+    owner_id: AccountId,
+    owner_by_id: LookupMap<TokenId, AccountId>,
+    supply: Balance,
+    burned: Balance,
+    tokens_per_owner: Option<LookupMap<AccountId, UnorderedSet<TokenId>>>,
+    user_count: Balance,
+}
+
+// the state before the backlog of ship attributes, tiers, rentals, levels, locks and the timelock
+#[derive(BorshDeserialize)]
+pub struct ContractV0 {
+    owner_id: AccountId,
+    eng: FungibleToken,
+    eng_icon: Option<String>,
+    tokens: MyNonFungibleTokenV0,
+    metadata: LazyOption<NFTContractMetadata>,
+    next_id: u64,
+    total_supplies: UnorderedMap<String, ShipSupply>,
+    supply_per_owner: LookupMap<AccountId, UnorderedMap<String, ShipSupply>>,
+    icons: UnorderedMap<String, String>,
+    box_id: AccountId,
+    shippool_id: AccountId,
+    shipmarket_id: AccountId,
+    auction_id: AccountId,
+    luckpool_id: AccountId,
+    capacity: CapacityV0,
+    // TYPE_S, TYPE_A, TYPE_B, TYPE_C, TYPE_D
+    eng_type_reward: Vec<u128>,
+}
+
 impl Contract {
     pub fn assert_owner(&self) {
         require!(
@@ -11,27 +67,35 @@ impl Contract {
 
 #[near_bindgen]
 impl Contract {
+    /// timelocked, returns the id of the queued action
     #[payable]
-    pub fn set_owner(&mut self, owner_id: AccountId) {
+    pub fn set_owner(&mut self, owner_id: AccountId) -> u64 {
         assert_one_yocto();
         self.assert_owner();
-        self.owner_id = owner_id;
+        self.internal_queue_action(TimelockAction::SetOwner { owner_id })
     }
 
     /// only owner can mint token into ciruculation,
     /// and owner would be auto-registered if not registered when mint
-    //
+    /// timelocked, returns the id of the queued action
     #[payable]
-    pub fn mint_eng(&mut self, amount: U128) {
+    pub fn mint_eng(&mut self, amount: U128) -> u64 {
         assert_one_yocto();
         self.assert_owner();
-        if self.eng.storage_balance_of(self.owner_id.clone()).is_none() {
-            self.eng.internal_register_account(&self.owner_id);
-        }
-        self.eng.internal_deposit(&self.owner_id, amount.into());
+        self.internal_queue_action(TimelockAction::MintEng { amount })
     }
     //
 
+    /// point ENG mints and burns to the standalone ENG token,
+    /// balances are then moved there by `migrate_eng_balances`
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn set_eng_token(&mut self, eng_token_id: AccountId) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_queue_action(TimelockAction::SetEngToken { eng_token_id })
+    }
+
     #[payable]
     pub fn set_eng_icon(&mut self, icon: String) {
         assert_one_yocto();
@@ -40,33 +104,131 @@ impl Contract {
         self.eng_icon = Some(icon);
     }
 
+    /// timelocked, returns the id of the queued action
     #[payable]
-    pub fn set_ship_icon(&mut self, ship_type_sub_type: String, icon: String) {
+    pub fn set_ship_icon(&mut self, ship_type_sub_type: String, icon: String) -> u64 {
         assert_one_yocto();
         self.assert_owner();
         require!(icon.len() <= MAX_ICON_LENGTH, "ERR_ICON_TOO_LARGE");
-        self.icons.insert(&ship_type_sub_type, &icon);
+        self.internal_queue_action(TimelockAction::SetShipIcon {
+            ship_type_sub_type,
+            icon,
+        })
+    }
+
+    #[payable]
+    pub fn set_max_burn_batch(&mut self, max_burn_batch: u32) {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(max_burn_batch > 0, "ERR_INVALID_BATCH_SIZE");
+        self.max_burn_batch = max_burn_batch;
+    }
+
+    /// set NEP-199 royalty for ship type, `royalty` maps receiver to basis points,
+    /// an empty map removes the royalty of this ship type.
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn set_ship_royalty(&mut self, ship_type: u8, royalty: HashMap<AccountId, u32>) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(
+            self.tiers.get(&ship_type).is_some(),
+            "ERR_ILLEGAL_SHIP_TYPE"
+        );
+        let total: u32 = royalty.values().sum();
+        require!(total <= MAX_ROYALTY, "ERR_ROYALTY_TOO_HIGH");
+        self.internal_queue_action(TimelockAction::SetShipRoyalty { ship_type, royalty })
     }
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have, you need to implement migration from old state
-    /// (keep the old struct with different name to deserialize it first).
-    /// After migration goes live, revert back to this implementation for next updates.
+    /// Migrates from ContractV0, the default tiers take the stored capacity and ENG reward tables,
+    /// ships minted before keep their attributes in the token id and are indexed by `index_ships_for_owner`.
+    /// After migration goes live, revert back to the NOOP implementation for next updates.
     #[init(ignore_state)]
     #[private]
     pub fn migrate() -> Self {
-        let contract: Contract = env::state_read().expect("ERR_NOT_INIT");
-        contract
-    }
-}
+        let old: ContractV0 = env::state_read().expect("ERR_NOT_INIT");
+        // the ENG minted before the upgrade counts towards the supply and the caps
+        let legacy_supply = old.eng.total_supply;
+        let mut this = Contract {
+            owner_id: old.owner_id,
+            eng: old.eng,
+            eng_icon: old.eng_icon,
+            tokens: MyNonFungibleToken {
+                owner_id: old.tokens.owner_id,
+                owner_by_id: old.tokens.owner_by_id,
+                supply: old.tokens.supply,
+                burned: old.tokens.burned,
+                tokens_per_owner: old.tokens.tokens_per_owner,
+                user_count: old.tokens.user_count,
+                approvals_by_id: LookupMap::new(StorageKey::Approval),
+                next_approval_id_by_id: LookupMap::new(StorageKey::NextApprovalId),
+            },
+            metadata: old.metadata,
+            next_id: old.next_id,
+            total_supplies: old.total_supplies,
+            supply_per_owner: old.supply_per_owner,
+            icons: old.icons,
+            ship_attributes: LookupMap::new(StorageKey::ShipAttributes),
 
-impl Contract {
-    pub fn internal_mint_eng_for_user(&mut self, receiver_id: AccountId, amount: U128) {
-        if self.eng.storage_balance_of(receiver_id.clone()).is_none() {
-            self.eng.internal_register_account(&receiver_id);
+            box_id: old.box_id,
+            shippool_id: old.shippool_id,
+            shipmarket_id: old.shipmarket_id,
+            auction_id: old.auction_id,
+            luckpool_id: old.luckpool_id,
+            collectpool_id: None,
+
+            royalties: UnorderedMap::new(StorageKey::Royalty),
+
+            random_nonce: 0,
+
+            pending_registrations: UnorderedMap::new(StorageKey::PendingRegistrations),
+
+            tiers: UnorderedMap::new(StorageKey::ShipTiers),
+
+            metadata_templates: UnorderedMap::new(StorageKey::MetadataTemplate),
+
+            max_burn_batch: DEFAULT_MAX_BURN_BATCH,
+
+            eng_emission: EngEmission::with_legacy_supply(legacy_supply),
+
+            eng_token_id: None,
+
+            rentals: LookupMap::new(StorageKey::Rentals),
+            staking_pools: UnorderedSet::new(StorageKey::StakingPools),
+
+            ship_progress: LookupMap::new(StorageKey::ShipProgress),
+            ship_levels: default_ship_levels(),
+
+            ships_per_owner: LookupMap::new(StorageKey::ShipsPerOwnerIndex),
+
+            collections: LookupMap::new(StorageKey::Collections),
+
+            nft_storage: LookupMap::new(StorageKey::NftStorage),
+            nft_storage_required: false,
+            ship_storage_payers: LookupMap::new(StorageKey::ShipStoragePayers),
+
+            ship_locks: LookupMap::new(StorageKey::ShipLocks),
+
+            next_action_id: 0,
+            pending_actions: UnorderedMap::new(StorageKey::PendingActions),
+            timelock_delay: DEFAULT_TIMELOCK_DELAY,
+
+            eng_claimable: LookupMap::new(StorageKey::EngClaimable),
+
+            tier_listeners: UnorderedSet::new(StorageKey::TierListeners),
+        };
+        for (ship_type, mut tier) in default_ship_tiers() {
+            if let Some(capacity) = old.capacity.range(ship_type) {
+                tier.capacity = capacity;
+            }
+            // the old reward table is indexed in reverse, TYPE_S first
+            if let Some(eng_reward) = old.eng_type_reward.get((TYPE_S - ship_type) as usize) {
+                tier.eng_reward = U128(*eng_reward);
+            }
+            this.tiers.insert(&ship_type, &tier);
         }
-        self.eng.internal_deposit(&receiver_id, amount.into());
+        this
     }
 }
 
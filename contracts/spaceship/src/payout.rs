@@ -0,0 +1,119 @@
+use crate::*;
+use near_contract_standards::non_fungible_token::refund_approved_account_ids;
+
+/// royalty shares are expressed in basis points of the sale balance
+pub const ROYALTY_DENOMINATOR: u32 = 10_000;
+/// total royalty of a ship type can never exceed half of the sale balance
+pub const MAX_ROYALTY: u32 = 5_000;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+/// NEP-199 royalties and payouts
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-22]
+    /// Given a `token_id` and the sale `balance`, return how the balance
+    /// should be split between royalty receivers and the token owner.
+    pub fn nft_payout(
+        &self,
+        token_id: TokenId,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("ERR: Token not found");
+        self.internal_payout(&owner_id, &token_id, balance.0, max_len_payout)
+    }
+
+    /// [SSP-00-23]
+    /// Transfer the token and return the payout of `balance` for the previous owner.
+    #[payable]
+    pub fn nft_transfer_payout(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        assert_one_yocto();
+        let owner_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("ERR: Token not found");
+        self.internal_assert_not_locked(&token_id);
+        require!(
+            self.internal_check_rental(&token_id, &receiver_id)
+                .is_none(),
+            "ERR_SHIP_RENTED"
+        );
+        let payout = self.internal_payout(&owner_id, &token_id, balance.0, max_len_payout);
+
+        let initial_usage = env::storage_usage();
+        self.update_owner_supply(&token_id, Some(owner_id), Some(receiver_id.clone()));
+        let (previous_owner_id, approved_account_ids) = self.tokens.internal_transfer(
+            &env::predecessor_account_id(),
+            &receiver_id,
+            &token_id,
+            approval_id,
+            memo,
+        );
+        if let Some(approved_account_ids) = approved_account_ids {
+            refund_approved_account_ids(previous_owner_id, &approved_account_ids);
+        }
+        self.internal_move_nft_storage(&token_id, &receiver_id, initial_usage, true);
+        payout
+    }
+
+    /// royalty of given ship type, account to basis points
+    pub fn get_ship_royalty(&self, ship_type: u8) -> HashMap<AccountId, u32> {
+        self.royalties.get(&ship_type).unwrap_or_default()
+    }
+}
+
+/// `share` basis points of `balance`, split so a large balance can not overflow
+fn royalty_amount(balance: u128, share: u32) -> u128 {
+    let denominator = ROYALTY_DENOMINATOR as u128;
+    balance / denominator * share as u128 + balance % denominator * share as u128 / denominator
+}
+
+impl Contract {
+    pub(crate) fn internal_payout(
+        &self,
+        owner_id: &AccountId,
+        token_id: &TokenId,
+        balance: u128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        let ship_type = self.internal_ship_attributes(token_id).ship_type;
+        let royalty = self.royalties.get(&ship_type).unwrap_or_default();
+        if let Some(max_len_payout) = max_len_payout {
+            require!(
+                royalty.len() < max_len_payout as usize,
+                "ERR_TOO_MANY_PAYOUT_RECEIVERS"
+            );
+        }
+
+        let mut payout: HashMap<AccountId, U128> = HashMap::new();
+        let mut owner_amount = balance;
+        for (account_id, share) in royalty.iter() {
+            let amount = royalty_amount(balance, *share);
+            owner_amount -= amount;
+            payout.insert(account_id.clone(), U128(amount));
+        }
+        let owner_total = payout.get(owner_id).map(|x| x.0).unwrap_or(0) + owner_amount;
+        payout.insert(owner_id.clone(), U128(owner_total));
+
+        Payout { payout }
+    }
+}
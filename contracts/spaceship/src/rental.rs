@@ -0,0 +1,185 @@
+use crate::*;
+use near_sdk::Promise;
+
+pub const GAS_FOR_NFT_ON_RENTAL_EXPIRED: Gas = Gas(30 * TGAS);
+
+/// Usage rights on a ship granted by its owner,
+/// the ship can only be staked by `user_id` and not moved by anyone else before `expires_at`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipRental {
+    pub owner_id: AccountId,
+    pub user_id: AccountId,
+    /// timestamp in seconds
+    pub expires_at: u64,
+}
+
+impl ShipRental {
+    pub fn is_active(&self) -> bool {
+        nano_to_sec(env::block_timestamp()) < self.expires_at
+    }
+}
+
+/// Staking pools free the slot of a rented ship its owner reclaimed
+#[ext_contract(ext_rental_pool)]
+pub trait RentalPool {
+    fn nft_on_rental_expired(&mut self, token_id: TokenId);
+}
+
+fn nano_to_sec(nano: u64) -> u64 {
+    nano / 10u64.pow(9)
+}
+
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-29] owner grants the usage rights of a ship to `user_id` until `expires_at` in seconds,
+    /// rights return to the owner automatically after that.
+    #[payable]
+    pub fn nft_set_user(&mut self, token_id: TokenId, user_id: AccountId, expires_at: u64) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        require!(
+            Some(owner_id.clone()) == self.tokens.owner_by_id.get(&token_id),
+            "ERR_NOT_NFT_OWNER"
+        );
+        require!(
+            self.internal_active_rental(&token_id).is_none(),
+            "ERR_SHIP_RENTED"
+        );
+        self.internal_assert_not_locked(&token_id);
+        require!(user_id != owner_id, "ERR_ILLEGAL_USER");
+        require!(
+            expires_at > nano_to_sec(env::block_timestamp()),
+            "ERR_ILLEGAL_EXPIRES"
+        );
+
+        self.rentals.insert(
+            &token_id,
+            &ShipRental {
+                owner_id: owner_id.clone(),
+                user_id: user_id.clone(),
+                expires_at,
+            },
+        );
+        Event::UpdateUser {
+            token_id: &token_id,
+            owner_id: &owner_id,
+            user_id: &user_id,
+            expires_at,
+        }
+        .emit();
+    }
+
+    /// current user of a ship, None if it is not rented or the rental expired
+    pub fn nft_user_of(&self, token_id: TokenId) -> Option<AccountId> {
+        self.internal_active_rental(&token_id)
+            .map(|rental| rental.user_id)
+    }
+
+    pub fn get_ship_rental(&self, token_id: TokenId) -> Option<ShipRental> {
+        self.internal_active_rental(&token_id)
+    }
+
+    /// [SSP-00-42] owner takes back a ship its user left staked or locked in a pool
+    /// after the rental expired, the pool frees the user's slot.
+    #[payable]
+    pub fn reclaim_ship(&mut self, token_id: TokenId) -> Promise {
+        assert_one_yocto();
+        let rental = self.rentals.get(&token_id).expect("ERR_SHIP_NOT_RENTED");
+        require!(
+            env::predecessor_account_id() == rental.owner_id,
+            "ERR_NOT_NFT_OWNER"
+        );
+        require!(!rental.is_active(), "ERR_SHIP_RENTED");
+        let holder_id = self
+            .tokens
+            .owner_by_id
+            .get(&token_id)
+            .expect("ERR: Token not found");
+
+        let pool_id = match self.ship_locks.get(&token_id) {
+            Some(lock) if lock.locked_by == rental.user_id => {
+                self.internal_unlock(&token_id, &lock.pool_id);
+                lock.pool_id
+            }
+            // only a pool the user staked the ship into holds it while the rental record exists
+            _ if holder_id != rental.owner_id => {
+                let initial_usage = env::storage_usage();
+                self.update_owner_supply(
+                    &token_id,
+                    Some(holder_id.clone()),
+                    Some(rental.owner_id.clone()),
+                );
+                self.tokens
+                    .internal_transfer(&holder_id, &rental.owner_id, &token_id, None, None);
+                self.internal_move_nft_storage(&token_id, &rental.owner_id, initial_usage, false);
+                holder_id
+            }
+            _ => env::panic_str("ERR_NOTHING_TO_RECLAIM"),
+        };
+        self.rentals.remove(&token_id);
+
+        ext_rental_pool::nft_on_rental_expired(token_id, pool_id, 0, GAS_FOR_NFT_ON_RENTAL_EXPIRED)
+    }
+
+    /// pools rented ships can be staked into by their users, they also report staking time for xp
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn add_staking_pool(&mut self, pool_id: AccountId) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_queue_action(TimelockAction::AddStakingPool { pool_id })
+    }
+
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn remove_staking_pool(&mut self, pool_id: AccountId) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_queue_action(TimelockAction::RemoveStakingPool { pool_id })
+    }
+
+    pub fn get_staking_pools(&self) -> Vec<AccountId> {
+        self.staking_pools.to_vec()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_active_rental(&self, token_id: &TokenId) -> Option<ShipRental> {
+        self.rentals
+            .get(token_id)
+            .filter(|rental| rental.is_active())
+    }
+
+    /// Panics if an active rental forbids moving the ship to `receiver_id`.
+    /// Returns the owner when the user stakes the rented ship into a staking pool.
+    pub(crate) fn internal_check_rental(
+        &mut self,
+        token_id: &TokenId,
+        receiver_id: &AccountId,
+    ) -> Option<AccountId> {
+        let rental = match self.rentals.get(token_id) {
+            Some(rental) if rental.is_active() => rental,
+            Some(_) => {
+                self.rentals.remove(token_id);
+                return None;
+            }
+            None => return None,
+        };
+
+        let holder_id = self.tokens.owner_by_id.get(token_id).unwrap();
+        if env::predecessor_account_id() == rental.user_id
+            && holder_id == rental.owner_id
+            && self.staking_pools.contains(receiver_id)
+        {
+            return Some(rental.owner_id);
+        }
+        // a staking pool can only return a rented ship to its owner
+        require!(
+            self.staking_pools.contains(&holder_id) && receiver_id == &rental.owner_id,
+            "ERR_SHIP_RENTED"
+        );
+        None
+    }
+}
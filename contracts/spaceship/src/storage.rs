@@ -0,0 +1,212 @@
+use crate::*;
+use near_sdk::Promise;
+
+/// NEAR an account deposited to pay for the storage of the ships minted to it.
+/// The `storage_*` methods belong to the ENG token, ship storage goes through `nft_storage_*`.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct NftStorage {
+    pub total: u128,
+    pub used_bytes: u64,
+}
+
+impl NftStorage {
+    pub fn available(&self) -> u128 {
+        // ships handed back by a reverted transfer may exceed the deposit
+        self.total
+            .saturating_sub(self.used_bytes as u128 * env::storage_byte_cost())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct NftStorageBalance {
+    pub total: U128,
+    pub available: U128,
+    pub used_bytes: u64,
+}
+
+impl From<&NftStorage> for NftStorageBalance {
+    fn from(storage: &NftStorage) -> Self {
+        Self {
+            total: U128(storage.total),
+            available: U128(storage.available()),
+            used_bytes: storage.used_bytes,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// [SSP-00-34] deposit NEAR for the storage of ships minted to `account_id`, default the caller,
+    /// the first deposit must cover the storage of the account's own entry
+    #[payable]
+    pub fn nft_storage_deposit(&mut self, account_id: Option<AccountId>) -> NftStorageBalance {
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let amount = env::attached_deposit();
+        require!(amount > 0, "ERR_ZERO_DEPOSIT");
+        self.internal_deposit_nft_storage(&account_id, amount);
+        NftStorageBalance::from(&self.nft_storage.get(&account_id).unwrap())
+    }
+
+    /// [SSP-00-35] withdraw the deposit not used by owned ships, all of it if `amount` is None
+    #[payable]
+    pub fn nft_storage_withdraw(&mut self, amount: Option<U128>) -> NftStorageBalance {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut storage = self
+            .nft_storage
+            .get(&account_id)
+            .expect("ERR_STORAGE_NOT_REGISTERED");
+        let available = storage.available();
+        let amount = amount.map(|v| v.0).unwrap_or(available);
+        require!(amount <= available, "ERR_NOT_ENOUGH_STORAGE_BALANCE");
+
+        storage.total -= amount;
+        self.nft_storage.insert(&account_id, &storage);
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+        NftStorageBalance::from(&storage)
+    }
+
+    pub fn nft_storage_balance_of(&self, account_id: AccountId) -> Option<NftStorageBalance> {
+        self.nft_storage
+            .get(&account_id)
+            .map(|storage| NftStorageBalance::from(&storage))
+    }
+
+    /// when set, mints and transfers are refused unless the receiver's deposit covers the storage they use,
+    /// staking pools receiving ships included
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn set_nft_storage_required(&mut self, required: bool) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_queue_action(TimelockAction::SetNftStorageRequired { required })
+    }
+
+    pub fn is_nft_storage_required(&self) -> bool {
+        self.nft_storage_required
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_deposit_nft_storage(&mut self, account_id: &AccountId, amount: u128) {
+        let mut storage = match self.nft_storage.get(account_id) {
+            Some(storage) => storage,
+            None => {
+                // the entry is paid from the deposit and stays used while it exists
+                let initial_usage = env::storage_usage();
+                let storage = NftStorage {
+                    total: 0,
+                    used_bytes: 0,
+                };
+                self.nft_storage.insert(account_id, &storage);
+                NftStorage {
+                    used_bytes: env::storage_usage() - initial_usage,
+                    ..storage
+                }
+            }
+        };
+        storage.total += amount;
+        require!(
+            storage.total >= storage.used_bytes as u128 * env::storage_byte_cost(),
+            "ERR_DEPOSIT_BELOW_REGISTRATION"
+        );
+        self.nft_storage.insert(account_id, &storage);
+    }
+
+    /// shipmarket attaches 1 yocto plus the storage deposit the owner passed through
+    pub(crate) fn internal_deposit_attached_storage(&mut self, owner_id: &AccountId) {
+        let deposit = env::attached_deposit();
+        require!(
+            deposit >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        if deposit > 1 {
+            self.internal_deposit_nft_storage(owner_id, deposit - 1);
+        }
+    }
+
+    /// charge `account_id` for the storage written by minting `token_id` since `initial_usage`
+    pub(crate) fn internal_charge_nft_storage(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        initial_usage: u64,
+    ) {
+        if !self.nft_storage_required {
+            return;
+        }
+        // the payer record is part of the charged storage
+        self.ship_storage_payers
+            .insert(token_id, &(account_id.clone(), 0));
+        let used_bytes = env::storage_usage().saturating_sub(initial_usage);
+        self.ship_storage_payers
+            .insert(token_id, &(account_id.clone(), used_bytes));
+
+        let mut storage = self.nft_storage.get(account_id).unwrap_or(NftStorage {
+            total: 0,
+            used_bytes: 0,
+        });
+        require!(
+            storage.available() >= used_bytes as u128 * env::storage_byte_cost(),
+            "ERR_INSUFFICIENT_NFT_STORAGE"
+        );
+        storage.used_bytes += used_bytes;
+        self.nft_storage.insert(account_id, &storage);
+    }
+
+    /// hand the storage of `token_id` over to its new owner `receiver_id`, together with
+    /// what the transfer wrote since `initial_usage`, the previous payer gets its share back.
+    /// `checked` refuses the transfer if the receiver's deposit does not cover it.
+    pub(crate) fn internal_move_nft_storage(
+        &mut self,
+        token_id: &TokenId,
+        receiver_id: &AccountId,
+        initial_usage: u64,
+        checked: bool,
+    ) {
+        if !self.nft_storage_required {
+            return;
+        }
+        let paid_bytes = self
+            .ship_storage_payers
+            .get(token_id)
+            .map_or(0, |(_, used_bytes)| used_bytes);
+        self.internal_release_nft_storage(token_id);
+        // the payer record is part of the charged storage
+        self.ship_storage_payers
+            .insert(token_id, &(receiver_id.clone(), 0));
+        let used_bytes = (paid_bytes + env::storage_usage()).saturating_sub(initial_usage);
+        self.ship_storage_payers
+            .insert(token_id, &(receiver_id.clone(), used_bytes));
+        if used_bytes == 0 {
+            return;
+        }
+
+        let mut storage = self.nft_storage.get(receiver_id).unwrap_or(NftStorage {
+            total: 0,
+            used_bytes: 0,
+        });
+        if checked {
+            require!(
+                storage.available() >= used_bytes as u128 * env::storage_byte_cost(),
+                "ERR_INSUFFICIENT_NFT_STORAGE"
+            );
+        }
+        storage.used_bytes += used_bytes;
+        self.nft_storage.insert(receiver_id, &storage);
+    }
+
+    /// give the storage paid for a ship back to whoever paid it
+    pub(crate) fn internal_release_nft_storage(&mut self, token_id: &TokenId) {
+        if let Some((payer_id, used_bytes)) = self.ship_storage_payers.remove(token_id) {
+            if let Some(mut storage) = self.nft_storage.get(&payer_id) {
+                storage.used_bytes -= std::cmp::min(used_bytes, storage.used_bytes);
+                self.nft_storage.insert(&payer_id, &storage);
+            }
+        }
+    }
+}
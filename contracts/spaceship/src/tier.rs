@@ -0,0 +1,190 @@
+use crate::*;
+
+pub const GAS_FOR_TIER_UPDATE: Gas = Gas(10 * TGAS);
+
+/// A ship tier of the registry, keyed by its ship_type
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct ShipTier {
+    pub name: String,
+    pub sub_type_count: u8,
+    pub capacity: CapacityRange,
+    /// ENG minted to the owner when a ship of this tier is burned
+    pub eng_reward: U128,
+    /// ship_type that two ships of this tier upgrade into
+    pub upgrade_target: Option<u8>,
+    /// capacity collectpool adds once every subtype of this tier is staked
+    pub collect_bonus: u32,
+}
+
+/// Contracts rolling ship types keep a copy of the registry, spaceship pushes every tier change
+#[ext_contract(ext_tier_listener)]
+pub trait ShipTierListener {
+    fn on_ship_tier_updated(
+        &mut self,
+        ship_type: u8,
+        sub_type_count: u8,
+        upgrade_target: Option<u8>,
+        capacity: CapacityRange,
+        collect_bonus: u32,
+    );
+}
+
+/// The tiers a new spaceship contract starts with
+pub fn default_ship_tiers() -> Vec<(u8, ShipTier)> {
+    let tier = |name: &str,
+                sub_type_count: u8,
+                min: u32,
+                max: u32,
+                eng_reward: u128,
+                upgrade_target: Option<u8>,
+                collect_bonus: u32| ShipTier {
+        name: name.to_string(),
+        sub_type_count,
+        capacity: CapacityRange { min, max },
+        eng_reward: U128(eng_reward),
+        upgrade_target,
+        collect_bonus,
+    };
+    vec![
+        (
+            TYPE_D,
+            tier("D", TYPE_D_MAX, 10, 15, 20 * YOCTO18, Some(TYPE_C), 5),
+        ),
+        (
+            TYPE_C,
+            tier("C", TYPE_C_MAX, 20, 30, 8 * YOCTO18, Some(TYPE_B), 10),
+        ),
+        (
+            TYPE_B,
+            tier("B", TYPE_B_MAX, 40, 60, 4 * YOCTO18, Some(TYPE_A), 15),
+        ),
+        (
+            TYPE_A,
+            tier("A", TYPE_A_MAX, 80, 120, 2 * YOCTO18, None, 20),
+        ),
+        (
+            TYPE_S,
+            tier("S", TYPE_S_COUNT as u8, 240, 240, YOCTO18, None, 0),
+        ),
+    ]
+}
+
+#[near_bindgen]
+impl Contract {
+    /// add a new tier or update an existing one, the change is pushed to the tier listeners
+    /// timelocked, returns the id of the queued action
+    #[payable]
+    pub fn set_ship_tier(&mut self, ship_type: u8, tier: ShipTier) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(ship_type > 0, "ERR_ILLEGAL_SHIP_TYPE");
+        require!(tier.sub_type_count > 0, "ERR_ILLEGAL_SUB_TYPE_COUNT");
+        require!(
+            tier.capacity.max >= tier.capacity.min,
+            "SpaceShip: invalid min and max"
+        );
+        if let Some(target) = tier.upgrade_target {
+            require!(
+                target != ship_type && self.tiers.get(&target).is_some(),
+                "ERR_ILLEGAL_UPGRADE_TARGET"
+            );
+        }
+        self.internal_queue_action(TimelockAction::SetShipTier { ship_type, tier })
+    }
+
+    pub fn get_ship_tier(&self, ship_type: u8) -> Option<ShipTier> {
+        self.tiers.get(&ship_type)
+    }
+
+    pub fn get_ship_tiers(&self) -> Vec<(u8, ShipTier)> {
+        self.tiers.to_vec()
+    }
+
+    /// another contract keeping a copy of the registry, it receives every tier right away
+    #[payable]
+    pub fn add_tier_listener(&mut self, listener_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.tier_listeners.insert(&listener_id);
+        for (ship_type, tier) in self.tiers.iter() {
+            self.internal_push_tier(&listener_id, ship_type, &tier);
+        }
+    }
+
+    #[payable]
+    pub fn remove_tier_listener(&mut self, listener_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.tier_listeners.remove(&listener_id);
+    }
+
+    pub fn get_tier_listeners(&self) -> Vec<AccountId> {
+        self.tier_listeners.to_vec()
+    }
+}
+
+impl Contract {
+    pub fn internal_get_tier(&self, ship_type: u8) -> ShipTier {
+        self.tiers.get(&ship_type).expect("ERR_ILLEGAL_SHIP_TYPE")
+    }
+
+    /// whether `target_type` is reached from `ship_type` by following the upgrade targets
+    pub(crate) fn internal_is_upgrade_of(&self, ship_type: u8, target_type: u8) -> bool {
+        let mut current = ship_type;
+        // every tier is visited at most once, a misconfigured cycle ends the walk
+        for _ in 0..self.tiers.len() {
+            match self
+                .tiers
+                .get(&current)
+                .and_then(|tier| tier.upgrade_target)
+            {
+                Some(next) if next == target_type => return true,
+                Some(next) => current = next,
+                None => return false,
+            }
+        }
+        false
+    }
+
+    pub(crate) fn internal_set_ship_tier(&mut self, ship_type: u8, tier: ShipTier) {
+        self.tiers.insert(&ship_type, &tier);
+
+        Event::ShipTierUpdated {
+            ship_type,
+            name: &tier.name,
+            sub_type_count: tier.sub_type_count,
+            upgrade_target: tier.upgrade_target,
+        }
+        .emit();
+        self.internal_notify_tier_listeners(ship_type, &tier);
+    }
+
+    /// push a tier change to the contracts keeping a copy of the registry
+    pub(crate) fn internal_notify_tier_listeners(&self, ship_type: u8, tier: &ShipTier) {
+        let mut listeners = vec![
+            self.box_id.clone(),
+            self.shipmarket_id.clone(),
+            self.shippool_id.clone(),
+        ];
+        listeners.extend(self.collectpool_id.clone());
+        listeners.extend(self.tier_listeners.iter());
+        for listener_id in listeners.iter() {
+            self.internal_push_tier(listener_id, ship_type, tier);
+        }
+    }
+
+    fn internal_push_tier(&self, listener_id: &AccountId, ship_type: u8, tier: &ShipTier) {
+        ext_tier_listener::on_ship_tier_updated(
+            ship_type,
+            tier.sub_type_count,
+            tier.upgrade_target,
+            tier.capacity.clone(),
+            tier.collect_bonus,
+            listener_id.clone(),
+            0,
+            GAS_FOR_TIER_UPDATE,
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::*;
+
+// two days for players to react before a queued action can run
+pub const DEFAULT_TIMELOCK_DELAY: u64 = 2 * 86_400;
+// the delay can be shortened but never turned off
+pub const MIN_TIMELOCK_DELAY: u64 = 3_600;
+
+/// Owner actions that only take effect `timelock_delay` seconds after they were queued
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub enum TimelockAction {
+    SetOwner {
+        owner_id: AccountId,
+    },
+    MintEng {
+        amount: U128,
+    },
+    SetShipIcon {
+        ship_type_sub_type: String,
+        icon: String,
+    },
+    SetSpaceshipTypeCapacity {
+        ship_type: u8,
+        min: u32,
+        max: u32,
+    },
+    SetTimelockDelay {
+        delay: u64,
+    },
+    SetShipTier {
+        ship_type: u8,
+        tier: ShipTier,
+    },
+    SetEngToken {
+        eng_token_id: AccountId,
+    },
+    SetEngEmissionCap {
+        total_cap: Option<U128>,
+        epoch_cap: Option<U128>,
+        epoch_blocks: u64,
+    },
+    SetShipRoyalty {
+        ship_type: u8,
+        royalty: HashMap<AccountId, u32>,
+    },
+    SetShipLevels {
+        levels: Vec<ShipLevel>,
+    },
+    AddStakingPool {
+        pool_id: AccountId,
+    },
+    RemoveStakingPool {
+        pool_id: AccountId,
+    },
+    SetNftStorageRequired {
+        required: bool,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, PartialEq))]
+pub struct PendingAction {
+    pub action_id: u64,
+    pub action: TimelockAction,
+    /// timestamp in seconds from which the action can be executed
+    pub eta: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// queue a change of the timelock delay, it is timelocked by the current delay
+    #[payable]
+    pub fn set_timelock_delay(&mut self, delay: u64) -> u64 {
+        assert_one_yocto();
+        self.assert_owner();
+        require!(delay >= MIN_TIMELOCK_DELAY, "ERR_TIMELOCK_DELAY_TOO_SHORT");
+        self.internal_queue_action(TimelockAction::SetTimelockDelay { delay })
+    }
+
+    pub fn get_timelock_delay(&self) -> u64 {
+        self.timelock_delay
+    }
+
+    /// [SSP-00-38] apply a queued action once its eta passed
+    #[payable]
+    pub fn execute_action(&mut self, action_id: u64) {
+        assert_one_yocto();
+        self.assert_owner();
+        let pending = self
+            .pending_actions
+            .get(&action_id)
+            .expect("ERR_ACTION_NOT_FOUND");
+        require!(
+            env::block_timestamp() / 10u64.pow(9) >= pending.eta,
+            "ERR_ACTION_NOT_READY"
+        );
+        self.pending_actions.remove(&action_id);
+
+        match pending.action.clone() {
+            TimelockAction::SetOwner { owner_id } => self.owner_id = owner_id,
+            TimelockAction::MintEng { amount } => {
+                self.internal_mint_eng(self.owner_id.clone(), amount, EngSource::OwnerMint)
+            }
+            TimelockAction::SetShipIcon {
+                ship_type_sub_type,
+                icon,
+            } => {
+                self.icons.insert(&ship_type_sub_type, &icon);
+            }
+            TimelockAction::SetSpaceshipTypeCapacity {
+                ship_type,
+                min,
+                max,
+            } => {
+                let mut tier = self.internal_get_tier(ship_type);
+                tier.capacity = CapacityRange { min, max };
+                self.internal_set_ship_tier(ship_type, tier);
+            }
+            TimelockAction::SetTimelockDelay { delay } => {
+                require!(delay >= MIN_TIMELOCK_DELAY, "ERR_TIMELOCK_DELAY_TOO_SHORT");
+                self.timelock_delay = delay
+            }
+            TimelockAction::SetShipTier { ship_type, tier } => {
+                self.internal_set_ship_tier(ship_type, tier)
+            }
+            TimelockAction::SetEngToken { eng_token_id } => self.eng_token_id = Some(eng_token_id),
+            TimelockAction::SetEngEmissionCap {
+                total_cap,
+                epoch_cap,
+                epoch_blocks,
+            } => self.internal_set_eng_emission_cap(total_cap, epoch_cap, epoch_blocks),
+            TimelockAction::SetShipRoyalty { ship_type, royalty } => {
+                if royalty.is_empty() {
+                    self.royalties.remove(&ship_type);
+                } else {
+                    self.royalties.insert(&ship_type, &royalty);
+                }
+            }
+            TimelockAction::SetShipLevels { levels } => self.ship_levels = levels,
+            TimelockAction::AddStakingPool { pool_id } => {
+                self.staking_pools.insert(&pool_id);
+            }
+            TimelockAction::RemoveStakingPool { pool_id } => {
+                self.staking_pools.remove(&pool_id);
+            }
+            TimelockAction::SetNftStorageRequired { required } => {
+                self.nft_storage_required = required
+            }
+        }
+        Event::ActionExecuted {
+            action_id,
+            action: &pending.action,
+        }
+        .emit();
+    }
+
+    /// [SSP-00-39] drop a queued action before it runs
+    #[payable]
+    pub fn cancel_action(&mut self, action_id: u64) {
+        assert_one_yocto();
+        self.assert_owner();
+        let pending = self
+            .pending_actions
+            .remove(&action_id)
+            .expect("ERR_ACTION_NOT_FOUND");
+        Event::ActionCancelled {
+            action_id,
+            action: &pending.action,
+        }
+        .emit();
+    }
+
+    /// [SSP-00-40]
+    /// Return the queued actions, oldest first
+    pub fn pending_actions(
+        &self,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<PendingAction> {
+        let mut actions = self.pending_actions.values_as_vector().to_vec();
+        actions.sort_by_key(|pending| pending.action_id);
+        actions
+            .into_iter()
+            .skip(from_index.unwrap_or(0) as usize)
+            .take(limit.unwrap_or(u64::MAX) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_queue_action(&mut self, action: TimelockAction) -> u64 {
+        self.next_action_id += 1;
+        let pending = PendingAction {
+            action_id: self.next_action_id,
+            action,
+            eta: env::block_timestamp() / 10u64.pow(9) + self.timelock_delay,
+        };
+        self.pending_actions.insert(&pending.action_id, &pending);
+        Event::ActionQueued {
+            action_id: pending.action_id,
+            action: &pending.action,
+            eta: pending.eta,
+        }
+        .emit();
+        pending.action_id
+    }
+}
@@ -12,7 +12,10 @@ pub struct Metadata {
     pub shipmarket_id: AccountId,
     pub auction_id: AccountId,
     pub luckpool_id: AccountId,
+    pub collectpool_id: Option<AccountId>,
     pub next_id: u64,
+    pub max_burn_batch: u32,
+    pub eng_token_id: Option<AccountId>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -36,11 +39,33 @@ impl Contract {
             shipmarket_id: self.shipmarket_id.clone(),
             auction_id: self.auction_id.clone(),
             luckpool_id: self.luckpool_id.clone(),
+            collectpool_id: self.collectpool_id.clone(),
             next_id: self.next_id,
+            max_burn_batch: self.max_burn_batch,
+            eng_token_id: self.eng_token_id.clone(),
         }
     }
 
     pub fn get_ship_icon(&self, ship_type_sub_type: String) -> Option<String> {
         self.icons.get(&ship_type_sub_type)
     }
+
+    /// ships of `owner_id` whose shippool registration failed and waits for a retry
+    pub fn get_pending_registrations(&self, owner_id: AccountId) -> Vec<TokenId> {
+        self.pending_registrations
+            .get(&owner_id)
+            .unwrap_or_default()
+    }
+
+    pub fn get_pending_registration_list(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<(AccountId, Vec<TokenId>)> {
+        self.pending_registrations
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
 }
@@ -30,4 +30,12 @@ impl Env {
             to_yocto("0.00125"),
         )
     }
+
+    pub fn burn_eng(&self, operator: &UserAccount, amount: U128) -> ExecutionResult {
+        operator.function_call(self.spaceship.contract.burn_eng(amount), MAX_GAS.0, 1)
+    }
+
+    pub fn claim_eng(&self, operator: &UserAccount) -> ExecutionResult {
+        operator.function_call(self.spaceship.contract.claim_eng(), MAX_GAS.0, 1)
+    }
 }
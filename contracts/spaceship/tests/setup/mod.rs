@@ -7,9 +7,17 @@ pub use near_sdk_sim::{
 };
 
 use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+pub use near_contract_standards::non_fungible_token::metadata::NFTContractMetadata;
 pub use near_contract_standards::non_fungible_token::{Token, TokenId};
 use mock_receiver::ContractContract as Mock;
 use spaceship::{ContractContract as SpaceShip, Metadata, ShipElements, SpaceShipSupply};
+pub use token_eng::ContractContract as EngToken;
+pub use spaceship::{
+    CapacityRange, CollectionProgress, EngEmissionStats, NftStorageBalance, Payout,
+    PendingAction, ShipAttributes, ShipFilter, ShipLevel, ShipLock, ShipMetadataTemplate,
+    ShipProgress, ShipRental, ShipTier, TimelockAction, YOCTO18,
+};
+pub use std::collections::HashMap;
 
 mod views;
 pub use views::*;
@@ -24,6 +32,7 @@ near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
     SPACESHIP_WASM_BYTES => "../../res/spaceship.wasm",
     PREV_SPACESHIP_WASM_BYTES => "../../res/spaceship.wasm",
     MOCK_WASM_BYTES => "../../res/mock_receiver.wasm",
+    ENG_WASM_BYTES => "../../res/token_eng.wasm",
 }
 
 pub const OWNER_ID: &str = "owner";
@@ -31,8 +40,10 @@ pub const MAGICBOX_ID: &str = "magicbox";
 pub const SHIPMARKET_ID: &str = "shipmarket";
 pub const AUCTION_ID: &str = "auction";
 pub const LUCKPOOL_ID: &str = "luckpool";
+pub const COLLECTPOOL_ID: &str = "collectpool";
 pub const SPACESHIP_ID: &str = "spaceship";
 pub const SHIPPOOL_ID: &str = "shippool";
+pub const ENG_ID: &str = "eng";
 
 pub const DEFAULT_GAS: Gas = Gas(Gas::ONE_TERA.0 * 15);
 pub const MAX_GAS: Gas = Gas(Gas::ONE_TERA.0 * 300);
@@ -54,6 +65,7 @@ pub struct Env {
     pub shipmarket: ContractAccount<Mock>,
     pub auction: UserAccount,
     pub luckpool: UserAccount,
+    pub collectpool: UserAccount,
     pub spaceship: ContractAccount<SpaceShip>,
     pub shippool: ContractAccount<Mock>,
 }
@@ -66,6 +78,7 @@ impl Env {
         let magicbox = root.create_user(MAGICBOX_ID.parse().unwrap(), to_yocto("100"));
         let auction = root.create_user(AUCTION_ID.parse().unwrap(), to_yocto("100"));
         let luckpool = root.create_user(LUCKPOOL_ID.parse().unwrap(), to_yocto("100"));
+        let collectpool = root.create_user(COLLECTPOOL_ID.parse().unwrap(), to_yocto("100"));
 
         let spaceship = deploy!(
             contract: SpaceShip,
@@ -74,7 +87,7 @@ impl Env {
             signer_account: root,
             deposit: to_yocto("100"), // Deposit required to cover contract storage.
             gas: near_sdk_sim::DEFAULT_GAS,
-            init_method: new(owner.account_id(), magicbox.account_id(), SHIPPOOL_ID.parse().unwrap(), SHIPMARKET_ID.parse().unwrap(), auction.account_id(), luckpool.account_id())
+            init_method: new(owner.account_id(), magicbox.account_id(), SHIPPOOL_ID.parse().unwrap(), SHIPMARKET_ID.parse().unwrap(), auction.account_id(), luckpool.account_id(), collectpool.account_id())
         );
 
         let shippool = deploy!(
@@ -100,16 +113,39 @@ impl Env {
             shipmarket,
             auction,
             luckpool,
+            collectpool,
             spaceship,
             shippool,
         }
     }
 
+    /// standalone ENG token which accepts balances migrated from spaceship
+    pub fn deploy_eng_token(&self) -> ContractAccount<EngToken> {
+        deploy!(
+            contract: EngToken,
+            contract_id: ENG_ID.to_string(),
+            bytes: &ENG_WASM_BYTES,
+            signer_account: self.root,
+            deposit: to_yocto("20"),
+            gas: near_sdk_sim::DEFAULT_GAS,
+            init_method: new(self.owner.account_id(), Some(self.spaceship.account_id()))
+        )
+    }
+
     pub fn upgrade_contract(&self, user: &UserAccount, contract_bytes: &[u8]) -> ExecutionResult {
         user.create_transaction(SPACESHIP_ID.parse().unwrap())
             .function_call("upgrade".to_string(), contract_bytes.to_vec(), MAX_GAS.0, 0)
             .submit()
     }
+
+    pub fn skip_time(&self, seconds: u64) {
+        self.root.borrow_runtime_mut().cur_block.block_timestamp += seconds * 10u64.pow(9);
+    }
+
+    /// current block timestamp in seconds
+    pub fn current_time(&self) -> u64 {
+        self.root.borrow_runtime().cur_block.block_timestamp / 10u64.pow(9)
+    }
 }
 
 pub fn init_env() -> Env {
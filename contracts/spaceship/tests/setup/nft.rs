@@ -17,6 +17,35 @@ impl Env {
         )
     }
 
+    pub fn batch_mint_with_deposit(
+        &self,
+        operator: &UserAccount,
+        owner_id: AccountId,
+        ship_types: Vec<String>,
+        ship_sub_types: Vec<String>,
+        deposit: Balance,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .batch_mint(owner_id, ship_types, ship_sub_types),
+            MAX_GAS.0,
+            deposit,
+        )
+    }
+
+    pub fn retry_register_ships(
+        &self,
+        operator: &UserAccount,
+        owner_id: AccountId,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.retry_register_ships(owner_id),
+            MAX_GAS.0,
+            0,
+        )
+    }
+
     pub fn nft_transfer(
         &self,
         operator: &UserAccount,
@@ -44,23 +73,111 @@ impl Env {
         )
     }
 
-    pub fn nft_payout(
+    pub fn nft_transfer_with_approval(
         &self,
         operator: &UserAccount,
         receiver_id: AccountId,
         token_id: TokenId,
+        approval_id: Option<u64>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .nft_transfer(receiver_id, token_id, approval_id, None),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn nft_approve(
+        &self,
+        operator: &UserAccount,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
     ) -> ExecutionResult {
         operator.function_call(
-            self.spaceship.contract.nft_payout(receiver_id, token_id),
+            self.spaceship.contract.nft_approve(token_id, account_id, msg),
+            MAX_GAS.0,
+            to_yocto("0.01"),
+        )
+    }
+
+    pub fn nft_revoke(
+        &self,
+        operator: &UserAccount,
+        token_id: TokenId,
+        account_id: AccountId,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.nft_revoke(token_id, account_id),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn nft_revoke_all(&self, operator: &UserAccount, token_id: TokenId) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.nft_revoke_all(token_id),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn auction_distribute(
+        &self,
+        operator: &UserAccount,
+        receiver_id: AccountId,
+        token_id: TokenId,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .auction_distribute(receiver_id, token_id),
             MAX_GAS.0,
             0,
         )
     }
 
+    pub fn nft_transfer_payout(
+        &self,
+        operator: &UserAccount,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.nft_transfer_payout(
+                receiver_id,
+                token_id,
+                approval_id,
+                None,
+                balance,
+                max_len_payout,
+            ),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
     pub fn user_burn(&self, operator: &UserAccount, token_id: TokenId) -> ExecutionResult {
         operator.function_call(self.spaceship.contract.user_burn(token_id), MAX_GAS.0, 1)
     }
 
+    pub fn user_batch_burn(
+        &self,
+        operator: &UserAccount,
+        token_ids: Vec<TokenId>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.user_batch_burn(token_ids),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
     pub fn upgrade_spaceship(
         &self,
         operator: &UserAccount,
@@ -82,4 +199,106 @@ impl Env {
             1,
         )
     }
+
+    pub fn fuse_spaceship(
+        &self,
+        operator: &UserAccount,
+        owner_id: AccountId,
+        token_ids: Vec<TokenId>,
+        target_type: u8,
+        target_sub_type: u8,
+        capacity_bonus: u32,
+        eng_amount: U128,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.fuse_spaceship(
+                owner_id,
+                token_ids,
+                target_type,
+                target_sub_type,
+                capacity_bonus,
+                eng_amount,
+            ),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn nft_set_user(
+        &self,
+        operator: &UserAccount,
+        token_id: TokenId,
+        user_id: AccountId,
+        expires_at: u64,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .nft_set_user(token_id, user_id, expires_at),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn add_ship_xp(
+        &self,
+        operator: &UserAccount,
+        token_id: TokenId,
+        staked_seconds: u64,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .add_ship_xp(token_id, staked_seconds),
+            MAX_GAS.0,
+            0,
+        )
+    }
+
+    pub fn nft_storage_deposit(
+        &self,
+        operator: &UserAccount,
+        account_id: Option<AccountId>,
+        deposit: Balance,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.nft_storage_deposit(account_id),
+            MAX_GAS.0,
+            deposit,
+        )
+    }
+
+    pub fn nft_storage_withdraw(
+        &self,
+        operator: &UserAccount,
+        amount: Option<U128>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.nft_storage_withdraw(amount),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn lock(
+        &self,
+        operator: &UserAccount,
+        token_id: TokenId,
+        pool: AccountId,
+        msg: String,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.lock(token_id, pool, msg),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn unlock(&self, operator: &UserAccount, token_id: TokenId) -> ExecutionResult {
+        operator.function_call(self.spaceship.contract.unlock(token_id), MAX_GAS.0, 1)
+    }
+
+    pub fn reclaim_ship(&self, operator: &UserAccount, token_id: TokenId) -> ExecutionResult {
+        operator.function_call(self.spaceship.contract.reclaim_ship(token_id), MAX_GAS.0, 1)
+    }
 }
@@ -1,23 +1,83 @@
 use crate::setup::*;
 
 impl Env {
+    /// execute a queued timelock action once its delay passed,
+    /// a failed queueing outcome is returned as is
+    pub fn execute_after_timelock(
+        &self,
+        operator: &UserAccount,
+        queued: ExecutionResult,
+    ) -> ExecutionResult {
+        if !queued.is_ok() {
+            return queued;
+        }
+        let action_id: u64 = queued.unwrap_json();
+        self.skip_time(self.get_timelock_delay());
+        self.execute_action(operator, action_id)
+    }
+
+    pub fn execute_action(&self, operator: &UserAccount, action_id: u64) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.execute_action(action_id),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn cancel_action(&self, operator: &UserAccount, action_id: u64) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.cancel_action(action_id),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn set_timelock_delay(&self, operator: &UserAccount, delay: u64) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.set_timelock_delay(delay),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn set_spaceship_type_capacity(
+        &self,
+        operator: &UserAccount,
+        ship_type: u8,
+        min: u32,
+        max: u32,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .set_spaceship_type_capacity(ship_type, min, max),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
     pub fn set_owner(
         &self,
         operator: &UserAccount,
         new_owner: &UserAccount,
         deposit: u128,
     ) -> ExecutionResult {
-        operator.function_call(
+        let queued = operator.function_call(
             self.spaceship.contract.set_owner(new_owner.account_id()),
             MAX_GAS.0,
             deposit,
-        )
+        );
+        self.execute_after_timelock(operator, queued)
     }
 
-    pub fn mint_eng(&self, operator: &UserAccount, amount: U128) -> ExecutionResult {
+    pub fn queue_mint_eng(&self, operator: &UserAccount, amount: U128) -> ExecutionResult {
         operator.function_call(self.spaceship.contract.mint_eng(amount), MAX_GAS.0, 1)
     }
 
+    pub fn mint_eng(&self, operator: &UserAccount, amount: U128) -> ExecutionResult {
+        self.execute_after_timelock(operator, self.queue_mint_eng(operator, amount))
+    }
+
     pub fn set_eng_icon(&self, operator: &UserAccount, icon: String) -> ExecutionResult {
         operator.function_call(
             self.spaceship.contract.set_eng_icon(icon.clone()),
@@ -32,12 +92,160 @@ impl Env {
         ship_type_sub_type: String,
         icon: String,
     ) -> ExecutionResult {
-        operator.function_call(
+        let queued = operator.function_call(
             self.spaceship
                 .contract
                 .set_ship_icon(ship_type_sub_type.clone(), icon.clone()),
             MAX_GAS.0,
             1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn set_ship_royalty(
+        &self,
+        operator: &UserAccount,
+        ship_type: u8,
+        royalty: HashMap<AccountId, u32>,
+    ) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship
+                .contract
+                .set_ship_royalty(ship_type, royalty),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn set_ship_tier(
+        &self,
+        operator: &UserAccount,
+        ship_type: u8,
+        tier: ShipTier,
+    ) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship.contract.set_ship_tier(ship_type, tier),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn set_ship_metadata_templates(
+        &self,
+        operator: &UserAccount,
+        templates: HashMap<String, ShipMetadataTemplate>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship
+                .contract
+                .set_ship_metadata_templates(templates),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn set_nft_metadata(
+        &self,
+        operator: &UserAccount,
+        base_uri: Option<String>,
+        icon: Option<String>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.set_nft_metadata(base_uri, icon),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn set_max_burn_batch(&self, operator: &UserAccount, max_burn_batch: u32) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.set_max_burn_batch(max_burn_batch),
+            MAX_GAS.0,
+            1,
         )
     }
+
+    pub fn set_eng_emission_cap(
+        &self,
+        operator: &UserAccount,
+        total_cap: Option<U128>,
+        epoch_cap: Option<U128>,
+        epoch_blocks: u64,
+    ) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship
+                .contract
+                .set_eng_emission_cap(total_cap, epoch_cap, epoch_blocks),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn set_eng_token(&self, operator: &UserAccount, eng_token_id: AccountId) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship.contract.set_eng_token(eng_token_id),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn migrate_eng_balances(
+        &self,
+        operator: &UserAccount,
+        account_ids: Vec<AccountId>,
+    ) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.migrate_eng_balances(account_ids),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn add_staking_pool(&self, operator: &UserAccount, pool_id: AccountId) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship.contract.add_staking_pool(pool_id),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn add_tier_listener(&self, operator: &UserAccount, listener_id: AccountId) -> ExecutionResult {
+        operator.function_call(
+            self.spaceship.contract.add_tier_listener(listener_id),
+            MAX_GAS.0,
+            1,
+        )
+    }
+
+    pub fn remove_staking_pool(&self, operator: &UserAccount, pool_id: AccountId) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship.contract.remove_staking_pool(pool_id),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn set_ship_levels(&self, operator: &UserAccount, levels: Vec<ShipLevel>) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship.contract.set_ship_levels(levels),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
+
+    pub fn set_nft_storage_required(&self, operator: &UserAccount, required: bool) -> ExecutionResult {
+        let queued = operator.function_call(
+            self.spaceship.contract.set_nft_storage_required(required),
+            MAX_GAS.0,
+            1,
+        );
+        self.execute_after_timelock(operator, queued)
+    }
 }
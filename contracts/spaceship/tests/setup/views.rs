@@ -19,6 +19,30 @@ impl Env {
             .unwrap_json::<FungibleTokenMetadata>()
     }
 
+    pub fn get_pending_registrations(&self, owner_id: AccountId) -> Vec<String> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_pending_registrations(owner_id))
+            .unwrap_json::<Vec<String>>()
+    }
+
+    pub fn get_ship_tier(&self, ship_type: u8) -> Option<ShipTier> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_ship_tier(ship_type))
+            .unwrap_json::<Option<ShipTier>>()
+    }
+
+    pub fn get_tier_listeners(&self) -> Vec<AccountId> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_tier_listeners())
+            .unwrap_json::<Vec<AccountId>>()
+    }
+
+    pub fn get_ship_levels(&self) -> Vec<ShipLevel> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_ship_levels())
+            .unwrap_json::<Vec<ShipLevel>>()
+    }
+
     pub fn get_ship_icon(&self, type_detail: String) -> Option<String> {
         self.owner
             .view_method_call(self.spaceship.contract.get_ship_icon(type_detail))
@@ -46,6 +70,12 @@ impl Env {
             .unwrap_json::<Vec<ShipElements>>()
     }
 
+    pub fn get_balance_of(&self, account_id: AccountId) -> u32 {
+        self.owner
+            .view_method_call(self.spaceship.contract.balance_of(account_id))
+            .unwrap_json::<u32>()
+    }
+
     pub fn get_balance_type_of(&self, account_id: AccountId, ship_type: u8) -> u32 {
         self.owner
             .view_method_call(self.spaceship.contract.balance_type_of(account_id, ship_type))
@@ -57,4 +87,128 @@ impl Env {
             .view_method_call(self.spaceship.contract.balance_subtype_of(account_id, ship_type, ship_sub_type))
             .unwrap_json::<u32>()
     }
+
+    pub fn get_nft_metadata(&self) -> NFTContractMetadata {
+        self.owner
+            .view_method_call(self.spaceship.contract.nft_metadata())
+            .unwrap_json::<NFTContractMetadata>()
+    }
+
+    pub fn get_nft_token(&self, token_id: TokenId) -> Option<Token> {
+        self.owner
+            .view_method_call(self.spaceship.contract.nft_token(token_id))
+            .unwrap_json::<Option<Token>>()
+    }
+
+    pub fn get_nft_is_approved(
+        &self,
+        token_id: TokenId,
+        approved_account_id: AccountId,
+        approval_id: Option<u64>,
+    ) -> bool {
+        self.owner
+            .view_method_call(self.spaceship.contract.nft_is_approved(
+                token_id,
+                approved_account_id,
+                approval_id,
+            ))
+            .unwrap_json::<bool>()
+    }
+
+    pub fn get_nft_payout(
+        &self,
+        token_id: TokenId,
+        balance: U128,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        self.owner
+            .view_method_call(
+                self.spaceship
+                    .contract
+                    .nft_payout(token_id, balance, max_len_payout),
+            )
+            .unwrap_json::<Payout>()
+    }
+
+    pub fn get_ship_attributes(&self, token_id: TokenId) -> Option<ShipAttributes> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_ship_attributes(token_id))
+            .unwrap_json::<Option<ShipAttributes>>()
+    }
+
+    pub fn get_eng_emission_stats(&self) -> EngEmissionStats {
+        self.owner
+            .view_method_call(self.spaceship.contract.eng_emission_stats())
+            .unwrap_json::<EngEmissionStats>()
+    }
+
+    pub fn get_eng_claimable(&self, account_id: AccountId) -> U128 {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_eng_claimable(account_id))
+            .unwrap_json::<U128>()
+    }
+
+    pub fn get_eng_total_burned(&self) -> U128 {
+        self.owner
+            .view_method_call(self.spaceship.contract.eng_total_burned())
+            .unwrap_json::<U128>()
+    }
+
+    pub fn get_nft_user_of(&self, token_id: TokenId) -> Option<AccountId> {
+        self.owner
+            .view_method_call(self.spaceship.contract.nft_user_of(token_id))
+            .unwrap_json::<Option<AccountId>>()
+    }
+
+    pub fn get_ship_progress(&self, token_id: TokenId) -> Option<ShipProgress> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_ship_progress(token_id))
+            .unwrap_json::<Option<ShipProgress>>()
+    }
+
+    pub fn get_ships_for_owner(
+        &self,
+        account_id: AccountId,
+        filter: Option<ShipFilter>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<ShipAttributes> {
+        self.owner
+            .view_method_call(
+                self.spaceship
+                    .contract
+                    .get_ships_for_owner(account_id, filter, from_index, limit),
+            )
+            .unwrap_json::<Vec<ShipAttributes>>()
+    }
+
+    pub fn get_collection_progress(&self, account_id: AccountId) -> CollectionProgress {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_collection_progress(account_id))
+            .unwrap_json::<CollectionProgress>()
+    }
+
+    pub fn get_nft_storage_balance_of(&self, account_id: AccountId) -> Option<NftStorageBalance> {
+        self.owner
+            .view_method_call(self.spaceship.contract.nft_storage_balance_of(account_id))
+            .unwrap_json::<Option<NftStorageBalance>>()
+    }
+
+    pub fn get_ship_lock(&self, token_id: TokenId) -> Option<ShipLock> {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_ship_lock(token_id))
+            .unwrap_json::<Option<ShipLock>>()
+    }
+
+    pub fn get_pending_actions(&self) -> Vec<PendingAction> {
+        self.owner
+            .view_method_call(self.spaceship.contract.pending_actions(None, None))
+            .unwrap_json::<Vec<PendingAction>>()
+    }
+
+    pub fn get_timelock_delay(&self) -> u64 {
+        self.owner
+            .view_method_call(self.spaceship.contract.get_timelock_delay())
+            .unwrap_json::<u64>()
+    }
 }
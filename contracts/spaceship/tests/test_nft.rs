@@ -325,6 +325,17 @@ fn transfer_spaceship() {
 
     let outcome = e.nft_transfer(&alice, bob.account_id(), token1_id.clone());
     outcome.assert_success();
+    // the ship attributes come in their own event, the NEP-171 memo stays empty
+    let logs = get_logs(&outcome);
+    assert!(logs.contains(&format!(
+        r#"EVENT_JSON:{{"standard":"nep171","version":"1.0.0","event":"nft_transfer","data":[{{"old_owner_id":"alice","new_owner_id":"bob","token_ids":["{}"]}}]}}"#,
+        token1_id
+    )));
+    assert!(logs.contains(&format!(
+        r#"EVENT_JSON:{{"standard":"spaceship","version":"1.0.0","event":"ship_transfer","data":[{{"old_owner_id":"alice","new_owner_id":"bob","token_id":"{}","ship_type":4,"ship_sub_type":1,"capacity":{}}}]}}"#,
+        token1_id,
+        ships[0].capacity
+    )));
     assert_eq!(e.get_balance_type_of(alice.account_id(), 4), 1);
     assert_eq!(e.get_balance_subtype_of(alice.account_id(), 4, 2), 1);
     assert_eq!(e.get_balance_type_of(bob.account_id(), 4), 1);
@@ -361,11 +372,11 @@ fn transfer_spaceship() {
 }
 
 #[test]
-fn nft_payout() {
+fn auction_distribute() {
     let e = Env::init_with_contract(spaceship_wasm_bytes());
     let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
 
-    let outcome = e.nft_payout(&e.auction, user.account_id(), String::from("1:5:1:240"));
+    let outcome = e.auction_distribute(&e.auction, user.account_id(), String::from("1:5:1:240"));
     outcome.assert_success();
 
     let ships = e.get_spaceship_list_for_owner(user.account_id(), None, None);
@@ -401,6 +412,60 @@ fn user_burn() {
     assert_eq!(ships.get(0).unwrap().to_token_id(), token2_id);
 }
 
+#[test]
+fn user_batch_burn() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["1".to_string(), "1".to_string(), "2".to_string()],
+        vec!["1".to_string(), "2".to_string(), "3".to_string()],
+    )
+    .assert_success();
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string()],
+        vec!["4".to_string()],
+    )
+    .assert_success();
+    let token_ids: Vec<TokenId> = e
+        .get_spaceship_list_for_owner(user.account_id(), None, None)
+        .iter()
+        .map(|ship| ship.to_token_id())
+        .collect();
+    let alice_token_id =
+        e.get_spaceship_list_for_owner(alice.account_id(), None, None)[0].to_token_id();
+
+    // the whole batch fails on a ship not owned by the caller
+    let mut with_foreign = token_ids.clone();
+    with_foreign.push(alice_token_id);
+    assert_err!(e.user_batch_burn(&user, with_foreign), "ERR_NOT_NFT_OWNER");
+    assert_eq!(e.get_balance_of(user.account_id()), 3);
+
+    e.set_max_burn_batch(&e.owner, 2).assert_success();
+    assert_err!(
+        e.user_batch_burn(&user, token_ids.clone()),
+        "ERR_INVALID_BATCH_SIZE"
+    );
+    e.set_max_burn_batch(&e.owner, 3).assert_success();
+
+    let outcome = e.user_batch_burn(&user, token_ids);
+    outcome.assert_success();
+    assert_eq!(outcome.unwrap_json::<U128>(), U128(48 * YOCTO18));
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"batch_burn""#)));
+    assert_eq!(e.get_balance_of(user.account_id()), 0);
+    assert_eq!(e.get_eng_balance_of(user.account_id()), U128(48 * YOCTO18));
+    assert_eq!(e.get_spaceship_supply().burned, U128(3));
+}
+
 #[test]
 fn upgrade_spaceship() {
     let e = Env::init_with_contract(spaceship_wasm_bytes());
@@ -450,3 +515,1479 @@ fn upgrade_spaceship() {
     assert_eq!(supplies.supply, U128(5));
     assert_eq!(supplies.owners, U128(2));
 }
+
+#[test]
+fn fuse_spaceship() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    e.mint_eng(&e.owner, U128(100)).assert_success();
+    e.eng_ft_register(&user, None).assert_success();
+    e.eng_ft_transfer(&&e.owner, user.account_id(), U128(50), None)
+        .assert_success();
+
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["1".to_string(), "1".to_string(), "1".to_string()],
+        vec!["1".to_string(), "2".to_string(), "3".to_string()],
+    )
+    .assert_success();
+    let token_ids: Vec<TokenId> = e
+        .get_spaceship_list_for_owner(user.account_id(), None, None)
+        .iter()
+        .map(|ship| ship.to_token_id())
+        .collect();
+
+    assert_err!(
+        e.fuse_spaceship(
+            &user,
+            user.account_id(),
+            token_ids.clone(),
+            3,
+            1,
+            50,
+            U128(50)
+        ),
+        "ERR_NOT_SHIPMARKET"
+    );
+    assert_err!(
+        e.fuse_spaceship(
+            &e.shipmarket.user_account,
+            user.account_id(),
+            vec![token_ids[0].clone(), token_ids[0].clone()],
+            3,
+            1,
+            50,
+            U128(50)
+        ),
+        "ERR_DUPLICATE_MATERIAL"
+    );
+    assert_err!(
+        e.fuse_spaceship(
+            &e.shipmarket.user_account,
+            user.account_id(),
+            token_ids.clone(),
+            3,
+            9,
+            50,
+            U128(50)
+        ),
+        "ERR_ILLEGAL_SHIP_SUB_TYPE"
+    );
+    // fusion only moves up the upgrade chain and never makes S-class ships
+    for target_type in [1, 5] {
+        assert_err!(
+            e.fuse_spaceship(
+                &e.shipmarket.user_account,
+                user.account_id(),
+                token_ids.clone(),
+                target_type,
+                1,
+                50,
+                U128(50)
+            ),
+            "ERR_ILLEGAL_TARGET_TYPE"
+        );
+    }
+
+    let outcome = e.fuse_spaceship(
+        &e.shipmarket.user_account,
+        user.account_id(),
+        token_ids.clone(),
+        3,
+        2,
+        50,
+        U128(50),
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"fuse_event""#)));
+    assert_eq!(e.get_balance_type_of(user.account_id(), 1), 0);
+    assert_eq!(e.get_balance_subtype_of(user.account_id(), 3, 2), 1);
+    assert_eq!(e.get_eng_balance_of(user.account_id()), U128(0));
+
+    // B rolls in [40, 60), raised by half
+    let ship = e.get_spaceship_list_for_owner(user.account_id(), None, None)[0].clone();
+    assert!(ship.capacity >= 60 && ship.capacity < 90);
+    assert_eq!(e.get_spaceship_supply().burned, U128(3));
+}
+
+#[test]
+fn approve_spaceship() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["4".to_string(), "4".to_string()],
+        vec!["1".to_string(), "2".to_string()],
+    )
+    .assert_success();
+    let ships = e.get_spaceship_list_for_owner(alice.account_id(), None, None);
+    let token1_id = ships.get(0).unwrap().to_token_id();
+
+    // only owner can approve
+    assert_err!(
+        e.nft_approve(&bob, token1_id.clone(), bob.account_id(), None),
+        "Predecessor must be token owner."
+    );
+
+    // approve bob, and shipmarket with nft_on_approve
+    e.nft_approve(&alice, token1_id.clone(), bob.account_id(), None)
+        .assert_success();
+    let outcome = e.nft_approve(
+        &alice,
+        token1_id.clone(),
+        e.shipmarket.account_id(),
+        Some("list".to_string()),
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .contains(&"[MOCK_RECEIVER] nft_on_approve, approval_id: 2, msg: list".to_string()));
+    assert!(e.get_nft_is_approved(token1_id.clone(), bob.account_id(), Some(1)));
+    assert!(!e.get_nft_is_approved(token1_id.clone(), bob.account_id(), Some(2)));
+    assert_eq!(
+        e.get_nft_token(token1_id.clone())
+            .unwrap()
+            .approved_account_ids
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // revoke shipmarket
+    e.nft_revoke(&alice, token1_id.clone(), e.shipmarket.account_id())
+        .assert_success();
+    assert!(!e.get_nft_is_approved(token1_id.clone(), e.shipmarket.account_id(), None));
+
+    // approved account transfers on behalf of the owner
+    assert_err!(
+        e.nft_transfer_with_approval(&bob, bob.account_id(), token1_id.clone(), Some(2)),
+        "is different from the given approval_id"
+    );
+    let outcome = e.nft_transfer_with_approval(&bob, bob.account_id(), token1_id.clone(), Some(1));
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""authorized_id":"bob""#)));
+    assert_eq!(e.get_balance_subtype_of(alice.account_id(), 4, 1), 0);
+    assert_eq!(e.get_balance_subtype_of(bob.account_id(), 4, 1), 1);
+
+    // approvals are cleared on transfer
+    assert_eq!(
+        e.get_nft_token(token1_id.clone())
+            .unwrap()
+            .approved_account_ids
+            .unwrap()
+            .len(),
+        0
+    );
+    assert_err!(
+        e.nft_transfer(&alice, alice.account_id(), token1_id.clone()),
+        "Sender not approved"
+    );
+
+    // revoke all
+    e.nft_approve(&bob, token1_id.clone(), alice.account_id(), None)
+        .assert_success();
+    e.nft_revoke_all(&bob, token1_id.clone()).assert_success();
+    assert!(!e.get_nft_is_approved(token1_id.clone(), alice.account_id(), None));
+}
+
+#[test]
+fn nft_transfer_payout() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+    let bank = e.root.create_user("bank".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["3".to_string()],
+        vec!["1".to_string()],
+    )
+    .assert_success();
+    let ships = e.get_spaceship_list_for_owner(alice.account_id(), None, None);
+    let token_id = ships.get(0).unwrap().to_token_id();
+
+    // no royalty, all goes to owner
+    let payout = e.get_nft_payout(token_id.clone(), U128(1000), None);
+    assert_eq!(payout.payout.len(), 1);
+    assert_eq!(payout.payout.get(&alice.account_id()), Some(&U128(1000)));
+
+    let mut royalty = HashMap::new();
+    royalty.insert(bank.account_id(), 500);
+    assert_err!(
+        e.set_ship_royalty(&alice, 3, royalty.clone()),
+        "ERR_NOT_ALLOWED"
+    );
+    royalty.insert(e.owner.account_id(), 5000);
+    assert_err!(
+        e.set_ship_royalty(&e.owner, 3, royalty.clone()),
+        "ERR_ROYALTY_TOO_HIGH"
+    );
+    royalty.remove(&e.owner.account_id());
+    e.set_ship_royalty(&e.owner, 3, royalty).assert_success();
+
+    let payout = e.get_nft_payout(token_id.clone(), U128(1000), Some(10));
+    assert_eq!(payout.payout.get(&bank.account_id()), Some(&U128(50)));
+    assert_eq!(payout.payout.get(&alice.account_id()), Some(&U128(950)));
+    // a balance near u128::MAX does not overflow the royalty math
+    let payout = e.get_nft_payout(token_id.clone(), U128(u128::MAX), None);
+    assert_eq!(
+        payout.payout.get(&bank.account_id()),
+        Some(&U128(u128::MAX / 20))
+    );
+    assert_eq!(
+        payout.payout.get(&alice.account_id()),
+        Some(&U128(u128::MAX - u128::MAX / 20))
+    );
+
+    // marketplace sells the ship on behalf of alice
+    e.nft_approve(&alice, token_id.clone(), bob.account_id(), None)
+        .assert_success();
+    assert_err!(
+        e.nft_transfer_payout(
+            &bob,
+            bob.account_id(),
+            token_id.clone(),
+            Some(1),
+            U128(1000),
+            Some(1)
+        ),
+        "ERR_TOO_MANY_PAYOUT_RECEIVERS"
+    );
+    let outcome = e.nft_transfer_payout(
+        &bob,
+        bob.account_id(),
+        token_id.clone(),
+        Some(1),
+        U128(1000),
+        Some(2),
+    );
+    outcome.assert_success();
+    let payout = outcome.unwrap_json::<Payout>();
+    assert_eq!(payout.payout.get(&alice.account_id()), Some(&U128(950)));
+    assert_eq!(e.get_balance_type_of(alice.account_id(), 3), 0);
+    assert_eq!(e.get_balance_type_of(bob.account_id(), 3), 1);
+}
+
+#[test]
+fn ship_attributes() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["2".to_string()],
+        vec!["7".to_string()],
+    )
+    .assert_success();
+    let ships = e.get_spaceship_list_for_owner(user.account_id(), None, None);
+    let ship = ships.get(0).unwrap().clone();
+    assert_eq!(ship.ship_type, 2);
+    assert_eq!(ship.ship_sub_type, 7);
+
+    let token_id = ship.to_token_id();
+    assert_eq!(e.get_ship_attributes(token_id.clone()), Some(ship.clone()));
+    let extra = e
+        .get_nft_token(token_id.clone())
+        .unwrap()
+        .metadata
+        .unwrap()
+        .extra;
+    assert_eq!(
+        extra,
+        Some(format!(
+            r#"{{"prefix_id":{},"ship_type":2,"ship_sub_type":7,"capacity":{}}}"#,
+            ship.prefix_id, ship.capacity
+        ))
+    );
+
+    e.user_burn(&user, token_id.clone()).assert_success();
+    assert_eq!(e.get_ship_attributes(token_id), None);
+}
+
+#[test]
+fn queue_failed_registration() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+    let rejected = e
+        .root
+        .create_user("rejected".parse().unwrap(), to_yocto("100"));
+
+    // shippool registers the ships, nothing queued
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["1".to_string()],
+        vec!["3".to_string()],
+    )
+    .assert_success();
+    assert!(e.get_pending_registrations(user.account_id()).is_empty());
+
+    // shippool rejects the registration, ships are minted and queued
+    let outcome = e.batch_mint(
+        &e.magicbox,
+        rejected.account_id(),
+        vec!["1".to_string(), "2".to_string()],
+        vec!["3".to_string(), "7".to_string()],
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"registration_queued""#)));
+    assert_eq!(
+        e.get_spaceship_list_for_owner(rejected.account_id(), None, None)
+            .len(),
+        2
+    );
+    assert_eq!(e.get_pending_registrations(rejected.account_id()).len(), 2);
+
+    // retry fails again, ships stay queued
+    e.retry_register_ships(&user, rejected.account_id())
+        .assert_success();
+    assert_eq!(e.get_pending_registrations(rejected.account_id()).len(), 2);
+
+    assert_err!(
+        e.retry_register_ships(&user, user.account_id()),
+        "ERR_NO_PENDING_REGISTRATION"
+    );
+
+    // an upgraded ship is queued the same way
+    e.batch_mint(
+        &e.magicbox,
+        rejected.account_id(),
+        vec!["1".to_string()],
+        vec!["4".to_string()],
+    )
+    .assert_success();
+    let materials: Vec<TokenId> = e
+        .get_spaceship_list_for_owner(rejected.account_id(), None, None)
+        .iter()
+        .filter(|ship| ship.ship_type == 1)
+        .map(|ship| ship.to_token_id())
+        .collect();
+    let outcome = e.upgrade_spaceship(
+        &e.shipmarket.user_account,
+        rejected.account_id(),
+        materials[0].clone(),
+        materials[1].clone(),
+        1,
+        U128(0),
+    );
+    assert!(outcome.unwrap_json::<bool>());
+    let upgraded = e
+        .get_spaceship_list_for_owner(rejected.account_id(), None, None)
+        .iter()
+        .find(|ship| ship.ship_type == 2 && ship.ship_sub_type == 1)
+        .unwrap()
+        .to_token_id();
+    assert!(e
+        .get_pending_registrations(rejected.account_id())
+        .contains(&upgraded));
+}
+
+#[test]
+fn ship_tier_registry() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    let tier_a = e.get_ship_tier(4).unwrap();
+    assert_eq!(tier_a.sub_type_count, 4);
+    assert_eq!(tier_a.upgrade_target, None);
+
+    let mythic = ShipTier {
+        name: "Mythic".to_string(),
+        sub_type_count: 2,
+        capacity: CapacityRange { min: 300, max: 300 },
+        eng_reward: U128(100),
+        upgrade_target: None,
+        collect_bonus: 25,
+    };
+    assert_err!(e.set_ship_tier(&user, 6, mythic.clone()), "ERR_NOT_ALLOWED");
+    assert_err!(
+        e.set_ship_tier(
+            &e.owner,
+            4,
+            ShipTier {
+                upgrade_target: Some(6),
+                ..tier_a.clone()
+            }
+        ),
+        "ERR_ILLEGAL_UPGRADE_TARGET"
+    );
+
+    // add a mythic tier above A, then let A upgrade into it
+    let outcome = e.set_ship_tier(&e.owner, 6, mythic.clone());
+    outcome.assert_success();
+    // shipmarket and shippool keep a copy of the registry
+    assert_eq!(
+        get_logs(&outcome)
+            .iter()
+            .filter(|log| log.as_str()
+                == "[MOCK_RECEIVER] on_ship_tier_updated, ship_type: 6, sub_type_count: 2")
+            .count(),
+        2
+    );
+    e.set_ship_tier(
+        &e.owner,
+        4,
+        ShipTier {
+            upgrade_target: Some(6),
+            ..tier_a
+        },
+    )
+    .assert_success();
+    assert_eq!(e.get_ship_tier(6), Some(mythic));
+
+    // a listener added later receives the whole registry
+    let outcome = e.add_tier_listener(&e.owner, e.shippool.account_id());
+    outcome.assert_success();
+    assert_eq!(
+        get_logs(&outcome)
+            .iter()
+            .filter(|log| log.starts_with("[MOCK_RECEIVER] on_ship_tier_updated"))
+            .count(),
+        6
+    );
+    assert_eq!(e.get_tier_listeners(), vec![e.shippool.account_id()]);
+
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["4".to_string(), "4".to_string()],
+        vec!["1".to_string(), "2".to_string()],
+    )
+    .assert_success();
+    assert_err!(
+        e.batch_mint(
+            &e.magicbox,
+            user.account_id(),
+            vec!["4".to_string()],
+            vec!["5".to_string()],
+        ),
+        "ERR_ILLEGAL_SHIP_SUB_TYPE"
+    );
+
+    e.eng_ft_register(&user, None).assert_success();
+    let ships = e.get_spaceship_list_for_owner(user.account_id(), None, None);
+    let token1_id = ships.get(0).unwrap().to_token_id();
+    let token2_id = ships.get(1).unwrap().to_token_id();
+    e.upgrade_spaceship(
+        &e.shipmarket.user_account,
+        user.account_id(),
+        token1_id,
+        token2_id,
+        2,
+        U128(0),
+    )
+    .assert_success();
+
+    let ships = e.get_spaceship_list_for_owner(user.account_id(), None, None);
+    assert_eq!(ships.len(), 1);
+    assert_eq!(ships[0].ship_type, 6);
+    assert_eq!(ships[0].ship_sub_type, 2);
+    assert_eq!(ships[0].capacity, 300);
+}
+
+#[test]
+fn ship_metadata_templates() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    let template = |title: &str| ShipMetadataTemplate {
+        title: Some(title.to_string()),
+        description: Some(format!("{} lore", title)),
+        media: Some(format!("{}.png", title)),
+        media_hash: None,
+        reference: Some(format!("{}.json", title)),
+        reference_hash: None,
+    };
+    let mut templates = HashMap::new();
+    templates.insert("2".to_string(), template("Frigate"));
+    templates.insert("2:7".to_string(), template("Frigate Mk7"));
+    assert_err!(
+        e.set_ship_metadata_templates(&user, templates.clone()),
+        "ERR_NOT_ALLOWED"
+    );
+    e.set_ship_metadata_templates(&e.owner, templates)
+        .assert_success();
+
+    let mut templates = HashMap::new();
+    templates.insert("2:17".to_string(), template("Frigate Mk17"));
+    assert_err!(
+        e.set_ship_metadata_templates(&e.owner, templates),
+        "ERR_ILLEGAL_SHIP_SUB_TYPE"
+    );
+
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["2".to_string(), "2".to_string()],
+        vec!["7".to_string(), "3".to_string()],
+    )
+    .assert_success();
+    let ships = e.get_spaceship_list_for_owner(user.account_id(), None, None);
+
+    // subtype template wins, other subtypes fall back to the type template
+    for ship in ships.iter() {
+        let metadata = e
+            .get_nft_token(ship.to_token_id())
+            .unwrap()
+            .metadata
+            .unwrap();
+        let title = if ship.ship_sub_type == 7 {
+            "Frigate Mk7"
+        } else {
+            "Frigate"
+        };
+        assert_eq!(metadata.title, Some(title.to_string()));
+        assert_eq!(metadata.media, Some(format!("{}.png", title)));
+        assert_eq!(metadata.reference, Some(format!("{}.json", title)));
+    }
+
+    assert_err!(
+        e.set_nft_metadata(&e.owner, None, Some("a".repeat(2049))),
+        "ERR_ICON_TOO_LARGE"
+    );
+    e.set_nft_metadata(
+        &e.owner,
+        Some("https://ships.example".to_string()),
+        Some("a".repeat(2048)),
+    )
+    .assert_success();
+    let metadata = e.get_nft_metadata();
+    assert_eq!(metadata.base_uri, Some("https://ships.example".to_string()));
+    assert_eq!(metadata.icon, Some("a".repeat(2048)));
+}
+
+#[test]
+fn eng_emission_accounting() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    e.mint_eng(&e.owner, U128(100 * YOCTO18)).assert_success();
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["1".to_string(), "2".to_string()],
+        vec!["1".to_string(), "1".to_string()],
+    )
+    .assert_success();
+    let token_ids: Vec<TokenId> = e
+        .get_spaceship_list_for_owner(user.account_id(), None, None)
+        .iter()
+        .map(|ship| ship.to_token_id())
+        .collect();
+    e.user_burn(&user, token_ids[0].clone()).assert_success();
+
+    let stats = e.get_eng_emission_stats();
+    assert_eq!(stats.owner_mint, U128(100 * YOCTO18));
+    assert!(stats.burn_reward.0 > 0);
+    assert_eq!(
+        stats.total_minted.0,
+        stats.owner_mint.0 + stats.burn_reward.0
+    );
+    assert_eq!(stats.total_supply, stats.total_minted);
+
+    e.burn_eng(&e.owner, U128(10 * YOCTO18)).assert_success();
+    assert_eq!(e.get_eng_total_burned(), U128(10 * YOCTO18));
+    assert_eq!(
+        e.get_eng_emission_stats().total_supply.0,
+        stats.total_minted.0 - 10 * YOCTO18
+    );
+
+    // no more ENG can be minted once the global cap is reached
+    assert_err!(
+        e.set_eng_emission_cap(&e.owner, Some(U128(YOCTO18)), None, 100),
+        "ERR_CAP_BELOW_MINTED"
+    );
+    e.set_eng_emission_cap(&e.owner, Some(stats.total_minted), None, 100)
+        .assert_success();
+    assert_err!(
+        e.user_burn(&user, token_ids[1].clone()),
+        "ERR_ENG_EMISSION_CAP_EXCEEDED"
+    );
+    assert_eq!(e.get_balance_of(user.account_id()), 1);
+
+    // every block is an epoch of its own
+    e.set_eng_emission_cap(&e.owner, None, Some(U128(YOCTO18)), 1)
+        .assert_success();
+    assert_err!(
+        e.mint_eng(&e.owner, U128(2 * YOCTO18)),
+        "ERR_ENG_EPOCH_CAP_EXCEEDED"
+    );
+    e.mint_eng(&e.owner, U128(YOCTO18)).assert_success();
+    assert_eq!(e.get_eng_emission_stats().owner_mint, U128(101 * YOCTO18));
+}
+
+#[test]
+fn migrate_eng_to_token() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    e.mint_eng(&e.owner, U128(100)).assert_success();
+    e.eng_ft_register(&user, None).assert_success();
+    e.eng_ft_transfer(&e.owner, user.account_id(), U128(40), None)
+        .assert_success();
+
+    let eng = e.deploy_eng_token();
+    assert_err!(
+        e.migrate_eng_balances(&e.owner, vec![user.account_id()]),
+        "ERR_ENG_TOKEN_NOT_SET"
+    );
+
+    // balances the token does not take stay in spaceship
+    e.set_eng_token(&e.owner, "missing".parse().unwrap())
+        .assert_success();
+    let outcome = e.migrate_eng_balances(&e.owner, vec![user.account_id()]);
+    assert!(!outcome.unwrap_json::<bool>());
+    assert_eq!(e.get_eng_balance_of(user.account_id()), U128(40));
+
+    e.set_eng_token(&e.owner, eng.account_id()).assert_success();
+    let outcome = e.migrate_eng_balances(&e.owner, vec![e.owner.account_id(), user.account_id()]);
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"eng_migrated""#)));
+
+    let eng_balance_of = |account_id: AccountId| {
+        e.owner
+            .view_method_call(eng.contract.ft_balance_of(account_id))
+            .unwrap_json::<U128>()
+    };
+    assert_eq!(e.get_eng_balance_of(user.account_id()), U128(0));
+    assert_eq!(eng_balance_of(user.account_id()), U128(40));
+    assert_eq!(eng_balance_of(e.owner.account_id()), U128(60));
+    assert_err!(e.burn_eng(&user, U128(1)), "ERR_ENG_MIGRATED");
+    assert_err!(
+        e.eng_ft_transfer(&user, e.owner.account_id(), U128(1), None),
+        "ERR_ENG_MIGRATED"
+    );
+
+    e.batch_mint(
+        &e.magicbox,
+        user.account_id(),
+        vec!["1".to_string(), "1".to_string(), "1".to_string()],
+        vec!["1".to_string(), "2".to_string(), "3".to_string()],
+    )
+    .assert_success();
+    let ships = e.get_spaceship_list_for_owner(user.account_id(), None, None);
+
+    // a burn reward the token refuses to mint becomes claimable
+    assert_err!(e.claim_eng(&user), "ERR_NOTHING_TO_CLAIM");
+    let outcome = e.user_burn(&user, ships[2].to_token_id());
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"eng_claimable""#)));
+    assert_eq!(eng_balance_of(user.account_id()), U128(40));
+    assert_eq!(e.get_eng_claimable(user.account_id()), U128(20 * YOCTO18));
+
+    // spaceship mints burn rewards and burns upgrade fees on the ENG token
+    e.owner
+        .function_call(
+            eng.contract.add_minter(e.spaceship.account_id()),
+            MAX_GAS.0,
+            1,
+        )
+        .assert_success();
+    e.owner
+        .function_call(
+            eng.contract.add_burner(e.spaceship.account_id()),
+            MAX_GAS.0,
+            1,
+        )
+        .assert_success();
+    e.claim_eng(&user).assert_success();
+    assert_eq!(eng_balance_of(user.account_id()), U128(20 * YOCTO18 + 40));
+    assert_eq!(e.get_eng_claimable(user.account_id()), U128(0));
+
+    // a failed burn upgrades nothing and reports it to shipmarket
+    let total_burned = e.get_eng_emission_stats().total_burned;
+    let outcome = e.upgrade_spaceship(
+        &e.shipmarket.user_account,
+        user.account_id(),
+        ships[0].to_token_id(),
+        ships[1].to_token_id(),
+        1,
+        U128(21 * YOCTO18),
+    );
+    assert!(!outcome.unwrap_json::<bool>());
+    assert_eq!(e.get_balance_type_of(user.account_id(), 1), 2);
+    assert_eq!(e.get_eng_emission_stats().total_burned, total_burned);
+
+    let outcome = e.upgrade_spaceship(
+        &e.shipmarket.user_account,
+        user.account_id(),
+        ships[0].to_token_id(),
+        ships[1].to_token_id(),
+        1,
+        U128(40),
+    );
+    assert!(outcome.unwrap_json::<bool>());
+    assert_eq!(eng_balance_of(user.account_id()), U128(20 * YOCTO18));
+    assert_eq!(e.get_balance_type_of(user.account_id(), 2), 1);
+    assert_eq!(
+        e.get_eng_emission_stats().total_burned.0,
+        total_burned.0 + 40
+    );
+
+    // the local ledger is left behind, the supply is the one of the token
+    let stats = e.get_eng_emission_stats();
+    assert_eq!(
+        stats.total_supply.0,
+        stats.total_minted.0 - stats.total_burned.0
+    );
+    assert_eq!(
+        stats.total_supply,
+        e.owner
+            .view_method_call(eng.contract.ft_total_supply())
+            .unwrap_json::<U128>()
+    );
+}
+
+#[test]
+fn ship_rental() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string()],
+        vec!["1".to_string()],
+    )
+    .assert_success();
+    let token_id = e.get_spaceship_list_for_owner(alice.account_id(), None, None)[0].to_token_id();
+    // staking pools are timelocked, added before the rental runs
+    e.add_staking_pool(&e.owner, e.shippool.account_id())
+        .assert_success();
+
+    assert_err!(
+        e.nft_set_user(
+            &bob,
+            token_id.clone(),
+            bob.account_id(),
+            e.current_time() + 3600
+        ),
+        "ERR_NOT_NFT_OWNER"
+    );
+    assert_err!(
+        e.nft_set_user(&alice, token_id.clone(), bob.account_id(), e.current_time()),
+        "ERR_ILLEGAL_EXPIRES"
+    );
+    let outcome = e.nft_set_user(
+        &alice,
+        token_id.clone(),
+        bob.account_id(),
+        e.current_time() + 3600,
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"update_user""#)));
+    assert_eq!(e.get_nft_user_of(token_id.clone()), Some(bob.account_id()));
+
+    // neither the owner nor the user can move a rented ship
+    assert_err!(
+        e.nft_transfer(&alice, bob.account_id(), token_id.clone()),
+        "ERR_SHIP_RENTED"
+    );
+    assert_err!(e.user_burn(&alice, token_id.clone()), "ERR_SHIP_RENTED");
+    assert_err!(
+        e.nft_transfer_call(
+            &bob,
+            e.shipmarket.account_id(),
+            token_id.clone(),
+            "0".to_string()
+        ),
+        "ERR_SHIP_RENTED"
+    );
+
+    // the user stakes it into a staking pool, which can only return it to the owner
+    e.nft_transfer_call(
+        &bob,
+        e.shippool.account_id(),
+        token_id.clone(),
+        "0".to_string(),
+    )
+    .assert_success();
+    assert_eq!(
+        e.get_nft_token(token_id.clone()).unwrap().owner_id,
+        e.shippool.account_id()
+    );
+    assert_err!(
+        e.nft_transfer(&e.shippool.user_account, bob.account_id(), token_id.clone()),
+        "ERR_SHIP_RENTED"
+    );
+    e.nft_transfer(
+        &e.shippool.user_account,
+        alice.account_id(),
+        token_id.clone(),
+    )
+    .assert_success();
+
+    // rights return to the owner after expiry
+    e.skip_time(3600);
+    assert_eq!(e.get_nft_user_of(token_id.clone()), None);
+    e.nft_transfer(&alice, bob.account_id(), token_id.clone())
+        .assert_success();
+}
+
+#[test]
+fn ship_rental_reclaim() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string(), "1".to_string()],
+        vec!["1".to_string(), "2".to_string()],
+    )
+    .assert_success();
+    let token_ids: Vec<TokenId> = e
+        .get_spaceship_list_for_owner(alice.account_id(), None, None)
+        .iter()
+        .map(|ship| ship.to_token_id())
+        .collect();
+    e.add_staking_pool(&e.owner, e.shippool.account_id())
+        .assert_success();
+    for token_id in token_ids.iter() {
+        e.nft_set_user(
+            &alice,
+            token_id.clone(),
+            bob.account_id(),
+            e.current_time() + 3600,
+        )
+        .assert_success();
+    }
+
+    // the user stakes one ship and locks the other
+    e.nft_transfer_call(
+        &bob,
+        e.shippool.account_id(),
+        token_ids[0].clone(),
+        "0".to_string(),
+    )
+    .assert_success();
+    e.lock(
+        &bob,
+        token_ids[1].clone(),
+        e.shippool.account_id(),
+        "0".to_string(),
+    )
+    .assert_success();
+
+    assert_err!(
+        e.reclaim_ship(&alice, token_ids[0].clone()),
+        "ERR_SHIP_RENTED"
+    );
+    e.skip_time(3600);
+    assert_err!(
+        e.reclaim_ship(&bob, token_ids[0].clone()),
+        "ERR_NOT_NFT_OWNER"
+    );
+
+    // after expiry the owner takes both back and the pool frees the user's slots
+    let outcome = e.reclaim_ship(&alice, token_ids[0].clone());
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains("[MOCK_RECEIVER] nft_on_rental_expired")));
+    assert_eq!(
+        e.get_nft_token(token_ids[0].clone()).unwrap().owner_id,
+        alice.account_id()
+    );
+
+    let outcome = e.reclaim_ship(&alice, token_ids[1].clone());
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_unlocked""#)));
+    assert_eq!(e.get_ship_lock(token_ids[1].clone()), None);
+
+    assert_err!(
+        e.reclaim_ship(&alice, token_ids[1].clone()),
+        "ERR_SHIP_NOT_RENTED"
+    );
+    for token_id in token_ids.iter() {
+        e.nft_transfer(&alice, bob.account_id(), token_id.clone())
+            .assert_success();
+    }
+}
+
+#[test]
+fn ship_leveling() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string()],
+        vec!["1".to_string()],
+    )
+    .assert_success();
+    let ship = e.get_spaceship_list_for_owner(alice.account_id(), None, None)[0].clone();
+    let token_id = ship.to_token_id();
+    let progress = e.get_ship_progress(token_id.clone()).unwrap();
+    assert_eq!(progress.level, 0);
+    assert_eq!(progress.effective_capacity, ship.capacity);
+
+    assert_err!(
+        e.add_ship_xp(&e.shippool.user_account, token_id.clone(), 3600),
+        "ERR_NOT_STAKING_POOL"
+    );
+    e.add_staking_pool(&e.owner, e.shippool.account_id())
+        .assert_success();
+    assert_err!(
+        e.set_ship_levels(
+            &e.owner,
+            vec![
+                ShipLevel {
+                    xp: 10,
+                    capacity_bonus: 10
+                },
+                ShipLevel {
+                    xp: 10,
+                    capacity_bonus: 20
+                },
+            ]
+        ),
+        "ERR_ILLEGAL_LEVELS"
+    );
+    // a level bonus can not overflow the capacity
+    assert_err!(
+        e.set_ship_levels(
+            &e.owner,
+            vec![ShipLevel {
+                xp: 10,
+                capacity_bonus: u32::MAX
+            }]
+        ),
+        "ERR_ILLEGAL_LEVELS"
+    );
+    e.set_ship_levels(
+        &e.owner,
+        vec![
+            ShipLevel {
+                xp: 10,
+                capacity_bonus: 100,
+            },
+            ShipLevel {
+                xp: 20,
+                capacity_bonus: 200,
+            },
+        ],
+    )
+    .assert_success();
+
+    // less than a level, no event
+    let outcome = e.add_ship_xp(&e.shippool.user_account, token_id.clone(), 9 * 3600);
+    outcome.assert_success();
+    assert!(get_logs(&outcome).is_empty());
+    assert_eq!(e.get_ship_progress(token_id.clone()).unwrap().xp, 9);
+
+    let outcome = e.add_ship_xp(&e.shippool.user_account, token_id.clone(), 3600 + 1800);
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_level_up""#)));
+    // the staking pools get the raised capacity
+    assert!(get_logs(&outcome).contains(&format!(
+        "[MOCK_RECEIVER] on_ship_level_up, token_id: {}, effective_capacity: {}",
+        token_id,
+        ship.capacity * 2
+    )));
+    let progress = e.get_ship_progress(token_id.clone()).unwrap();
+    assert_eq!(progress.xp, 10);
+    assert_eq!(progress.level, 1);
+    assert_eq!(progress.effective_capacity, ship.capacity * 2);
+
+    // the half hour left over counts with the next report
+    e.add_ship_xp(&e.shippool.user_account, token_id.clone(), 1800)
+        .assert_success();
+    let progress = e.get_ship_progress(token_id.clone()).unwrap();
+    assert_eq!(progress.xp, 11);
+    assert_eq!(progress.staked_seconds, 11 * 3600);
+
+    e.add_ship_xp(&e.shippool.user_account, token_id.clone(), 100 * 3600)
+        .assert_success();
+    let progress = e.get_ship_progress(token_id.clone()).unwrap();
+    assert_eq!(progress.level, 2);
+    assert_eq!(progress.effective_capacity, ship.capacity * 3);
+
+    // the owner filter counts the leveled capacity
+    let filter = |min_capacity: u32| ShipFilter {
+        min_capacity: Some(min_capacity),
+        ..Default::default()
+    };
+    assert_eq!(
+        e.get_ships_for_owner(
+            alice.account_id(),
+            Some(filter(ship.capacity * 3)),
+            None,
+            None
+        ),
+        vec![ship.clone()]
+    );
+    assert!(e
+        .get_ships_for_owner(
+            alice.account_id(),
+            Some(filter(ship.capacity * 3 + 1)),
+            None,
+            None
+        )
+        .is_empty());
+
+    // level shows in the metadata, the token id keeps the rolled capacity
+    let extra = e
+        .get_nft_token(token_id.clone())
+        .unwrap()
+        .metadata
+        .unwrap()
+        .extra
+        .unwrap();
+    assert!(extra.contains(r#""level":2"#));
+    assert!(extra.contains(&format!(r#""capacity":{}"#, ship.capacity)));
+}
+
+#[test]
+fn ships_for_owner_filter() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec![
+            "1".to_string(),
+            "1".to_string(),
+            "3".to_string(),
+            "3".to_string(),
+            "2".to_string(),
+        ],
+        vec![
+            "1".to_string(),
+            "2".to_string(),
+            "1".to_string(),
+            "2".to_string(),
+            "1".to_string(),
+        ],
+    )
+    .assert_success();
+
+    let b_filter = ShipFilter {
+        ship_type: Some(3),
+        ..Default::default()
+    };
+    let b_ships = e.get_ships_for_owner(alice.account_id(), Some(b_filter.clone()), None, None);
+    assert_eq!(b_ships.len(), 2);
+    assert!(b_ships.iter().all(|ship| ship.ship_type == 3));
+
+    let ships = e.get_ships_for_owner(
+        alice.account_id(),
+        Some(ShipFilter {
+            ship_type: Some(3),
+            ship_sub_type: Some(2),
+            ..Default::default()
+        }),
+        None,
+        None,
+    );
+    assert_eq!(ships.len(), 1);
+    assert_eq!(ships[0].ship_sub_type, 2);
+
+    let ships = e.get_ships_for_owner(
+        alice.account_id(),
+        Some(ShipFilter {
+            ship_type: Some(3),
+            min_capacity: Some(50),
+            ..Default::default()
+        }),
+        None,
+        None,
+    );
+    assert_eq!(
+        ships.len(),
+        b_ships.iter().filter(|ship| ship.capacity >= 50).count()
+    );
+
+    // pagination runs over all ships without a filter
+    assert_eq!(
+        e.get_ships_for_owner(alice.account_id(), None, None, Some(2))
+            .len(),
+        2
+    );
+    assert_eq!(
+        e.get_ships_for_owner(alice.account_id(), None, Some(4), None)
+            .len(),
+        1
+    );
+
+    // the index follows transfers and burns
+    let token_id = b_ships[0].to_token_id();
+    e.nft_transfer(&alice, bob.account_id(), token_id.clone())
+        .assert_success();
+    assert_eq!(
+        e.get_ships_for_owner(alice.account_id(), Some(b_filter.clone()), None, None)
+            .len(),
+        1
+    );
+    assert_eq!(
+        e.get_ships_for_owner(bob.account_id(), Some(b_filter.clone()), None, None),
+        vec![b_ships[0].clone()]
+    );
+    e.user_burn(&bob, token_id).assert_success();
+    assert!(e
+        .get_ships_for_owner(bob.account_id(), Some(b_filter), None, None)
+        .is_empty());
+}
+
+#[test]
+fn collection_progress() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["4".to_string(), "4".to_string(), "4".to_string()],
+        vec!["1".to_string(), "2".to_string(), "2".to_string()],
+    )
+    .assert_success();
+    let progress = e.get_collection_progress(alice.account_id());
+    let tier_a = progress.tiers.iter().find(|t| t.ship_type == 4).unwrap();
+    assert_eq!(tier_a.sub_types_held, 2);
+    assert_eq!(tier_a.sub_type_count, 4);
+    assert!(progress.completed_tiers.is_empty());
+
+    let outcome = e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["4".to_string(), "4".to_string()],
+        vec!["3".to_string(), "4".to_string()],
+    );
+    outcome.assert_success();
+    assert_eq!(
+        get_logs(&outcome)
+            .iter()
+            .filter(|log| log.contains(r#""event":"collection_completed""#))
+            .count(),
+        1
+    );
+    assert_eq!(
+        e.get_collection_progress(alice.account_id())
+            .completed_tiers,
+        vec![4]
+    );
+
+    // a duplicate subtype leaving keeps the set complete, the last one breaks it
+    let ships = e.get_ships_for_owner(
+        alice.account_id(),
+        Some(ShipFilter {
+            ship_type: Some(4),
+            ship_sub_type: Some(2),
+            ..Default::default()
+        }),
+        None,
+        None,
+    );
+    e.nft_transfer(&alice, bob.account_id(), ships[0].to_token_id())
+        .assert_success();
+    assert_eq!(
+        e.get_collection_progress(alice.account_id())
+            .completed_tiers,
+        vec![4]
+    );
+    e.nft_transfer(&alice, bob.account_id(), ships[1].to_token_id())
+        .assert_success();
+    let progress = e.get_collection_progress(alice.account_id());
+    assert!(progress.completed_tiers.is_empty());
+    assert_eq!(
+        progress
+            .tiers
+            .iter()
+            .find(|t| t.ship_type == 4)
+            .unwrap()
+            .sub_types_held,
+        3
+    );
+}
+
+#[test]
+fn nft_storage_deposit() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+    let storage_cost = |bytes: u64| bytes as u128 * near_sdk::env::STORAGE_PRICE_PER_BYTE;
+
+    e.set_nft_storage_required(&e.owner, true).assert_success();
+    assert_err!(
+        e.batch_mint(
+            &e.magicbox,
+            alice.account_id(),
+            vec!["1".to_string()],
+            vec!["1".to_string()],
+        ),
+        "ERR_INSUFFICIENT_NFT_STORAGE"
+    );
+
+    // a deposit pays for the entry it creates, whoever it is made for
+    assert_err!(
+        e.nft_storage_deposit(&bob, Some(alice.account_id()), 1),
+        "ERR_DEPOSIT_BELOW_REGISTRATION"
+    );
+    assert!(e.get_nft_storage_balance_of(alice.account_id()).is_none());
+
+    // magicbox passes the deposit through
+    e.batch_mint_with_deposit(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string()],
+        vec!["1".to_string()],
+        to_yocto("0.1"),
+    )
+    .assert_success();
+    let storage = e.get_nft_storage_balance_of(alice.account_id()).unwrap();
+    assert_eq!(storage.total, U128(to_yocto("0.1")));
+    assert!(storage.used_bytes > 0);
+    assert!(storage.available.0 < to_yocto("0.1"));
+
+    e.nft_storage_deposit(&alice, None, to_yocto("0.1"))
+        .assert_success();
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string()],
+        vec!["2".to_string()],
+    )
+    .assert_success();
+    assert_err!(
+        e.nft_storage_withdraw(&alice, Some(U128(to_yocto("0.2")))),
+        "ERR_NOT_ENOUGH_STORAGE_BALANCE"
+    );
+    let minted_bytes = e
+        .get_nft_storage_balance_of(alice.account_id())
+        .unwrap()
+        .used_bytes;
+
+    // the upgraded ship is charged on its own, shipmarket passes a deposit through
+    let ships = e.get_spaceship_list_for_owner(alice.account_id(), None, None);
+    let outcome = e.shipmarket.user_account.function_call(
+        e.spaceship.contract.upgrade_spaceship(
+            alice.account_id(),
+            ships[0].to_token_id(),
+            ships[1].to_token_id(),
+            1,
+            U128(0),
+        ),
+        MAX_GAS.0,
+        to_yocto("0.01") + 1,
+    );
+    assert!(outcome.unwrap_json::<bool>());
+    let storage = e.get_nft_storage_balance_of(alice.account_id()).unwrap();
+    assert_eq!(storage.total, U128(to_yocto("0.21")));
+    assert!(storage.used_bytes < minted_bytes);
+    let upgraded = e.get_spaceship_list_for_owner(alice.account_id(), None, None)[0].to_token_id();
+
+    // the receiver of a transfer pays for the ship from then on
+    assert_err!(
+        e.nft_transfer(&alice, bob.account_id(), upgraded.clone()),
+        "ERR_INSUFFICIENT_NFT_STORAGE"
+    );
+    e.nft_storage_deposit(&bob, None, to_yocto("0.1"))
+        .assert_success();
+    let bob_registered = e
+        .get_nft_storage_balance_of(bob.account_id())
+        .unwrap()
+        .used_bytes;
+    e.nft_transfer(&alice, bob.account_id(), upgraded.clone())
+        .assert_success();
+    assert!(
+        e.get_nft_storage_balance_of(bob.account_id())
+            .unwrap()
+            .used_bytes
+            > bob_registered
+    );
+
+    // burning gives the storage back, the entry itself stays paid
+    e.user_burn(&bob, upgraded).assert_success();
+    let bob_storage = e.get_nft_storage_balance_of(bob.account_id()).unwrap();
+    assert_eq!(bob_storage.used_bytes, bob_registered);
+    let storage = e.get_nft_storage_balance_of(alice.account_id()).unwrap();
+    assert!(storage.used_bytes > 0);
+    let registered = storage_cost(storage.used_bytes);
+    assert_eq!(storage.available, U128(to_yocto("0.21") - registered));
+
+    let balance = alice.account().unwrap().amount;
+    e.nft_storage_withdraw(&alice, None).assert_success();
+    assert!(alice.account().unwrap().amount > balance + to_yocto("0.2") - registered);
+    assert_eq!(
+        e.get_nft_storage_balance_of(alice.account_id())
+            .unwrap()
+            .total,
+        U128(registered)
+    );
+}
+
+#[test]
+fn ship_lock() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let alice = e
+        .root
+        .create_user("alice".parse().unwrap(), to_yocto("100"));
+    let bob = e.root.create_user("bob".parse().unwrap(), to_yocto("100"));
+
+    e.batch_mint(
+        &e.magicbox,
+        alice.account_id(),
+        vec!["1".to_string()],
+        vec!["1".to_string()],
+    )
+    .assert_success();
+    let token_id = e.get_spaceship_list_for_owner(alice.account_id(), None, None)[0].to_token_id();
+
+    assert_err!(
+        e.lock(
+            &alice,
+            token_id.clone(),
+            e.shippool.account_id(),
+            "0".to_string()
+        ),
+        "ERR_NOT_STAKING_POOL"
+    );
+    e.add_staking_pool(&e.owner, e.shippool.account_id())
+        .assert_success();
+    assert_err!(
+        e.lock(
+            &bob,
+            token_id.clone(),
+            e.shippool.account_id(),
+            "0".to_string()
+        ),
+        "ERR_NOT_NFT_OWNER"
+    );
+
+    // a pool refusing the ship releases the lock
+    let outcome = e.lock(
+        &alice,
+        token_id.clone(),
+        e.shippool.account_id(),
+        "reject".to_string(),
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_unlocked""#)));
+    assert_eq!(e.get_ship_lock(token_id.clone()), None);
+
+    // a locked ship stays with its owner but can not move
+    let outcome = e.lock(
+        &alice,
+        token_id.clone(),
+        e.shippool.account_id(),
+        "0".to_string(),
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_locked""#)));
+    assert_eq!(
+        e.get_ship_lock(token_id.clone()),
+        Some(ShipLock {
+            pool_id: e.shippool.account_id(),
+            locked_by: alice.account_id(),
+        })
+    );
+    assert_eq!(
+        e.get_nft_token(token_id.clone()).unwrap().owner_id,
+        alice.account_id()
+    );
+    assert_err!(
+        e.nft_transfer(&alice, bob.account_id(), token_id.clone()),
+        "ERR_SHIP_LOCKED"
+    );
+    assert_err!(e.user_burn(&alice, token_id.clone()), "ERR_SHIP_LOCKED");
+
+    // the owner can not release a ship the pool still keeps staked
+    let outcome = e.unlock(&alice, token_id.clone());
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains("ERR_NOT_LOCKING_POOL")));
+    assert!(e.get_ship_lock(token_id.clone()).is_some());
+    e.unlock(&e.shippool.user_account, token_id.clone())
+        .assert_success();
+    assert_eq!(e.get_ship_lock(token_id.clone()), None);
+    e.nft_transfer(&alice, bob.account_id(), token_id.clone())
+        .assert_success();
+
+    // once the pool dropped the ship, e.g. after its unlock call failed, the owner releases it
+    e.lock(
+        &bob,
+        token_id.clone(),
+        e.shippool.account_id(),
+        "0".to_string(),
+    )
+    .assert_success();
+    e.root
+        .function_call(
+            e.shippool.contract.release_ship(token_id.clone()),
+            MAX_GAS.0,
+            0,
+        )
+        .assert_success();
+    let outcome = e.unlock(&bob, token_id.clone());
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_unlocked""#)));
+    assert_eq!(e.get_ship_lock(token_id.clone()), None);
+
+    // a ship locked into a pool that is no longer a staking pool can be released by its owner
+    e.lock(
+        &bob,
+        token_id.clone(),
+        e.shippool.account_id(),
+        "0".to_string(),
+    )
+    .assert_success();
+    e.remove_staking_pool(&e.owner, e.shippool.account_id())
+        .assert_success();
+    assert_err!(e.unlock(&alice, token_id.clone()), "ERR_NOT_ALLOWED");
+    let outcome = e.unlock(&bob, token_id.clone());
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_unlocked""#)));
+    assert_eq!(e.get_ship_lock(token_id.clone()), None);
+}
@@ -52,3 +52,103 @@ fn set_ship_icon() {
     );
     assert_eq!(e.get_ship_icon(String::from("2:2")), None);
 }
+
+#[test]
+fn timelock_actions() {
+    let e = Env::init_with_contract(spaceship_wasm_bytes());
+    let user = e.root.create_user("user".parse().unwrap(), to_yocto("100"));
+
+    assert_err!(
+        e.set_spaceship_type_capacity(&user, 1, 100, 200),
+        "ERR_NOT_ALLOWED"
+    );
+    let outcome = e.set_spaceship_type_capacity(&e.owner, 1, 100, 200);
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"action_queued""#)));
+    let action_id: u64 = outcome.unwrap_json();
+    let pending = e.get_pending_actions();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(
+        pending[0].action,
+        TimelockAction::SetSpaceshipTypeCapacity {
+            ship_type: 1,
+            min: 100,
+            max: 200
+        }
+    );
+    assert_eq!(pending[0].eta, e.current_time() + e.get_timelock_delay());
+
+    // nothing changes before the eta
+    assert_err!(
+        e.execute_action(&e.owner, action_id),
+        "ERR_ACTION_NOT_READY"
+    );
+    e.skip_time(e.get_timelock_delay());
+    assert_err!(e.execute_action(&user, action_id), "ERR_NOT_ALLOWED");
+    let outcome = e.execute_action(&e.owner, action_id);
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"action_executed""#)));
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"ship_tier_updated""#)));
+    assert_eq!(
+        e.get_ship_tier(1).unwrap().capacity,
+        CapacityRange { min: 100, max: 200 }
+    );
+    // the new capacity is pushed to the contracts keeping a copy of the tiers
+    let receipts = format!("{:?}", outcome.promise_results());
+    assert!(
+        receipts.contains(MAGICBOX_ID)
+            && receipts.contains(SHIPMARKET_ID)
+            && receipts.contains(COLLECTPOOL_ID)
+    );
+    assert!(e.get_pending_actions().is_empty());
+
+    // tier, royalty, level and ENG settings wait for the delay as well
+    let outcome = e.owner.function_call(
+        e.spaceship.contract.set_ship_levels(vec![ShipLevel {
+            xp: 1,
+            capacity_bonus: 1,
+        }]),
+        MAX_GAS.0,
+        1,
+    );
+    let action_id: u64 = outcome.unwrap_json();
+    assert_ne!(e.get_ship_levels().len(), 1);
+    assert_err!(
+        e.execute_action(&e.owner, action_id),
+        "ERR_ACTION_NOT_READY"
+    );
+    e.skip_time(e.get_timelock_delay());
+    e.execute_action(&e.owner, action_id).assert_success();
+    assert_eq!(e.get_ship_levels().len(), 1);
+
+    // a cancelled action can not run
+    let action_id: u64 = e.queue_mint_eng(&e.owner, U128(100)).unwrap_json();
+    let outcome = e.cancel_action(&e.owner, action_id);
+    outcome.assert_success();
+    assert!(get_logs(&outcome)
+        .iter()
+        .any(|log| log.contains(r#""event":"action_cancelled""#)));
+    e.skip_time(e.get_timelock_delay());
+    assert_err!(
+        e.execute_action(&e.owner, action_id),
+        "ERR_ACTION_NOT_FOUND"
+    );
+    assert_eq!(e.get_eng_balance_of(e.owner.account_id()), U128(0));
+
+    // the delay can not be turned off
+    assert_err!(
+        e.set_timelock_delay(&e.owner, 0),
+        "ERR_TIMELOCK_DELAY_TOO_SHORT"
+    );
+
+    // the delay itself is timelocked
+    e.execute_after_timelock(&e.owner, e.set_timelock_delay(&e.owner, 3600))
+        .assert_success();
+    assert_eq!(e.get_timelock_delay(), 3600);
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use near_contract_standards::fungible_token::events::{FtBurn, FtMint};
+use near_contract_standards::fungible_token::metadata::{
+    FungibleTokenMetadata, FungibleTokenMetadataProvider, FT_METADATA_SPEC
+};
+use near_contract_standards::fungible_token::FungibleToken;
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::UnorderedSet;
+use near_sdk::json_types::U128;
+use near_sdk::{
+    env, near_bindgen, assert_one_yocto, require, AccountId,
+    PanicOnDefault, PromiseOrValue
+};
+use near_sdk::serde::{Deserialize, Serialize};
+
+mod owner;
+mod view;
+
+pub use crate::view::*;
+
+pub const MAX_ICON_LENGTH: usize = 2048;
+
+#[near_bindgen]
+#[derive(BorshSerialize, BorshDeserialize, PanicOnDefault)]
+pub struct Contract {
+    owner_id: AccountId,
+    token: FungibleToken,
+    icon: Option<String>,
+    // accounts allowed to mint ENG to users, e.g. spaceship for burn rewards
+    minters: UnorderedSet<AccountId>,
+    // accounts allowed to burn ENG of users, e.g. shipmarket and luckpool
+    burners: UnorderedSet<AccountId>,
+    // the contract ENG used to live in, allowed to import balances until the migration is closed
+    migrate_from: Option<AccountId>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, migrate_from: Option<AccountId>) -> Self {
+        Self {
+            owner_id,
+            token: FungibleToken::new(b"t".to_vec()),
+            icon: None,
+            minters: UnorderedSet::new(b"m".to_vec()),
+            burners: UnorderedSet::new(b"b".to_vec()),
+            migrate_from,
+        }
+    }
+
+    #[payable]
+    pub fn burn(&mut self, amount: U128) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_burn(&sender_id, amount);
+    }
+
+    /// Arguments:
+    /// - `receiver_ids` - each receivers account ID, an empty string means burn token.
+    /// - `amounts` - the amount of tokens to each receiver_id.
+    /// - `memo` - a string message that was passed with this transfer, will be recorded as log
+    #[payable]
+    pub fn batch_transfer(
+        &mut self,
+        receiver_ids: Vec<String>,
+        amounts: Vec<U128>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let receivers: HashMap<_, _> = receiver_ids.iter().zip(amounts.iter()).collect();
+        for (receiver, amount) in receivers {
+            if amount.0 == 0_u128 {
+                continue
+            }
+            if receiver.is_empty() {
+                // burn token
+                self.internal_burn(&sender_id, *amount);
+            } else {
+                let receiver_id: AccountId = receiver.parse().expect("ERR_INVALID_RECEIVER_ID");
+                self.token.internal_transfer(&sender_id, &receiver_id, amount.0, memo.clone());
+            }
+        }
+    }
+
+    /// minter mints ENG to `receiver_id`,
+    /// the receiver would be auto-registered if not registered
+    #[payable]
+    pub fn mint(&mut self, receiver_id: AccountId, amount: U128) {
+        assert_one_yocto();
+        require!(self.minters.contains(&env::predecessor_account_id()), "ERR_NOT_MINTER");
+        self.internal_mint(&receiver_id, amount, None);
+    }
+
+    /// burner burns ENG of `account_id`, replaces spaceship's `burn_eng_for_user`
+    #[payable]
+    pub fn burn_from(&mut self, account_id: AccountId, amount: U128) {
+        assert_one_yocto();
+        require!(self.burners.contains(&env::predecessor_account_id()), "ERR_NOT_BURNER");
+        self.internal_burn(&account_id, amount);
+    }
+
+    /// called by `migrate_from` with the balances it moved out of its own state
+    #[payable]
+    pub fn migrate_balances(&mut self, account_ids: Vec<AccountId>, amounts: Vec<U128>) {
+        assert_one_yocto();
+        require!(
+            Some(env::predecessor_account_id()) == self.migrate_from,
+            "ERR_NOT_ALLOWED"
+        );
+        require!(account_ids.len() == amounts.len(), "ERR_INVALID_ARGS");
+        for (account_id, amount) in account_ids.iter().zip(amounts) {
+            self.internal_mint(account_id, amount, Some("migrate"));
+        }
+    }
+}
+
+impl Contract {
+    pub fn internal_mint(&mut self, receiver_id: &AccountId, amount: U128, memo: Option<&str>) {
+        if self.token.storage_balance_of(receiver_id.clone()).is_none() {
+            self.token.internal_register_account(receiver_id);
+        }
+        self.token.internal_deposit(receiver_id, amount.into());
+        FtMint { owner_id: receiver_id, amount: &amount, memo }.emit();
+    }
+
+    pub fn internal_burn(&mut self, account_id: &AccountId, amount: U128) {
+        self.token.internal_withdraw(account_id, amount.into());
+        FtBurn { owner_id: account_id, amount: &amount, memo: None }.emit();
+    }
+}
+
+near_contract_standards::impl_fungible_token_core!(Contract, token);
+near_contract_standards::impl_fungible_token_storage!(Contract, token);
+
+#[near_bindgen]
+impl FungibleTokenMetadataProvider for Contract {
+    fn ft_metadata(&self) -> FungibleTokenMetadata {
+        FungibleTokenMetadata {
+            spec: FT_METADATA_SPEC.to_string(),
+            name: "Engine".to_string(),
+            symbol: "ENG".to_string(),
+            icon: self.icon.clone(),
+            reference: None,
+            reference_hash: None,
+            decimals: 18,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    #[test]
+    fn test_roles() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let mut contract = Contract::new(accounts(0), Some(accounts(3)));
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .build());
+        contract.add_minter(accounts(1));
+        contract.add_burner(accounts(2));
+        assert_eq!(contract.get_minters(), vec![accounts(1)]);
+        assert_eq!(contract.get_burners(), vec![accounts(2)]);
+
+        testing_env!(context.predecessor_account_id(accounts(1)).build());
+        contract.mint(accounts(4), 1_000.into());
+        assert_eq!(contract.ft_balance_of(accounts(4)), 1_000.into());
+
+        testing_env!(context.predecessor_account_id(accounts(2)).build());
+        contract.burn_from(accounts(4), 400.into());
+        assert_eq!(contract.ft_balance_of(accounts(4)), 600.into());
+
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.migrate_balances(vec![accounts(4), accounts(5)], vec![100.into(), 200.into()]);
+        assert_eq!(contract.ft_balance_of(accounts(4)), 700.into());
+        assert_eq!(contract.ft_balance_of(accounts(5)), 200.into());
+        assert_eq!(contract.ft_total_supply(), 900.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_MINTER")]
+    fn test_mint_without_role() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let mut contract = Contract::new(accounts(0), None);
+        testing_env!(context
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(1)
+            .build());
+        contract.mint(accounts(1), 1_000.into());
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ALLOWED")]
+    fn test_migrate_after_closed() {
+        let mut context = VMContextBuilder::new();
+        testing_env!(context.build());
+        let mut contract = Contract::new(accounts(0), Some(accounts(3)));
+        testing_env!(context
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(1)
+            .build());
+        contract.close_migration();
+        testing_env!(context.predecessor_account_id(accounts(3)).build());
+        contract.migrate_balances(vec![accounts(4)], vec![100.into()]);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::*;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Metadata {
+    pub version: String,
+    pub owner_id: AccountId,
+    pub migrate_from: Option<AccountId>,
+}
+
+#[near_bindgen]
+impl Contract {
+    //******** Contract Concern */
+    pub fn get_metadata(&self) -> Metadata {
+        Metadata {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            owner_id: self.owner_id.clone(),
+            migrate_from: self.migrate_from.clone(),
+        }
+    }
+
+    pub fn get_minters(&self) -> Vec<AccountId> {
+        self.minters.to_vec()
+    }
+
+    pub fn get_burners(&self) -> Vec<AccountId> {
+        self.burners.to_vec()
+    }
+}
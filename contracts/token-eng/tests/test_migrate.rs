@@ -0,0 +1,54 @@
+mod setup;
+use crate::setup::*;
+
+#[test]
+fn test_update(){
+    let e = Env::init_with_contract(previous_wasm_bytes());
+    let users = Users::init(&e);
+
+    assert_err!(
+        e.upgrade_contract(&users.alice, cur_wasm_bytes()),
+        "ERR_NOT_ALLOWED"
+    );
+
+    e.upgrade_contract(&e.owner, cur_wasm_bytes()).assert_success();
+    assert_eq!(e.get_metadata().version, "0.0.1".to_string());
+}
+
+#[test]
+fn test_migrate_balances(){
+    let e = init_env();
+    let users = Users::init(&e);
+    assert_eq!(e.get_metadata().migrate_from, Some(e.spaceship.account_id()));
+
+    assert_err!(
+        e.migrate_balances(&users.alice, vec![users.alice.account_id()], vec![U128(100)]),
+        "ERR_NOT_ALLOWED"
+    );
+    assert_err!(
+        e.migrate_balances(&e.spaceship, vec![users.alice.account_id()], vec![]),
+        "ERR_INVALID_ARGS"
+    );
+
+    // unregistered accounts are registered on import
+    let outcome = e.migrate_balances(
+        &e.spaceship,
+        vec![users.alice.account_id(), users.bob.account_id()],
+        vec![U128(100), U128(200)]
+    );
+    outcome.assert_success();
+    assert!(get_logs(&outcome).iter().any(|log| log.contains(r#""event":"ft_mint""#) && log.contains(r#""memo":"migrate""#)));
+    assert_eq!(e.ft_balance_of(users.alice.account_id()), U128(100));
+    assert_eq!(e.ft_balance_of(users.bob.account_id()), U128(200));
+    assert_eq!(e.ft_total_supply(), U128(300));
+
+    // no more imports once the migration is closed
+    e.close_migration(&e.owner).assert_success();
+    assert_eq!(e.get_metadata().migrate_from, None);
+    assert_err!(
+        e.migrate_balances(&e.spaceship, vec![users.charlie.account_id()], vec![U128(100)]),
+        "ERR_NOT_ALLOWED"
+    );
+    assert_eq!(e.ft_balance_of(users.charlie.account_id()), U128(0));
+    assert_eq!(e.ft_total_supply(), U128(300));
+}
@@ -8,7 +8,7 @@ use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, UnorderedSet, LookupMap, UnorderedMap, Vector};
+use near_sdk::collections::{LazyOption, UnorderedSet, LookupMap, LookupSet, UnorderedMap, Vector};
 use near_sdk::json_types::U128;
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
@@ -25,6 +25,7 @@ pub const TGAS: u64 = 1_000_000_000_000;
 pub const GAS_FOR_TRANSFER: Gas = Gas(25* TGAS);
 pub const GAS_FOR_NFT_TRANSFER: Gas = Gas(25* TGAS);
 pub const GAS_FOR_TRANSFER_ON_CALL: Gas = Gas(45 * TGAS);
+pub const GAS_FOR_ADD_SHIP_XP: Gas = Gas(30 * TGAS);
 
 pub const YOCTO18: u128 = 1_000_000_000_000_000_000;
 pub const CALC_PRECISION: u128 = 1_000_000_000_000;
@@ -75,6 +76,12 @@ pub(crate) enum StorageKey {
     Rewards,
     SlotInfo,
     CapacityInfo,
+    ShipOwner,
+    StakeTime,
+    LockedShip,
+    ShipStaker,
+    ShipCapacity,
+    StakedCapacity,
 }
 
 #[ext_contract(ext_nft)]
@@ -88,6 +95,12 @@ trait NonFungibleToken {
     );
 }
 
+#[ext_contract(ext_spaceship)]
+trait SpaceShip {
+    fn add_ship_xp(&mut self, token_id: TokenId, staked_seconds: u64);
+    fn unlock(&mut self, token_id: TokenId);
+}
+
 #[ext_contract(ext_boxmall)]
 trait boxmall{
     fn ship_wallet_add( &mut self, to: AccountId, amount: U128 );
@@ -131,6 +144,13 @@ pub struct Contract {
     decay_table: Vec<u128>, // mapping(uint256 => uint256)
 
     balance: Balance,
+
+    ship_owners: LookupMap<TokenId, AccountId>, // rented ship => its owner, the ship goes back there on withdraw
+    stake_times: LookupMap<TokenId, u64>, // staked ship => timestamp in seconds it was staked, reported to spaceship as xp
+    locked_ships: LookupSet<TokenId>, // ships staked by a spaceship lock, they stay with their owner
+    ship_stakers: LookupMap<TokenId, AccountId>, // rented ship => the user who staked it, its slot is freed when the rental expires
+    ship_capacity: LookupMap<TokenId, u32>, // ship => capacity raised by its level, pushed by spaceship
+    staked_capacity: LookupMap<TokenId, u32>, // staked ship => capacity it counts with, taken back on withdraw
 }
 
 #[near_bindgen]
@@ -167,6 +187,13 @@ impl Contract {
             decay_table: vec![0;DEFAULT_TOTAL_PERIOD as usize],
 
             balance: 0,
+
+            ship_owners: LookupMap::new(StorageKey::ShipOwner),
+            stake_times: LookupMap::new(StorageKey::StakeTime),
+            locked_ships: LookupSet::new(StorageKey::LockedShip),
+            ship_stakers: LookupMap::new(StorageKey::ShipStaker),
+            ship_capacity: LookupMap::new(StorageKey::ShipCapacity),
+            staked_capacity: LookupMap::new(StorageKey::StakedCapacity),
         };
         this.internal_init_decay_table();
         this.internal_init_slot();
@@ -177,34 +204,7 @@ impl Contract {
     #[payable]
     pub fn withdraw( &mut self, slot_index: u64) {
         assert_one_yocto();
-        let predecessor_id = env::predecessor_account_id();
-        self.internal_check_start();
-        self.internal_update_reward(predecessor_id.clone());
-
-        let mut stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&predecessor_id).expect("No_StakeSlot");
-
-        let mut withdraw_slot: StakeSlot = stake_slot_vec[slot_index as usize].clone();
-        require!(withdraw_slot.token_id != "".to_string(), "No ship can withdraw");
-
-        ext_nft::nft_transfer(
-            predecessor_id.clone(), 
-            withdraw_slot.token_id.clone(),
-            None,
-            None,
-            self.spaceship.clone(),
-            1,
-            GAS_FOR_NFT_TRANSFER
-        );
-
-        let mut capacity = self.capacity_info.get(&predecessor_id.clone()).unwrap_or(0);
-        let ship_capacity = self.internal_get_ship_capacity_by_token_id(withdraw_slot.token_id.clone());
-        capacity -= ship_capacity as u32;
-        self.capacity_info.insert(&predecessor_id.clone(), &capacity);
-        self.total_capacity -= ship_capacity as u32;       
-        withdraw_slot.token_id = "".to_string();
-        stake_slot_vec[slot_index as usize] = withdraw_slot;
-
-        self.slot_info.insert(&predecessor_id,&stake_slot_vec);
+        self.internal_withdraw(env::predecessor_account_id(), slot_index, true);
     }
 
     #[payable]
@@ -269,6 +269,52 @@ impl FungibleTokenReceiver for Contract {
     }
 }
 
+#[near_bindgen]
+impl Contract {
+    /// Callback on a ship locked into this pool by spaceship, it is staked without being transferred.
+    pub fn nft_on_lock(
+        &mut self,
+        sender_id: AccountId,
+        owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        let predecessor_id = env::predecessor_account_id();
+        require!(predecessor_id == self.spaceship, "Invalid contract Id");
+
+        let slot_index: SlotIndex = msg.parse::<SlotIndex>().expect("msg must contain all digits");
+        log!("{} locks ship {} of {}", sender_id, token_id, owner_id);
+
+        self.locked_ships.insert(&token_id);
+        if sender_id != owner_id {
+            self.ship_stakers.insert(&token_id, &sender_id);
+        }
+        self.stake_times.insert(&token_id, &(env::block_timestamp() / 10u64.pow(9)));
+
+        self.internal_stake(sender_id, slot_index, token_id.clone());
+
+        PromiseOrValue::Value(true)
+    }
+
+    /// Callback on a rented ship spaceship gave back to its owner after the rental expired,
+    /// the user's slot is freed without sending the ship.
+    pub fn nft_on_rental_expired(&mut self, token_id: TokenId) {
+        require!(env::predecessor_account_id() == self.spaceship, "Invalid contract Id");
+        if let Some(staker_id) = self.ship_stakers.get(&token_id) {
+            let stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&staker_id).expect("No_StakeSlot");
+            if let Some(slot_index) = stake_slot_vec.iter().position(|slot| slot.token_id == token_id) {
+                self.internal_withdraw(staker_id, slot_index as u64, false);
+            }
+        }
+    }
+
+    /// Callback on a ship leveling up in spaceship, it stakes with the raised capacity from then on
+    pub fn on_ship_level_up(&mut self, token_id: TokenId, effective_capacity: u32) {
+        require!(env::predecessor_account_id() == self.spaceship, "Invalid contract Id");
+        self.ship_capacity.insert(&token_id, &effective_capacity);
+    }
+}
+
 #[near_bindgen]
 impl NonFungibleTokenReceiver for Contract {
     /// Callback on receiving NFT tokens by this contract.
@@ -285,12 +331,76 @@ impl NonFungibleTokenReceiver for Contract {
         require!( predecessor_id == self.spaceship, "Invalid contract Id");
 
         let slot_index: SlotIndex = msg.parse::<SlotIndex>().expect("msg must contain all digits");
+
+        // a rented ship staked by its user goes back to the owner on withdraw
+        if sender_id != previous_owner_id {
+            self.ship_owners.insert(&token_id, &previous_owner_id);
+            self.ship_stakers.insert(&token_id, &sender_id);
+        }
+        self.stake_times.insert(&token_id, &(env::block_timestamp() / 10u64.pow(9)));
+
         self.internal_stake(sender_id, slot_index, token_id.clone());
         PromiseOrValue::Value(false)
     }
 }
 
 impl Contract{
+    /// frees the slot, `release_ship` false when the ship already left the pool
+    pub fn internal_withdraw( &mut self, account_id: AccountId, slot_index: u64, release_ship: bool) {
+        self.internal_check_start();
+        self.internal_update_reward(account_id.clone());
+
+        let mut stake_slot_vec: Vec<StakeSlot> = self.slot_info.get(&account_id).expect("No_StakeSlot");
+
+        let mut withdraw_slot: StakeSlot = stake_slot_vec[slot_index as usize].clone();
+        require!(withdraw_slot.token_id != "".to_string(), "No ship can withdraw");
+
+        self.ship_stakers.remove(&withdraw_slot.token_id);
+        if !release_ship {
+            // spaceship already gave the ship back to its owner
+            self.locked_ships.remove(&withdraw_slot.token_id);
+            self.ship_owners.remove(&withdraw_slot.token_id);
+        } else if self.locked_ships.remove(&withdraw_slot.token_id) {
+            ext_spaceship::unlock(
+                withdraw_slot.token_id.clone(),
+                self.spaceship.clone(),
+                1,
+                GAS_FOR_NFT_TRANSFER
+            );
+        } else {
+            let receiver_id = self.ship_owners.remove(&withdraw_slot.token_id).unwrap_or(account_id.clone());
+            ext_nft::nft_transfer(
+                receiver_id,
+                withdraw_slot.token_id.clone(),
+                None,
+                None,
+                self.spaceship.clone(),
+                1,
+                GAS_FOR_NFT_TRANSFER
+            );
+        }
+
+        if let Some(staked_at) = self.stake_times.remove(&withdraw_slot.token_id) {
+            ext_spaceship::add_ship_xp(
+                withdraw_slot.token_id.clone(),
+                env::block_timestamp() / 10u64.pow(9) - staked_at,
+                self.spaceship.clone(),
+                0,
+                GAS_FOR_ADD_SHIP_XP
+            );
+        }
+
+        let mut capacity = self.capacity_info.get(&account_id.clone()).unwrap_or(0);
+        let ship_capacity = self.internal_take_staked_capacity(&withdraw_slot.token_id);
+        capacity -= ship_capacity;
+        self.capacity_info.insert(&account_id.clone(), &capacity);
+        self.total_capacity -= ship_capacity;       
+        withdraw_slot.token_id = "".to_string();
+        stake_slot_vec[slot_index as usize] = withdraw_slot;
+
+        self.slot_info.insert(&account_id,&stake_slot_vec);
+    }
+
     pub fn internal_buy_slot( &mut self, sender_id: AccountId, amount: u128, slot_index: u64)  {
         let slot_temp: Slot = self.slot.get(slot_index).expect("Invalid slot_index");
         require!(!slot_temp.enable, "Slot is enable");
@@ -333,10 +443,11 @@ impl Contract{
         self.slot_info.insert(&sender_id,& stake_slot_vec);
 
         let mut capacity = self.capacity_info.get(&sender_id).unwrap_or(0);
-        let ship_capacity = self.internal_get_ship_capacity_by_token_id(token_id.clone());
-        capacity += ship_capacity as u32;
+        let ship_capacity = self.internal_ship_capacity(&token_id);
+        self.staked_capacity.insert(&token_id, &ship_capacity);
+        capacity += ship_capacity;
         self.capacity_info.insert(&sender_id, &capacity);
-        self.total_capacity += ship_capacity as u32;
+        self.total_capacity += ship_capacity;
     }
 
 
@@ -402,6 +513,22 @@ impl Contract{
         capacity
     }
 
+    /// capacity a ship stakes with, its level bonus included
+    pub fn internal_ship_capacity(&self, token_id: &TokenId) -> u32 {
+        self.ship_capacity.get(token_id).unwrap_or(self.internal_get_ship_capacity_by_token_id(token_id.clone()) as u32)
+    }
+
+    /// capacity a staked ship counts with, ships staked before levels counted their rolled capacity
+    pub fn internal_staked_capacity(&self, token_id: &TokenId) -> u32 {
+        self.staked_capacity.get(token_id).unwrap_or(self.internal_get_ship_capacity_by_token_id(token_id.clone()) as u32)
+    }
+
+    pub fn internal_take_staked_capacity(&mut self, token_id: &TokenId) -> u32 {
+        let capacity = self.internal_staked_capacity(token_id);
+        self.staked_capacity.remove(token_id);
+        capacity
+    }
+
     pub fn get_last_block_applicable( &self ) -> u64 {
         return cmp::min(env::block_height(), self.end_block);
     }